@@ -2,26 +2,33 @@ use clap::{Args, Parser, Subcommand, ValueEnum};
 use futures::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
 use ochat_common::{
-    data::{Request, RequestType, versions::Versions},
-    load_token, save_token,
+    data::{Request, RequestError, versions::Versions},
+    delete_token, load_token, save_token,
 };
 use ochat_types::{
     WORD_ART,
+    assistants::{Assistant, AssistantDataBuilder},
+    chats::{
+        export::{DatasetFormat, ExportFormat},
+        import::{ImportData, ImportFormat},
+        messages::Role,
+    },
+    errors::ErrorCode,
     generation::text::{
         ChatQueryDataBuilder, ChatQueryMessage, ChatQueryMessageBuilder, ChatStreamResult,
     },
-    providers::{
-        Provider, ProviderData, ProviderDataBuilder, ProviderType,
-        ollama::{OllamaModelsInfo, OllamaPullModelStreamResult},
-    },
-    user::{SigninData, SignupData, Token},
+    providers::{Provider, ProviderDataBuilder, ProviderType, ollama::OllamaPullModelStreamResult},
+    settings::SettingsProvider,
+    trash::TrashKind,
+    user::{ApiTokenData, ApiTokenScope, ChangePasswordData, SigninData, SignupData},
 };
 use rustyline::{DefaultEditor, error::ReadlineError};
-use serde_json::Value;
 use std::{
     env,
     error::Error,
+    fs,
     io::{self, Write},
+    path::PathBuf,
     process::Command,
     time::Duration,
 };
@@ -47,9 +54,18 @@ struct Arguments {
 
 #[derive(Subcommand, Debug, Clone)]
 enum ProviderAction {
-    Run { model: String },
-    Pull { model: String },
-    Rm { model: String },
+    Run {
+        model: String,
+        /// Name or id of an assistant whose system prompt, options and tools to use.
+        #[arg(long)]
+        assistant: Option<String>,
+    },
+    Pull {
+        model: String,
+    },
+    Rm {
+        model: String,
+    },
     List,
 }
 
@@ -58,6 +74,109 @@ enum OllamaAction {
     All { search: Option<String> },
 }
 
+#[derive(Subcommand, Debug, Clone)]
+enum TokenAction {
+    Create {
+        name: String,
+        #[arg(short, long, value_enum, default_value = "full")]
+        scope: ClapTokenScope,
+    },
+    Rm {
+        id: String,
+    },
+    List,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum SessionAction {
+    Rm { id: String },
+    List,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum TrashAction {
+    Restore { kind: ClapTrashKind, id: String },
+    Purge { kind: ClapTrashKind, id: String },
+    Empty,
+    List,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum JobAction {
+    /// Stops a running generation, keeping what it has written so far.
+    Cancel {
+        message: String,
+    },
+    List,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum AssistantAction {
+    /// Chats with the assistant's model using its system prompt, options and tools.
+    Run {
+        name: String,
+    },
+    Add {
+        name: String,
+        /// A `provider:model` pair.
+        model: String,
+        #[arg(long)]
+        avatar: Option<String>,
+        #[arg(long)]
+        system_prompt: Option<String>,
+        /// Id of the generation options to use instead of the model's defaults.
+        #[arg(long)]
+        options: Option<String>,
+        #[arg(long)]
+        tool: Vec<String>,
+        #[arg(long)]
+        starter: Vec<String>,
+    },
+    Rm {
+        id: String,
+    },
+    List,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum ChatAction {
+    Export {
+        id: String,
+        #[arg(short, long, value_enum, default_value = "markdown")]
+        format: ClapExportFormat,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    Import {
+        path: PathBuf,
+        #[arg(short, long, value_enum, default_value = "auto")]
+        format: ClapImportFormat,
+        #[arg(long)]
+        folder: Option<String>,
+    },
+    /// Builds a JSONL fine-tuning dataset from the rated messages of every chat.
+    Dataset {
+        #[arg(value_enum)]
+        format: ClapDatasetFormat,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Shows the chat's configuration, or updates any of the given fields.
+    Config {
+        id: String,
+        /// A `provider:model` pair, repeat to compare several models.
+        #[arg(long)]
+        model: Vec<String>,
+        #[arg(long)]
+        tool: Vec<String>,
+        #[arg(long)]
+        system_prompt: Option<String>,
+        /// Id of the generation options to use instead of each model's defaults.
+        #[arg(long)]
+        options: Option<String>,
+    },
+}
+
 #[derive(Args, Debug, Clone)]
 struct ProviderArgs {
     id: String,
@@ -77,6 +196,45 @@ enum Action {
         url: String,
         r#type: ClapProviderType,
     },
+    Token {
+        #[command(subcommand)]
+        action: TokenAction,
+    },
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+    },
+    Chat {
+        #[command(subcommand)]
+        action: ChatAction,
+    },
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    Job {
+        #[command(subcommand)]
+        action: JobAction,
+    },
+    Assistant {
+        #[command(subcommand)]
+        action: AssistantAction,
+    },
+    Password {
+        old_password: String,
+        new_password: String,
+    },
+    Refresh,
+    Logout,
+    Gc {
+        #[arg(long)]
+        dry_run: bool,
+    },
+    Migrations {
+        action: ClapMigrationAction,
+        #[arg(long)]
+        server_args: Option<Vec<String>>,
+    },
     SignIn {
         name: String,
         password: String,
@@ -118,6 +276,110 @@ impl Into<ProviderType> for ClapProviderType {
     }
 }
 
+#[derive(ValueEnum, Debug, Clone)]
+enum ClapMigrationAction {
+    Status,
+    DryRun,
+    Run,
+}
+
+impl ClapMigrationAction {
+    fn as_arg(&self) -> &'static str {
+        match self {
+            Self::Status => "status",
+            Self::DryRun => "dry-run",
+            Self::Run => "run",
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+enum ClapTokenScope {
+    Full,
+    ReadOnly,
+    Generation,
+}
+
+impl Into<ApiTokenScope> for ClapTokenScope {
+    fn into(self) -> ApiTokenScope {
+        match self {
+            Self::Full => ApiTokenScope::Full,
+            Self::ReadOnly => ApiTokenScope::ReadOnly,
+            Self::Generation => ApiTokenScope::Generation,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+enum ClapExportFormat {
+    Json,
+    Markdown,
+    Html,
+}
+
+impl Into<ExportFormat> for ClapExportFormat {
+    fn into(self) -> ExportFormat {
+        match self {
+            Self::Json => ExportFormat::Json,
+            Self::Markdown => ExportFormat::Markdown,
+            Self::Html => ExportFormat::Html,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+enum ClapDatasetFormat {
+    Dpo,
+    Sft,
+}
+
+impl Into<DatasetFormat> for ClapDatasetFormat {
+    fn into(self) -> DatasetFormat {
+        match self {
+            Self::Dpo => DatasetFormat::Dpo,
+            Self::Sft => DatasetFormat::Sft,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+enum ClapImportFormat {
+    Auto,
+    #[value(name = "chatgpt")]
+    ChatGPT,
+    #[value(name = "openwebui")]
+    OpenWebUI,
+    Ochat,
+}
+
+impl Into<ImportFormat> for ClapImportFormat {
+    fn into(self) -> ImportFormat {
+        match self {
+            Self::Auto => ImportFormat::Auto,
+            Self::ChatGPT => ImportFormat::ChatGPT,
+            Self::OpenWebUI => ImportFormat::OpenWebUI,
+            Self::Ochat => ImportFormat::Ochat,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+enum ClapTrashKind {
+    Chat,
+    Folder,
+    Prompt,
+}
+
+impl Into<TrashKind> for ClapTrashKind {
+    fn into(self) -> TrashKind {
+        match self {
+            Self::Chat => TrashKind::Chat,
+            Self::Folder => TrashKind::Folder,
+            Self::Prompt => TrashKind::Prompt,
+        }
+    }
+}
+
 fn spawn_iced() -> Result<std::process::Child, std::io::Error> {
     match Command::new("ochat-iced").spawn() {
         Ok(x) => Ok(x),
@@ -142,8 +404,42 @@ fn spawn_server(url: String) -> Result<std::process::Child, std::io::Error> {
     }
 }
 
+fn run_server_migrations(
+    action: ClapMigrationAction,
+    args: Vec<String>,
+) -> Result<(), std::io::Error> {
+    match Command::new("ochat-server")
+        .arg("--migrations")
+        .arg(action.as_arg())
+        .args(args)
+        .spawn()
+    {
+        Ok(mut x) => {
+            let status = x.wait()?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(std::io::Error::other(format!(
+                    "The migrations did not finish, the server exited with {}.",
+                    status
+                )))
+            }
+        }
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                eprintln!("Consider using 'ochat install --server' to install the server.")
+            }
+            Err(e)
+        }
+    }
+}
+
 async fn run_action(req: &Request, action: Action) -> Result<(), Box<dyn Error>> {
     match action {
+        Action::Migrations {
+            action,
+            server_args,
+        } => run_server_migrations(action, server_args.unwrap_or_default())?,
         Action::Install {
             server,
             server_args,
@@ -204,14 +500,10 @@ async fn run_action(req: &Request, action: Action) -> Result<(), Box<dyn Error>>
         }
         Action::SignIn { name, password } => {
             match req
-                .make_request::<Token, SigninData>(
-                    "signin/",
-                    &SigninData {
-                        name: name.clone(),
-                        password,
-                    },
-                    RequestType::Post,
-                )
+                .signin(&SigninData {
+                    name: name.clone(),
+                    password,
+                })
                 .await
             {
                 Ok(jwt) => {
@@ -227,15 +519,11 @@ async fn run_action(req: &Request, action: Action) -> Result<(), Box<dyn Error>>
             password,
         } => {
             match req
-                .make_request::<Token, SignupData>(
-                    "signup/",
-                    &SignupData {
-                        name: name.clone(),
-                        email,
-                        password,
-                    },
-                    RequestType::Post,
-                )
+                .signup(&SignupData {
+                    name: name.clone(),
+                    email,
+                    password,
+                })
                 .await
             {
                 Ok(jwt) => {
@@ -245,27 +533,378 @@ async fn run_action(req: &Request, action: Action) -> Result<(), Box<dyn Error>>
                 Err(e) => eprintln!("{}", e),
             }
         }
+        Action::Logout => {
+            match req.logout().await {
+                Ok(_) => println!("Logged Out."),
+                Err(e) => eprintln!("{}", e),
+            }
+            delete_token();
+        }
+        Action::Refresh => {
+            let jwt = req.refresh().await?;
+            save_token(&jwt);
+            println!("Session refreshed.");
+        }
+        Action::Password {
+            old_password,
+            new_password,
+        } => {
+            let _ = req
+                .change_password(&ChangePasswordData {
+                    old_password,
+                    new_password,
+                })
+                .await?;
+            println!("Password changed, all other sessions have been signed out.");
+        }
+        Action::Gc { dry_run } => {
+            let report = if dry_run {
+                req.get_garbage().await?
+            } else {
+                req.remove_garbage().await?
+            };
+
+            println!(
+                "{} {} trashed items, {} messages, {} files, {} previews and {} uploads ({} bytes).",
+                if report.dry_run {
+                    "Would remove"
+                } else {
+                    "Removed"
+                },
+                report.trash,
+                report.messages,
+                report.files,
+                report.previews,
+                report.uploads,
+                report.bytes
+            );
+        }
+        Action::Trash { action } => match action {
+            TrashAction::Restore { kind, id } => match req.restore_trash(&kind.into(), &id).await {
+                Ok(Some(x)) => println!("Restored {} '{}'.", x.kind, x.name),
+                Ok(None) => println!("Nothing to restore for {}.", id),
+                Err(e) => return Err(e.into()),
+            },
+            TrashAction::Purge { kind, id } => match req.purge_trash(&kind.into(), &id).await {
+                Ok(Some(x)) => println!("Purged {} '{}'.", x.kind, x.name),
+                Ok(None) => println!("Nothing to purge for {}.", id),
+                Err(e) => return Err(e.into()),
+            },
+            TrashAction::Empty => {
+                let items = req.empty_trash().await?;
+                println!("Purged {} items.", items.len());
+            }
+            TrashAction::List => {
+                let items = req.list_trash().await?;
+
+                let mut table = Builder::new();
+                table.push_record(["kind", "id", "name", "deleted"]);
+
+                for item in items {
+                    table.push_record([
+                        item.kind.to_string(),
+                        item.id,
+                        item.name,
+                        item.deleted_at.0.to_string(),
+                    ]);
+                }
+
+                let mut table = table.build();
+                table.with((Alignment::center(), Style::rounded()));
+                print!("{}", table);
+            }
+        },
+        Action::Job { action } => match action {
+            JobAction::Cancel { message } => {
+                let job = req.cancel_text_job(&message).await?;
+                println!("Cancelled {} on {}.", job.message, job.model);
+            }
+            JobAction::List => {
+                let jobs = req.list_text_jobs().await?;
+
+                let mut table = Builder::new();
+                table.push_record(["message", "provider", "model", "started"]);
+
+                for job in jobs {
+                    table.push_record([
+                        job.message,
+                        job.provider,
+                        job.model,
+                        job.started.0.to_string(),
+                    ]);
+                }
+
+                let mut table = table.build();
+                table.with((Alignment::center(), Style::rounded()));
+                print!("{}", table);
+            }
+        },
+        Action::Assistant { action } => match action {
+            AssistantAction::Run { name } => {
+                let assistant = find_assistant(req, &name).await?;
+                repl(
+                    req,
+                    assistant.model.provider.clone(),
+                    assistant.model.model.clone(),
+                    Some(&assistant),
+                )
+                .await?;
+            }
+            AssistantAction::Add {
+                name,
+                model,
+                avatar,
+                system_prompt,
+                options,
+                tool,
+                starter,
+            } => {
+                let data = AssistantDataBuilder::default()
+                    .name(name)
+                    .model(parse_model(&model)?)
+                    .avatar(avatar)
+                    .system_prompt(system_prompt)
+                    .options(options)
+                    .tools(tool)
+                    .starters(starter)
+                    .build()
+                    .unwrap();
+
+                match req.add_assistant(&data).await? {
+                    Some(x) => println!("Created assistant '{}' ({}).", x.name, x.id.key()),
+                    None => println!("Failed to create assistant '{}'.", data.name),
+                }
+            }
+            AssistantAction::Rm { id } => match req.delete_assistant(&id).await {
+                Ok(Some(x)) => println!("Successfully deleted {}!", x.name),
+                Ok(None) => println!("Failed to delete {}.", id),
+                Err(e) => return Err(e.into()),
+            },
+            AssistantAction::List => {
+                let assistants = req.list_all_assistants().await?;
+
+                let mut table = Builder::new();
+                table.push_record(["id", "name", "mention", "model", "tools"]);
+
+                for assistant in assistants {
+                    table.push_record([
+                        assistant.id.key().to_string(),
+                        assistant.to_string(),
+                        format!("@{}", assistant.mention()),
+                        format!("{}:{}", assistant.model.provider, assistant.model.model),
+                        assistant.tools.join("\n"),
+                    ]);
+                }
+
+                let mut table = table.build();
+                table.with((Alignment::center(), Style::rounded()));
+                print!("{}", table);
+            }
+        },
+        Action::Session { action } => match action {
+            SessionAction::Rm { id } => match req.revoke_session(&id).await {
+                Ok(Some(_)) => println!("Successfully revoked {}!", id),
+                Ok(None) => println!("Failed to revoke {}.", id),
+                Err(e) => return Err(e.into()),
+            },
+            SessionAction::List => {
+                let sessions = req.list_all_sessions().await?;
+
+                let mut table = Builder::new();
+                table.push_record(["id", "device", "signed in", "last used", "current"]);
+
+                for session in sessions {
+                    table.push_record([
+                        session.id.key().to_string(),
+                        session.device.unwrap_or(String::from("unknown")),
+                        session.time.0.to_string(),
+                        session
+                            .last_used
+                            .map(|x| x.0.to_string())
+                            .unwrap_or(String::from("never")),
+                        if session.current { "*" } else { "" }.to_string(),
+                    ]);
+                }
+
+                let mut table = table.build();
+                table.with((Alignment::center(), Style::rounded()));
+                print!("{}", table);
+            }
+        },
+        Action::Chat { action } => match action {
+            ChatAction::Export { id, format, output } => {
+                let export = req.export_chat(&id, &format.into()).await?;
+
+                match output {
+                    Some(path) => {
+                        fs::write(&path, export)?;
+                        println!("Exported {} to '{}'.", id, path.display());
+                    }
+                    None => print!("{}", export),
+                }
+            }
+            ChatAction::Import {
+                path,
+                format,
+                folder,
+            } => {
+                let result = req
+                    .import_chats(&ImportData {
+                        format: format.into(),
+                        folder,
+                        data: serde_json::from_str(&fs::read_to_string(path)?)?,
+                    })
+                    .await?;
+
+                let mut table = Builder::new();
+                table.push_record(["title", "id", "error"]);
+
+                for chat in result.chats {
+                    table.push_record([
+                        chat.title.unwrap_or_default(),
+                        chat.chat
+                            .map(|x| x.id.key().to_string())
+                            .unwrap_or_default(),
+                        chat.error.unwrap_or_default(),
+                    ]);
+                }
+
+                let mut table = table.build();
+                table.with((Alignment::center(), Style::rounded()));
+                print!("{}", table);
+                println!("Imported {}, failed {}.", result.imported, result.failed);
+            }
+            ChatAction::Dataset { format, output } => {
+                let dataset = req.export_dataset(&format.into()).await?;
+
+                match output {
+                    Some(path) => {
+                        fs::write(&path, &dataset)?;
+                        println!(
+                            "Exported {} records to '{}'.",
+                            dataset.lines().count(),
+                            path.display()
+                        );
+                    }
+                    None => print!("{}", dataset),
+                }
+            }
+            ChatAction::Config {
+                id,
+                model,
+                tool,
+                system_prompt,
+                options,
+            } => {
+                let Some(mut config) = req.get_chat_config(&id).await? else {
+                    println!("Chat {} does not exist.", id);
+                    return Ok(());
+                };
+
+                if !model.is_empty() {
+                    config.models = model
+                        .iter()
+                        .map(|x| parse_model(x))
+                        .collect::<Result<Vec<SettingsProvider>, Box<dyn Error>>>()?;
+                }
+
+                if !tool.is_empty() {
+                    config.tools = tool;
+                }
+
+                if let Some(x) = system_prompt {
+                    config.system_prompt = Some(x).filter(|x| !x.is_empty());
+                }
+
+                if let Some(x) = options {
+                    config.options = Some(x).filter(|x| !x.is_empty());
+                }
+
+                if req.set_chat_config(&id, &config).await?.is_none() {
+                    println!("Chat {} does not exist.", id);
+                    return Ok(());
+                }
+
+                let mut table = Builder::new();
+                table.push_record(["models", "tools", "system prompt", "options"]);
+                table.push_record([
+                    config
+                        .models
+                        .iter()
+                        .map(|x| format!("{}:{}", x.provider, x.model))
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                    config.tools.join("\n"),
+                    config.system_prompt.unwrap_or_default(),
+                    config.options.unwrap_or_default(),
+                ]);
+
+                let mut table = table.build();
+                table.with((Alignment::center(), Style::rounded()));
+                print!("{}", table);
+            }
+        },
+        Action::Token { action } => match action {
+            TokenAction::Create { name, scope } => {
+                let token = req
+                    .create_api_token(&ApiTokenData {
+                        name,
+                        scope: scope.into(),
+                        expires: None,
+                    })
+                    .await?;
+
+                println!("Created token '{}'.", token.info.name);
+                println!("{}", token.token);
+                println!("Store it somewhere safe, it will not be shown again.");
+            }
+            TokenAction::Rm { id } => match req.revoke_api_token(&id).await {
+                Ok(Some(_)) => println!("Successfully revoked {}!", id),
+                Ok(None) => println!("Failed to revoke {}.", id),
+                Err(e) => return Err(e.into()),
+            },
+            TokenAction::List => {
+                let tokens = req.list_all_api_tokens().await?;
+
+                let mut table = Builder::new();
+                table.push_record(["id", "name", "scope", "expires", "last used"]);
+
+                for token in tokens {
+                    table.push_record([
+                        token.id.key().to_string(),
+                        token.name,
+                        token.scope.to_string(),
+                        token
+                            .expires
+                            .map(|x| x.0.to_string())
+                            .unwrap_or(String::from("never")),
+                        token
+                            .last_used
+                            .map(|x| x.0.to_string())
+                            .unwrap_or(String::from("never")),
+                    ]);
+                }
+
+                let mut table = table.build();
+                table.with((Alignment::center(), Style::rounded()));
+                print!("{}", table);
+            }
+        },
         Action::Ollama { action } => match action {
             OllamaAction::All { search } => {
-                let models = req
-                    .make_request::<Vec<OllamaModelsInfo>, ()>(
-                        &if let Some(search) = search {
-                            format!("provider/ollama/model/search/{}", search)
-                        } else {
-                            "provider/ollama/model/all/".to_string()
-                        },
-                        &(),
-                        RequestType::Get,
-                    )
-                    .await
-                    .map(|x| {
-                        if x.len() > 100 {
-                            x[0..=100].to_vec()
-                        } else {
-                            x
-                        }
-                    })
-                    .unwrap_or_default();
+                let models = if let Some(search) = search {
+                    req.search_ollama_models(&search).await
+                } else {
+                    req.list_all_ollama_models().await
+                }
+                .map(|x| {
+                    if x.len() > 100 {
+                        x[0..=100].to_vec()
+                    } else {
+                        x
+                    }
+                })
+                .unwrap_or_default();
 
                 let mut table = Builder::new();
                 table.push_record(["name", "author"]);
@@ -281,20 +920,13 @@ async fn run_action(req: &Request, action: Action) -> Result<(), Box<dyn Error>>
         },
         Action::Provider(args) => match args.action {
             ProviderAction::List => {
-                let models: Vec<Value> = req
-                    .make_request(
-                        &format!("provider/{}/model/all/", args.id),
-                        &(),
-                        RequestType::Get,
-                    )
-                    .await
-                    .unwrap();
+                let models = req.list_all_provider_models(&args.id).await?;
 
                 let mut table = Builder::new();
                 table.push_record(["model"]);
 
                 for model in models {
-                    table.push_record([model["id"].as_str().unwrap().to_string()]);
+                    table.push_record([model.id]);
                 }
 
                 let mut table = table.build();
@@ -302,21 +934,17 @@ async fn run_action(req: &Request, action: Action) -> Result<(), Box<dyn Error>>
                 print!("{}", table);
             }
             ProviderAction::Pull { model } => pull_model(&req, &args.id, &model).await,
-            ProviderAction::Run { model } => {
-                let _ = repl(&req, args.id, model).await?;
+            ProviderAction::Run { model, assistant } => {
+                let assistant = match assistant {
+                    Some(x) => Some(find_assistant(req, &x).await?),
+                    None => None,
+                };
+                let _ = repl(&req, args.id, model, assistant.as_ref()).await?;
             }
             ProviderAction::Rm { model } => {
-                if let Ok(Some(_)) = req
-                    .make_request::<Option<Value>, ()>(
-                        &format!("provider/{0}/model/{1}", args.id, model),
-                        &(),
-                        RequestType::Delete,
-                    )
-                    .await
-                {
-                    println!("Successfully deleted {}!", model);
-                } else {
-                    println!("Failed to delete {}.", model)
+                match req.delete_provider_model(&args.id, &model).await {
+                    Ok(_) => println!("Successfully deleted {}!", model),
+                    Err(e) => return Err(e.into()),
                 }
             }
         },
@@ -328,24 +956,14 @@ async fn run_action(req: &Request, action: Action) -> Result<(), Box<dyn Error>>
                 .build()
                 .unwrap();
 
-            let _ = req
-                .make_request::<Option<Provider>, ProviderData>(
-                    "provider/",
-                    &data,
-                    RequestType::Post,
-                )
-                .await;
+            let _ = req.add_provider(&data).await;
 
-            let providers: Vec<Provider> = req
-                .make_request("provider/all/", &(), RequestType::Get)
-                .await?;
+            let providers = req.list_all_providers().await?;
 
             print_providers(providers);
         }
         Action::List => {
-            let providers: Vec<Provider> = req
-                .make_request("provider/all/", &(), RequestType::Get)
-                .await?;
+            let providers = req.list_all_providers().await?;
 
             print_providers(providers);
         }
@@ -363,9 +981,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let req = Request {
         url: url.clone(),
-        jwt: match load_token() {
-            Ok(x) => Some(x.token),
-            Err(_) => None,
+        jwt: match env::var("OCHAT_TOKEN") {
+            Ok(x) if !x.trim().is_empty() => Some(x.trim().to_string()),
+            _ => match load_token() {
+                Ok(x) => Some(x.token),
+                Err(_) => None,
+            },
         },
     };
 
@@ -414,6 +1035,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
             println!("Closing server.");
             let _ = server.kill()?;
         }
+
+        if let Err(e) = &res
+            && let Some(e) = e.downcast_ref::<RequestError>()
+        {
+            eprintln!("{}", e);
+            std::process::exit(exit_code(e));
+        }
+
         return res;
     }
 
@@ -424,24 +1053,85 @@ async fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-async fn repl(req: &Request, provider: String, model: String) -> Result<(), Box<dyn Error>> {
-    if let Ok(Some(_)) = req
-        .make_request::<Option<Value>, ()>(
-            &format!("provider/{0}/model/{1}", provider, model),
-            &(),
-            RequestType::Get,
-        )
-        .await
-    {
-        // Model succesfully retrieved!
-    } else {
-        println!("Pulling {}.", model);
-        pull_model(&req, &provider, &model).await;
+fn exit_code(e: &RequestError) -> i32 {
+    match e {
+        RequestError::Connection(_) => 3,
+        RequestError::Decode(_) => 1,
+        RequestError::Server(x) => match x.code {
+            ErrorCode::NotFound => 4,
+            ErrorCode::Unauthorized | ErrorCode::Forbidden => 5,
+            ErrorCode::Validation => 6,
+            ErrorCode::Conflict => 7,
+            ErrorCode::Upstream => 8,
+            ErrorCode::Internal => 1,
+        },
+    }
+}
+
+fn parse_model(value: &str) -> Result<SettingsProvider, Box<dyn Error>> {
+    let Some((provider, model)) = value.split_once(":") else {
+        return Err(format!("Expected a 'provider:model' pair, got '{}'.", value).into());
+    };
+
+    Ok(SettingsProvider {
+        provider: provider.trim().to_string(),
+        model: model.trim().to_string(),
+    })
+}
+
+/// Finds an assistant by its id, name or @mention.
+async fn find_assistant(req: &Request, name: &str) -> Result<Assistant, Box<dyn Error>> {
+    let name = name.trim().trim_start_matches('@');
+
+    req.list_all_assistants()
+        .await?
+        .into_iter()
+        .find(|x| {
+            x.id.key().to_string() == name
+                || x.name.eq_ignore_ascii_case(name)
+                || x.mention() == name.to_lowercase()
+        })
+        .ok_or_else(|| format!("Assistant '{}' does not exist.", name).into())
+}
+
+async fn repl(
+    req: &Request,
+    provider: String,
+    model: String,
+    assistant: Option<&Assistant>,
+) -> Result<(), Box<dyn Error>> {
+    match req.get_provider_model(&provider, &model).await {
+        Ok(_) => {
+            // Model succesfully retrieved!
+        }
+        Err(e) if e.is(ErrorCode::NotFound) => {
+            println!("Pulling {}.", model);
+            pull_model(&req, &provider, &model).await;
+        }
+        Err(e) => return Err(e.into()),
     }
 
     let mut messages: Vec<ChatQueryMessage> = Vec::new();
     let mut editor = DefaultEditor::new()?;
 
+    if let Some(assistant) = assistant {
+        println!("Chatting with {}.", assistant);
+
+        if let Some(prompt) = &assistant.system_prompt {
+            messages.push(
+                ChatQueryMessageBuilder::default()
+                    .text(prompt.clone())
+                    .role(Role::System)
+                    .build()
+                    .unwrap(),
+            );
+        }
+
+        for starter in &assistant.starters {
+            println!("  - {}", starter);
+        }
+    }
+
     loop {
         let readline = editor.readline("\n>>> ");
         match readline {
@@ -455,19 +1145,17 @@ async fn repl(req: &Request, provider: String, model: String) -> Result<(), Box<
                 );
 
                 let mut response = req
-                    .get_client()
-                    .get(&format!("{0}/generation/text/stream/", req.url,))
-                    .json(
+                    .stream_text(
                         &ChatQueryDataBuilder::default()
                             .provider(provider.clone())
                             .model(model.clone())
                             .messages(messages.clone())
+                            .tools(assistant.map(|x| x.tools.clone()).unwrap_or_default())
+                            .options(assistant.and_then(|x| x.options.clone()))
                             .build()
                             .unwrap(),
                     )
-                    .send()
-                    .await
-                    .unwrap()
+                    .await?
                     .bytes_stream();
 
                 let mut stdout = io::stdout();
@@ -483,8 +1171,51 @@ async fn repl(req: &Request, provider: String, model: String) -> Result<(), Box<
                                         let _ = stdout.flush().unwrap();
                                     }
                                     ChatStreamResult::Generated(x) => {
+                                        if let Some(metrics) = x
+                                            .metrics
+                                            .as_ref()
+                                            .filter(|x| !x.unsupported_options.is_empty())
+                                        {
+                                            eprintln!(
+                                                "\n[Options not supported by this model: {}]",
+                                                metrics
+                                                    .unsupported_options
+                                                    .iter()
+                                                    .map(|x| x.to_string())
+                                                    .collect::<Vec<String>>()
+                                                    .join(", ")
+                                            );
+                                        }
+                                        if !x.func_calls.is_empty() {
+                                            messages.push(
+                                                ChatQueryMessageBuilder::default()
+                                                    .text(String::new())
+                                                    .role(Role::Function)
+                                                    .func_calls(x.func_calls.clone())
+                                                    .build()
+                                                    .unwrap(),
+                                            );
+                                        }
                                         messages.push(x.into());
                                     }
+                                    // Progress goes to stderr so piping the answer stays clean.
+                                    ChatStreamResult::ToolCall(x) => {
+                                        eprintln!("[{}...]", x.describe())
+                                    }
+                                    ChatStreamResult::ToolResult(x) => {
+                                        eprintln!("[{} finished]", x.name)
+                                    }
+                                    ChatStreamResult::Citations(x) => {
+                                        for citation in x {
+                                            match citation.title {
+                                                Some(title) => {
+                                                    eprintln!("  - {} ({})", title, citation.url)
+                                                }
+                                                None => eprintln!("  - {}", citation.url),
+                                            }
+                                        }
+                                    }
+                                    ChatStreamResult::Status(x) => eprintln!("[{x}...]"),
                                     ChatStreamResult::Finished => {
                                         break;
                                     }
@@ -528,16 +1259,13 @@ async fn pull_model(req: &Request, provider: &str, model: &str) {
         .progress_chars("#>-"),
     );
 
-    let mut response = req
-        .get_client()
-        .post(&format!(
-            "{0}/provider/{1}/model/{2}",
-            req.url, provider, model
-        ))
-        .send()
-        .await
-        .unwrap()
-        .bytes_stream();
+    let mut response = match req.pull_ollama_model(provider, model).await {
+        Ok(x) => x.bytes_stream(),
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
 
     while let Some(response) = response.next().await {
         match response {
@@ -0,0 +1,905 @@
+use crate::data::{Request, RequestError, RequestType, get_client};
+use ochat_types::{
+    ServerFeatures,
+    assistants::{Assistant, AssistantData},
+    chats::{
+        Chat, ChatConfig, ChatData,
+        export::{DatasetFormat, ExportFormat},
+        import::{ImportData, ImportResult},
+        messages::{Message, MessageData, MessageRating, MessageSearchResult},
+        previews::Preview,
+    },
+    errors::{ErrorBody, ErrorCode},
+    files::{B64File, B64FileData, DBFile},
+    folders::{Folder, FolderData, FolderNameData},
+    gc::GcReport,
+    generation::{
+        stt::{SttQueryData, SttResponse},
+        text::{ChatQueryData, ChatResponse, TextJob, TextJobData},
+        tts::{TtsQueryData, TtsResponse},
+    },
+    health::HealthStatus,
+    options::{
+        GenOptions, GenOptionsData,
+        relationships::{GenModelRelationship, GenModelRelationshipData},
+    },
+    prompts::{Prompt, PromptData},
+    providers::{
+        Model, Provider, ProviderData,
+        hf::{DownloadedHFModels, HFModel, HFModelDetails, ModelType},
+        ollama::OllamaModelsInfo,
+    },
+    settings::{Settings, SettingsData, SettingsProvider},
+    trash::{TrashItem, TrashKind},
+    user::{
+        ApiToken, ApiTokenData, ChangePasswordData, NewApiToken, Session, SigninData, SignupData,
+        Token, User,
+    },
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use urlencoding::encode;
+
+pub trait Required<T> {
+    fn required(self) -> Result<T, RequestError>;
+}
+
+impl<T> Required<T> for Result<Option<T>, RequestError> {
+    fn required(self) -> Result<T, RequestError> {
+        self.and_then(|x| {
+            x.ok_or(RequestError::Server(ErrorBody::new(
+                ErrorCode::NotFound,
+                "The server returned nothing.",
+            )))
+        })
+    }
+}
+
+fn model_type_path(model_type: &ModelType) -> &'static str {
+    match model_type {
+        ModelType::Text => "text",
+        ModelType::Stt => "stt",
+        ModelType::Tts => "tts",
+    }
+}
+
+/// Huggingface ids take up two segments, the user and the id are encoded on their own.
+fn encode_hf_model(model: &str) -> String {
+    model.split('/').map(encode).collect::<Vec<_>>().join("/")
+}
+
+impl Request {
+    async fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, RequestError> {
+        self.make_request(endpoint, &(), RequestType::Get).await
+    }
+
+    async fn post<T: DeserializeOwned, B: Serialize>(
+        &self,
+        endpoint: &str,
+        body: &B,
+    ) -> Result<T, RequestError> {
+        self.make_request(endpoint, body, RequestType::Post).await
+    }
+
+    async fn put<T: DeserializeOwned, B: Serialize>(
+        &self,
+        endpoint: &str,
+        body: &B,
+    ) -> Result<T, RequestError> {
+        self.make_request(endpoint, body, RequestType::Put).await
+    }
+
+    async fn delete<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T, RequestError> {
+        self.make_request(endpoint, &(), RequestType::Delete).await
+    }
+
+    /// Sends a request to a streaming endpoint and returns the response for the caller to read with `bytes_stream`.
+    pub async fn stream_request<B: Serialize>(
+        &self,
+        endpoint: &str,
+        body: &B,
+        request_type: RequestType,
+    ) -> Result<reqwest::Response, RequestError> {
+        let url = format!("{}/{}", self.url, endpoint);
+        let request = get_client(&self.jwt);
+
+        let request = match request_type {
+            RequestType::Get => request.get(url),
+            RequestType::Post => request.post(url),
+            RequestType::Put => request.put(url),
+            RequestType::Delete => request.delete(url),
+        };
+
+        let response = request
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| RequestError::Connection(e.to_string()))?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(RequestError::Server(
+                serde_json::from_str::<ErrorBody>(&text).unwrap_or_else(|_| {
+                    ErrorBody::new(ErrorCode::from_status(status.as_u16()), text)
+                }),
+            ));
+        }
+
+        Ok(response)
+    }
+
+    pub async fn openapi(&self) -> Result<Value, RequestError> {
+        self.get("openapi.json").await
+    }
+
+    pub async fn version(&self) -> Result<String, RequestError> {
+        self.get("version/").await
+    }
+
+    pub async fn features(&self) -> Result<Vec<ServerFeatures>, RequestError> {
+        self.get("features/").await
+    }
+
+    pub async fn health(&self) -> Result<HealthStatus, RequestError> {
+        self.get("health").await
+    }
+
+    pub async fn ready(&self) -> Result<HealthStatus, RequestError> {
+        self.get("ready").await
+    }
+
+    pub async fn signin(&self, data: &SigninData) -> Result<Token, RequestError> {
+        self.post("signin/", data).await
+    }
+
+    pub async fn signup(&self, data: &SignupData) -> Result<Token, RequestError> {
+        self.post("signup/", data).await
+    }
+
+    pub async fn get_current_user(&self) -> Result<Option<User>, RequestError> {
+        self.get("user/").await
+    }
+
+    pub async fn get_user(&self, id: &str) -> Result<Option<User>, RequestError> {
+        self.get(&format!("user/{}", encode(id))).await
+    }
+
+    pub async fn logout(&self) -> Result<Option<Session>, RequestError> {
+        self.post("user/logout/", &()).await
+    }
+
+    pub async fn refresh(&self) -> Result<Token, RequestError> {
+        self.post("user/refresh/", &()).await
+    }
+
+    pub async fn change_password(
+        &self,
+        data: &ChangePasswordData,
+    ) -> Result<Option<User>, RequestError> {
+        self.put("user/password/", data).await
+    }
+
+    pub async fn list_all_sessions(&self) -> Result<Vec<Session>, RequestError> {
+        self.get("user/session/all/").await
+    }
+
+    pub async fn revoke_session(&self, id: &str) -> Result<Option<Session>, RequestError> {
+        self.delete(&format!("user/session/{}", encode(id))).await
+    }
+
+    pub async fn create_api_token(&self, data: &ApiTokenData) -> Result<NewApiToken, RequestError> {
+        self.post("user/token/", data).await
+    }
+
+    pub async fn list_all_api_tokens(&self) -> Result<Vec<ApiToken>, RequestError> {
+        self.get("user/token/all/").await
+    }
+
+    pub async fn revoke_api_token(&self, id: &str) -> Result<Option<ApiToken>, RequestError> {
+        self.delete(&format!("user/token/{}", encode(id))).await
+    }
+
+    pub async fn list_all_users(&self) -> Result<Vec<User>, RequestError> {
+        self.get("users/").await
+    }
+
+    pub async fn create_user(&self, data: &SignupData) -> Result<Option<User>, RequestError> {
+        self.post("users/", data).await
+    }
+
+    pub async fn delete_user(&self, id: &str) -> Result<Option<User>, RequestError> {
+        self.delete(&format!("users/{}", encode(id))).await
+    }
+
+    pub async fn enable_user(&self, id: &str) -> Result<Option<User>, RequestError> {
+        self.put(&format!("users/{}/enable/", encode(id)), &())
+            .await
+    }
+
+    pub async fn disable_user(&self, id: &str) -> Result<Option<User>, RequestError> {
+        self.put(&format!("users/{}/disable/", encode(id)), &())
+            .await
+    }
+
+    pub async fn promote_user(&self, id: &str) -> Result<Option<User>, RequestError> {
+        self.put(&format!("users/{}/promote/", encode(id)), &())
+            .await
+    }
+
+    pub async fn demote_user(&self, id: &str) -> Result<Option<User>, RequestError> {
+        self.put(&format!("users/{}/demote/", encode(id)), &())
+            .await
+    }
+
+    pub async fn get_garbage(&self) -> Result<GcReport, RequestError> {
+        self.get("gc/").await
+    }
+
+    pub async fn remove_garbage(&self) -> Result<GcReport, RequestError> {
+        self.post("gc/", &()).await
+    }
+
+    pub async fn create_chat(&self, data: &ChatData) -> Result<Option<Chat>, RequestError> {
+        self.post("chat/", data).await
+    }
+
+    pub async fn branch_new_chat(
+        &self,
+        messages: &[MessageData],
+    ) -> Result<Option<Chat>, RequestError> {
+        self.post("chat/branch/", &messages).await
+    }
+
+    pub async fn list_all_chats(&self) -> Result<Vec<Chat>, RequestError> {
+        self.get("chat/all/").await
+    }
+
+    pub async fn get_chat(&self, id: &str) -> Result<Option<Chat>, RequestError> {
+        self.get(&format!("chat/{}", encode(id))).await
+    }
+
+    pub async fn update_chat(
+        &self,
+        id: &str,
+        data: &ChatData,
+    ) -> Result<Option<Chat>, RequestError> {
+        self.put(&format!("chat/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_chat(&self, id: &str) -> Result<Option<Chat>, RequestError> {
+        self.delete(&format!("chat/{}", encode(id))).await
+    }
+
+    pub async fn set_chat_root(&self, id: &str, root: &str) -> Result<Option<Chat>, RequestError> {
+        self.put(&format!("chat/{}/root/{}", encode(id), encode(root)), &())
+            .await
+    }
+
+    pub async fn get_chat_config(&self, id: &str) -> Result<Option<ChatConfig>, RequestError> {
+        self.get(&format!("chat/{}/config/", encode(id))).await
+    }
+
+    pub async fn set_chat_config(
+        &self,
+        id: &str,
+        config: &ChatConfig,
+    ) -> Result<Option<Chat>, RequestError> {
+        self.put(&format!("chat/{}/config/", encode(id)), config)
+            .await
+    }
+
+    /// Returns the rated messages of every chat as a JSONL dataset.
+    pub async fn export_dataset(&self, format: &DatasetFormat) -> Result<String, RequestError> {
+        self.stream_request(&format!("chat/dataset/{}", format), &(), RequestType::Get)
+            .await?
+            .text()
+            .await
+            .map_err(|e| RequestError::Decode(e.to_string()))
+    }
+
+    pub async fn import_chats(&self, data: &ImportData) -> Result<ImportResult, RequestError> {
+        self.post("chat/import/", data).await
+    }
+
+    /// Returns the exported chat as text, json is the full `ChatExport`.
+    pub async fn export_chat(
+        &self,
+        id: &str,
+        format: &ExportFormat,
+    ) -> Result<String, RequestError> {
+        self.stream_request(
+            &format!("chat/{}/export/{}", encode(id), format),
+            &(),
+            RequestType::Get,
+        )
+        .await?
+        .text()
+        .await
+        .map_err(|e| RequestError::Decode(e.to_string()))
+    }
+
+    pub async fn create_message(
+        &self,
+        data: &MessageData,
+    ) -> Result<Option<Message>, RequestError> {
+        self.post("message/", data).await
+    }
+
+    pub async fn create_message_with_parent(
+        &self,
+        parent: &str,
+        data: &MessageData,
+    ) -> Result<Option<Message>, RequestError> {
+        self.post(&format!("message/parent/{}", encode(parent)), data)
+            .await
+    }
+
+    pub async fn list_all_messages_from_parent(
+        &self,
+        parent: &str,
+    ) -> Result<Vec<Message>, RequestError> {
+        self.get(&format!("message/parent/{}", encode(parent)))
+            .await
+    }
+
+    pub async fn get_default_message_list_from_parent(
+        &self,
+        parent: &str,
+    ) -> Result<Vec<Message>, RequestError> {
+        self.get(&format!("message/parent/{}/default/", encode(parent)))
+            .await
+    }
+
+    pub async fn get_all_messages_from_root(&self, id: &str) -> Result<Vec<Message>, RequestError> {
+        self.get(&format!("message/parent/{}/all/", encode(id)))
+            .await
+    }
+
+    pub async fn list_all_messages(&self) -> Result<Vec<Message>, RequestError> {
+        self.get("message/all/").await
+    }
+
+    pub async fn get_message(&self, id: &str) -> Result<Option<Message>, RequestError> {
+        self.get(&format!("message/{}", encode(id))).await
+    }
+
+    pub async fn update_message(
+        &self,
+        id: &str,
+        data: &MessageData,
+    ) -> Result<Option<Message>, RequestError> {
+        self.put(&format!("message/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_message(&self, id: &str) -> Result<Option<Message>, RequestError> {
+        self.delete(&format!("message/{}", encode(id))).await
+    }
+
+    pub async fn rate_message(
+        &self,
+        id: &str,
+        rating: &MessageRating,
+    ) -> Result<Option<Message>, RequestError> {
+        self.put(&format!("message/{}/rating/", encode(id)), rating)
+            .await
+    }
+
+    pub async fn remove_message_rating(&self, id: &str) -> Result<Option<Message>, RequestError> {
+        self.delete(&format!("message/{}/rating/", encode(id)))
+            .await
+    }
+
+    pub async fn list_all_previews(&self) -> Result<Vec<Preview>, RequestError> {
+        self.get("preview/all/").await
+    }
+
+    pub async fn search_messages(
+        &self,
+        search: &str,
+    ) -> Result<Vec<MessageSearchResult>, RequestError> {
+        self.get(&format!("message/search/{}", encode(search)))
+            .await
+    }
+
+    pub async fn search_previews(&self, search: &str) -> Result<Vec<Preview>, RequestError> {
+        self.get(&format!("preview/search/{}", encode(search)))
+            .await
+    }
+
+    pub async fn get_preview(&self, id: &str) -> Result<Option<Preview>, RequestError> {
+        self.get(&format!("preview/{}", encode(id))).await
+    }
+
+    pub async fn update_preview(&self, id: &str) -> Result<Option<Preview>, RequestError> {
+        self.put(&format!("preview/{}", encode(id)), &()).await
+    }
+
+    pub async fn create_folder(&self, data: &FolderData) -> Result<Option<Folder>, RequestError> {
+        self.post("folder/", data).await
+    }
+
+    pub async fn list_all_folders(&self) -> Result<Vec<Folder>, RequestError> {
+        self.get("folder/all/").await
+    }
+
+    pub async fn search_folders(&self, search: &str) -> Result<Vec<Folder>, RequestError> {
+        self.get(&format!("folder/search/{}", encode(search))).await
+    }
+
+    pub async fn get_folder(&self, id: &str) -> Result<Option<Folder>, RequestError> {
+        self.get(&format!("folder/{}", encode(id))).await
+    }
+
+    pub async fn update_folder(
+        &self,
+        id: &str,
+        data: &FolderData,
+    ) -> Result<Option<Folder>, RequestError> {
+        self.put(&format!("folder/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_folder(&self, id: &str) -> Result<Option<Folder>, RequestError> {
+        self.delete(&format!("folder/{}", encode(id))).await
+    }
+
+    pub async fn set_folder_parent(
+        &self,
+        id: &str,
+        parent: &str,
+    ) -> Result<Option<Folder>, RequestError> {
+        self.put(
+            &format!("folder/{}/parent/{}", encode(id), encode(parent)),
+            &(),
+        )
+        .await
+    }
+
+    pub async fn unparent_folder(&self, id: &str) -> Result<Option<Folder>, RequestError> {
+        self.put(&format!("folder/{}/parent/none", encode(id)), &())
+            .await
+    }
+
+    pub async fn add_folder_chat(
+        &self,
+        id: &str,
+        chat: &str,
+    ) -> Result<Option<Folder>, RequestError> {
+        self.put(&format!("folder/{}/chat/{}", encode(id), encode(chat)), &())
+            .await
+    }
+
+    pub async fn remove_folder_chat(
+        &self,
+        id: &str,
+        chat: &str,
+    ) -> Result<Option<Folder>, RequestError> {
+        self.delete(&format!("folder/{}/chat/{}", encode(id), encode(chat)))
+            .await
+    }
+
+    pub async fn update_folder_name(
+        &self,
+        id: &str,
+        data: &FolderNameData,
+    ) -> Result<Option<Folder>, RequestError> {
+        self.put(&format!("folder/{}/name/", encode(id)), data)
+            .await
+    }
+
+    pub async fn archive_chat(&self, chat: &str) -> Result<Option<Folder>, RequestError> {
+        self.put(&format!("folder/archive/chat/{}", encode(chat)), &())
+            .await
+    }
+
+    pub async fn fav_chat(&self, chat: &str) -> Result<Option<Folder>, RequestError> {
+        self.put(&format!("folder/fav/chat/{}", encode(chat)), &())
+            .await
+    }
+
+    pub async fn create_file(&self, data: &B64FileData) -> Result<Option<DBFile>, RequestError> {
+        self.post("file/", data).await
+    }
+
+    pub async fn list_all_files(&self) -> Result<Vec<DBFile>, RequestError> {
+        self.get("file/all/").await
+    }
+
+    pub async fn get_file(&self, id: &str) -> Result<Option<B64File>, RequestError> {
+        self.get(&format!("file/{}", encode(id))).await
+    }
+
+    pub async fn update_file(
+        &self,
+        id: &str,
+        data: &B64FileData,
+    ) -> Result<Option<DBFile>, RequestError> {
+        self.put(&format!("file/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_file(&self, id: &str) -> Result<Option<DBFile>, RequestError> {
+        self.delete(&format!("file/{}", encode(id))).await
+    }
+
+    pub async fn add_prompt(&self, data: &PromptData) -> Result<Option<Prompt>, RequestError> {
+        self.post("prompt/", data).await
+    }
+
+    pub async fn list_all_prompts(&self) -> Result<Vec<Prompt>, RequestError> {
+        self.get("prompt/all/").await
+    }
+
+    pub async fn search_prompts(&self, search: &str) -> Result<Vec<Prompt>, RequestError> {
+        self.get(&format!("prompt/search/{}", encode(search))).await
+    }
+
+    pub async fn get_prompt(&self, id: &str) -> Result<Option<Prompt>, RequestError> {
+        self.get(&format!("prompt/{}", encode(id))).await
+    }
+
+    pub async fn update_prompt(
+        &self,
+        id: &str,
+        data: &PromptData,
+    ) -> Result<Option<Prompt>, RequestError> {
+        self.put(&format!("prompt/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_prompt(&self, id: &str) -> Result<Option<Prompt>, RequestError> {
+        self.delete(&format!("prompt/{}", encode(id))).await
+    }
+
+    pub async fn add_assistant(
+        &self,
+        data: &AssistantData,
+    ) -> Result<Option<Assistant>, RequestError> {
+        self.post("assistant/", data).await
+    }
+
+    pub async fn list_all_assistants(&self) -> Result<Vec<Assistant>, RequestError> {
+        self.get("assistant/all/").await
+    }
+
+    pub async fn get_assistant(&self, id: &str) -> Result<Option<Assistant>, RequestError> {
+        self.get(&format!("assistant/{}", encode(id))).await
+    }
+
+    pub async fn update_assistant(
+        &self,
+        id: &str,
+        data: &AssistantData,
+    ) -> Result<Option<Assistant>, RequestError> {
+        self.put(&format!("assistant/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_assistant(&self, id: &str) -> Result<Option<Assistant>, RequestError> {
+        self.delete(&format!("assistant/{}", encode(id))).await
+    }
+
+    pub async fn list_trash(&self) -> Result<Vec<TrashItem>, RequestError> {
+        self.get("trash/").await
+    }
+
+    pub async fn empty_trash(&self) -> Result<Vec<TrashItem>, RequestError> {
+        self.delete("trash/").await
+    }
+
+    pub async fn restore_trash(
+        &self,
+        kind: &TrashKind,
+        id: &str,
+    ) -> Result<Option<TrashItem>, RequestError> {
+        self.put(&format!("trash/{}/{}", kind, encode(id)), &())
+            .await
+    }
+
+    pub async fn purge_trash(
+        &self,
+        kind: &TrashKind,
+        id: &str,
+    ) -> Result<Option<TrashItem>, RequestError> {
+        self.delete(&format!("trash/{}/{}", kind, encode(id))).await
+    }
+
+    pub async fn add_gen_options(
+        &self,
+        data: &GenOptionsData,
+    ) -> Result<Option<GenOptions>, RequestError> {
+        self.post("option/", data).await
+    }
+
+    pub async fn list_all_gen_options(&self) -> Result<Vec<GenOptions>, RequestError> {
+        self.get("option/all/").await
+    }
+
+    pub async fn search_gen_options(&self, search: &str) -> Result<Vec<GenOptions>, RequestError> {
+        self.get(&format!("option/search/{}", encode(search))).await
+    }
+
+    pub async fn get_gen_options(&self, id: &str) -> Result<Option<GenOptions>, RequestError> {
+        self.get(&format!("option/{}", encode(id))).await
+    }
+
+    pub async fn update_gen_options(
+        &self,
+        id: &str,
+        data: &GenOptionsData,
+    ) -> Result<Option<GenOptions>, RequestError> {
+        self.put(&format!("option/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_gen_options(&self, id: &str) -> Result<Option<GenOptions>, RequestError> {
+        self.delete(&format!("option/{}", encode(id))).await
+    }
+
+    pub async fn get_models_from_options(
+        &self,
+        id: &str,
+    ) -> Result<Vec<SettingsProvider>, RequestError> {
+        self.get(&format!("option/{}/model/all/", encode(id))).await
+    }
+
+    pub async fn get_gen_models_from_options(
+        &self,
+        id: &str,
+    ) -> Result<Vec<GenModelRelationship>, RequestError> {
+        self.get(&format!("option/{}/all/", encode(id))).await
+    }
+
+    pub async fn add_gen_models(
+        &self,
+        data: &GenModelRelationshipData,
+    ) -> Result<Option<GenModelRelationship>, RequestError> {
+        self.post("option/relationship/", data).await
+    }
+
+    pub async fn list_all_gen_models(&self) -> Result<Vec<GenOptions>, RequestError> {
+        self.get("option/relationship/all/").await
+    }
+
+    pub async fn get_gen_models(
+        &self,
+        id: &str,
+    ) -> Result<Option<GenModelRelationship>, RequestError> {
+        self.get(&format!("option/relationship/{}", encode(id)))
+            .await
+    }
+
+    pub async fn update_gen_models(
+        &self,
+        id: &str,
+        data: &GenModelRelationshipData,
+    ) -> Result<Option<GenModelRelationship>, RequestError> {
+        self.put(&format!("option/relationship/{}", encode(id)), data)
+            .await
+    }
+
+    pub async fn delete_gen_models(
+        &self,
+        id: &str,
+    ) -> Result<Option<GenModelRelationship>, RequestError> {
+        self.delete(&format!("option/relationship/{}", encode(id)))
+            .await
+    }
+
+    pub async fn add_provider(
+        &self,
+        data: &ProviderData,
+    ) -> Result<Option<Provider>, RequestError> {
+        self.post("provider/", data).await
+    }
+
+    pub async fn list_all_providers(&self) -> Result<Vec<Provider>, RequestError> {
+        self.get("provider/all/").await
+    }
+
+    pub async fn get_provider(&self, id: &str) -> Result<Option<Provider>, RequestError> {
+        self.get(&format!("provider/{}", encode(id))).await
+    }
+
+    pub async fn update_provider(
+        &self,
+        id: &str,
+        data: &ProviderData,
+    ) -> Result<Option<Provider>, RequestError> {
+        self.put(&format!("provider/{}", encode(id)), data).await
+    }
+
+    pub async fn delete_provider(&self, id: &str) -> Result<Option<Provider>, RequestError> {
+        self.delete(&format!("provider/{}", encode(id))).await
+    }
+
+    pub async fn list_all_provider_models(&self, id: &str) -> Result<Vec<Model>, RequestError> {
+        self.get(&format!("provider/{}/model/all/", encode(id)))
+            .await
+    }
+
+    pub async fn get_provider_model(&self, id: &str, model: &str) -> Result<Model, RequestError> {
+        self.get(&format!("provider/{}/model/{}", encode(id), encode(model)))
+            .await
+    }
+
+    pub async fn delete_provider_model(
+        &self,
+        id: &str,
+        model: &str,
+    ) -> Result<String, RequestError> {
+        self.delete(&format!("provider/{}/model/{}", encode(id), encode(model)))
+            .await
+    }
+
+    pub async fn get_default_gen_options_from_model(
+        &self,
+        id: &str,
+        model: &str,
+    ) -> Result<Option<GenOptions>, RequestError> {
+        self.get(&format!(
+            "provider/{}/model/{}/options/",
+            encode(id),
+            encode(model)
+        ))
+        .await
+    }
+
+    /// Streams `OllamaPullModelStreamResult` values.
+    pub async fn pull_ollama_model(
+        &self,
+        id: &str,
+        model: &str,
+    ) -> Result<reqwest::Response, RequestError> {
+        self.stream_request(
+            &format!("provider/{}/model/{}", encode(id), encode(model)),
+            &(),
+            RequestType::Post,
+        )
+        .await
+    }
+
+    pub async fn list_all_ollama_models(&self) -> Result<Vec<OllamaModelsInfo>, RequestError> {
+        self.get("provider/ollama/model/all/").await
+    }
+
+    pub async fn search_ollama_models(
+        &self,
+        search: &str,
+    ) -> Result<Vec<OllamaModelsInfo>, RequestError> {
+        self.get(&format!("provider/ollama/model/search/{}", encode(search)))
+            .await
+    }
+
+    pub async fn get_downloaded_hf_models(&self) -> Result<DownloadedHFModels, RequestError> {
+        self.get("provider/hf/model/downloaded/").await
+    }
+
+    pub async fn list_all_hf_models(
+        &self,
+        model_type: &ModelType,
+    ) -> Result<Vec<HFModel>, RequestError> {
+        self.get(&format!(
+            "provider/hf/{}/model/all/",
+            model_type_path(model_type)
+        ))
+        .await
+    }
+
+    pub async fn search_hf_models(
+        &self,
+        model_type: &ModelType,
+        search: &str,
+    ) -> Result<Vec<HFModel>, RequestError> {
+        self.get(&format!(
+            "provider/hf/{}/model/search/{}",
+            model_type_path(model_type),
+            encode(search)
+        ))
+        .await
+    }
+
+    pub async fn list_all_downloaded_hf_models(
+        &self,
+        model_type: &ModelType,
+    ) -> Result<Vec<SettingsProvider>, RequestError> {
+        self.get(&format!(
+            "provider/hf/{}/model/downloaded/",
+            model_type_path(model_type)
+        ))
+        .await
+    }
+
+    /// `model` is the huggingface id in the form `user/id`.
+    pub async fn fetch_hf_model_details(
+        &self,
+        model_type: &ModelType,
+        model: &str,
+    ) -> Result<HFModelDetails, RequestError> {
+        self.get(&format!(
+            "provider/hf/{}/model/{}",
+            model_type_path(model_type),
+            encode_hf_model(model)
+        ))
+        .await
+    }
+
+    /// Streams `HFPullModelStreamResult` values, `model` is the huggingface id in the form `user/id`.
+    pub async fn pull_hf_model(
+        &self,
+        model_type: &ModelType,
+        model: &str,
+        name: &str,
+    ) -> Result<reqwest::Response, RequestError> {
+        self.stream_request(
+            &format!(
+                "provider/hf/{}/model/{}/{}",
+                model_type_path(model_type),
+                encode_hf_model(model),
+                encode(name)
+            ),
+            &(),
+            RequestType::Post,
+        )
+        .await
+    }
+
+    pub async fn get_settings(&self) -> Result<Settings, RequestError> {
+        self.get("settings/").await
+    }
+
+    pub async fn update_settings(
+        &self,
+        data: &SettingsData,
+    ) -> Result<Option<Settings>, RequestError> {
+        self.put("settings/", data).await
+    }
+
+    pub async fn reset_settings(&self) -> Result<Option<Settings>, RequestError> {
+        self.post("settings/reset/", &()).await
+    }
+
+    pub async fn run_text(&self, data: &ChatQueryData) -> Result<ChatResponse, RequestError> {
+        self.make_request("generation/text/run/", data, RequestType::Get)
+            .await
+    }
+
+    /// Streams `ChatStreamResult` values.
+    pub async fn stream_text(
+        &self,
+        data: &ChatQueryData,
+    ) -> Result<reqwest::Response, RequestError> {
+        self.stream_request("generation/text/stream/", data, RequestType::Get)
+            .await
+    }
+
+    pub async fn start_text_job(&self, data: &TextJobData) -> Result<TextJob, RequestError> {
+        self.post("generation/text/job/", data).await
+    }
+
+    pub async fn list_text_jobs(&self) -> Result<Vec<TextJob>, RequestError> {
+        self.get("generation/text/job/all/").await
+    }
+
+    /// Streams a running job from the start, the first chunk holds everything generated so far.
+    pub async fn attach_text_job(&self, id: &str) -> Result<reqwest::Response, RequestError> {
+        self.stream_request(
+            &format!("generation/text/job/{}", encode(id)),
+            &(),
+            RequestType::Get,
+        )
+        .await
+    }
+
+    pub async fn cancel_text_job(&self, id: &str) -> Result<TextJob, RequestError> {
+        self.delete(&format!("generation/text/job/{}", encode(id)))
+            .await
+    }
+
+    pub async fn run_tts(&self, data: &TtsQueryData) -> Result<TtsResponse, RequestError> {
+        self.make_request("generation/tts/run/", data, RequestType::Get)
+            .await
+    }
+
+    pub async fn run_stt(&self, data: &SttQueryData) -> Result<SttResponse, RequestError> {
+        self.make_request("generation/stt/run/", data, RequestType::Get)
+            .await
+    }
+}
@@ -1,12 +1,13 @@
 use ochat_types::{
-    providers::Provider,
+    errors::{ErrorBody, ErrorCode},
+    providers::{Provider, hf::ModelType},
     settings::{SettingsProvider, SettingsProviderBuilder},
 };
 use reqwest::header::{HeaderMap, HeaderValue};
 use serde::{Serialize, de::DeserializeOwned};
-use serde_json::Value;
-use std::error::Error;
+use std::{error::Error, fmt::Display};
 
+pub mod client;
 pub mod start;
 pub mod versions;
 
@@ -32,7 +33,7 @@ impl Request {
         endpoint: &str,
         body: &Json,
         request_type: RequestType,
-    ) -> Result<T, String> {
+    ) -> Result<T, RequestError> {
         request_ochat_server(
             &self.jwt,
             &format!("{}/{}", self.url, endpoint,),
@@ -60,14 +61,13 @@ impl Data {
             _ => String::from("http://localhost:1212/api"),
         };
 
+        let req = Request {
+            url: instance.clone(),
+            jwt: jwt.clone(),
+        };
+
         let providers: Vec<Provider> = if jwt.is_some() {
-            request_ochat_server(
-                &jwt,
-                &format!("{}/{}", instance, "provider/all/"),
-                &(),
-                RequestType::Get,
-            )
-            .await?
+            req.list_all_providers().await?
         } else {
             Vec::new()
         };
@@ -80,20 +80,8 @@ impl Data {
         .await?;
 
         Ok(Data {
-            stt_models: request_ochat_server(
-                &jwt,
-                &format!("{}/provider/hf/stt/model/downloaded/", instance),
-                &(),
-                RequestType::Get,
-            )
-            .await?,
-            tts_models: request_ochat_server(
-                &jwt,
-                &format!("{}/provider/hf/tts/model/downloaded/", instance),
-                &(),
-                RequestType::Get,
-            )
-            .await?,
+            stt_models: req.list_all_downloaded_hf_models(&ModelType::Stt).await?,
+            tts_models: req.list_all_downloaded_hf_models(&ModelType::Tts).await?,
             instance_url: Some(instance),
             providers,
             models,
@@ -110,36 +98,26 @@ impl Data {
         if jwt.is_none() {
             return Ok(Vec::new());
         }
+        let req = Request {
+            url,
+            jwt: jwt.clone(),
+        };
         let mut models: Vec<SettingsProvider> = Vec::new();
 
         for provider in providers.iter() {
-            let provider_models: Result<Vec<Value>, String> = request_ochat_server(
-                jwt,
-                &format!("{}/{}", url, format!("provider/{}/model/all/", &provider)),
-                &(),
-                RequestType::Get,
-            )
-            .await;
-
-            if let Ok(provider_models) = provider_models {
+            if let Ok(provider_models) = req.list_all_provider_models(provider).await {
                 for model in provider_models {
                     models.push(
                         SettingsProviderBuilder::default()
                             .provider(provider.to_string())
-                            .model(model["id"].as_str().unwrap().to_string())
+                            .model(model.id)
                             .build()?,
                     );
                 }
             }
         }
 
-        let mut hf_models: Vec<SettingsProvider> = request_ochat_server(
-            jwt,
-            &format!("{}/provider/hf/text/model/downloaded/", url),
-            &(),
-            RequestType::Get,
-        )
-        .await?;
+        let mut hf_models = req.list_all_downloaded_hf_models(&ModelType::Text).await?;
 
         models.append(&mut hf_models);
 
@@ -168,6 +146,44 @@ pub enum RequestType {
     Delete,
 }
 
+#[derive(Clone, Debug)]
+pub enum RequestError {
+    Server(ErrorBody),
+    Connection(String),
+    Decode(String),
+}
+
+impl RequestError {
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::Server(x) => Some(x.code),
+            _ => None,
+        }
+    }
+
+    pub fn is(&self, code: ErrorCode) -> bool {
+        self.code() == Some(code)
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Server(x) => write!(f, "{}", x),
+            Self::Connection(x) => write!(f, "Connection Error : {}", x),
+            Self::Decode(x) => write!(f, "Decode Error : {}", x),
+        }
+    }
+}
+
+impl Error for RequestError {}
+
+impl From<RequestError> for String {
+    fn from(value: RequestError) -> Self {
+        value.to_string()
+    }
+}
+
 pub fn get_client(jwt: &Option<String>) -> reqwest::Client {
     if let Some(jwt) = jwt {
         let mut headers = HeaderMap::new();
@@ -186,7 +202,7 @@ pub async fn request_ochat_server<T: DeserializeOwned, Json: Serialize>(
     url: &str,
     body: &Json,
     request_type: RequestType,
-) -> Result<T, String> {
+) -> Result<T, RequestError> {
     let request = get_client(jwt);
 
     let request = match request_type {
@@ -196,15 +212,27 @@ pub async fn request_ochat_server<T: DeserializeOwned, Json: Serialize>(
         RequestType::Delete => request.delete(url),
     };
 
+    let response = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| RequestError::Connection(e.to_string()))?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        return Err(RequestError::Server(
+            serde_json::from_str::<ErrorBody>(&text)
+                .unwrap_or_else(|_| ErrorBody::new(ErrorCode::from_status(status.as_u16()), text)),
+        ));
+    }
+
     serde_json::from_value(
-        request
-            .json(body)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?
+        response
             .json()
             .await
-            .map_err(|e| e.to_string())?,
+            .map_err(|e| RequestError::Decode(e.to_string()))?,
     )
-    .map_err(|e| e.to_string())
+    .map_err(|e| RequestError::Decode(e.to_string()))
 }
@@ -1,12 +1,10 @@
-use crate::data::{Request, RequestType};
+use crate::data::Request;
 use ochat_types::version::Version;
 use serde_json::Value;
 
 impl Versions {
     pub async fn get_server(req: Request) -> Result<Version, String> {
-        let version = req
-            .make_request::<String, ()>("version/", &(), RequestType::Get)
-            .await?;
+        let version = req.version().await?;
         let version: Vec<String> = version.split(".").map(|x| x.to_string()).collect();
         Ok(Version {
             major: version
@@ -32,6 +32,11 @@ pub fn save_token(token: &Token) {
     }
 }
 
+pub fn delete_token() {
+    let path = get_path_settings(TOKEN_PATH.to_string());
+    let _ = fs::remove_file(path);
+}
+
 pub fn load_token() -> Result<Token, String> {
     let path = get_path_settings(TOKEN_PATH.to_string());
     load_from_file(&path)
@@ -16,13 +16,13 @@ use iced::{
 };
 use iced_selection::{markdown, text};
 use ochat_common::{
-    data::{Data, RequestType, versions::Versions},
+    data::{Data, versions::Versions},
     load_token,
 };
 use ochat_types::{
     ServerFeatures,
     providers::{hf::HFPullModelStreamResult, ollama::OllamaPullModelStreamResult},
-    settings::{Settings, SettingsData},
+    settings::SettingsData,
 };
 use std::{
     collections::BTreeMap,
@@ -40,8 +40,8 @@ use crate::{
             HomePage,
             panes::{
                 data::{
-                    HomePaneSharedData, MessageMk, ModelsData, OptionsData, PromptsData, ViewFile,
-                    ViewFileType,
+                    AssistantsData, HomePaneSharedData, MessageMk, ModelsData, OptionsData,
+                    PromptsData, ViewFile, ViewFileType,
                 },
                 view::{
                     HomePaneViewData, HomePaneViewMessage, chat::ChatsView, editor::EditorView,
@@ -169,9 +169,11 @@ pub enum Message {
 pub enum CacheMessage {
     ResetSideBarItems,
     AddMessage(MessageMk),
+    InsertMessageAbove(String, MessageMk),
     SetModels(ModelsData),
     SetPrompts(PromptsData),
     SetOptions(OptionsData),
+    SetAssistants(AssistantsData),
     SetSideBarItems(SideBarItems),
     SetSettings(SettingsData),
     ExpandFile(ViewFile),
@@ -193,6 +195,23 @@ impl CacheMessage {
                     .0
                     .insert(x.base.id.key().to_string(), x);
             }
+            Self::InsertMessageAbove(child, x) => {
+                let id = x.base.id.key().to_string();
+
+                for message in app.cache.home_shared.messages.0.values_mut() {
+                    for y in message.base.children.iter_mut().filter(|y| **y == child) {
+                        *y = id.clone();
+                    }
+                }
+
+                for view in app.view_data.home.chats.values_mut() {
+                    if let Some(i) = view.messages.iter().position(|y| *y == child) {
+                        view.messages.insert(i, id.clone());
+                    }
+                }
+
+                app.cache.home_shared.messages.0.insert(id, x);
+            }
             Self::SetVersions(x) => {
                 app.cache.versions = x;
             }
@@ -205,6 +224,9 @@ impl CacheMessage {
             Self::SetOptions(x) => {
                 app.cache.home_shared.options = x;
             }
+            Self::SetAssistants(x) => {
+                app.cache.home_shared.assistants = x;
+            }
             Self::SetServerFeatures(x) => {
                 app.cache.server_features = x;
             }
@@ -308,22 +330,16 @@ impl Application {
             Task::done(Message::Cache(CacheMessage::ResetSideBarItems)),
             Task::future(async {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Settings, ()>("settings/", &(), RequestType::Get)
-                    .await
-                {
+                match req.get_settings().await {
                     Ok(x) => Message::Cache(CacheMessage::SetSettings(x.into())),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Task::future(async {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Vec<ServerFeatures>, ()>("features/", &(), RequestType::Get)
-                    .await
-                {
+                match req.features().await {
                     Ok(x) => Message::Cache(CacheMessage::SetServerFeatures(x.into())),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Task::future(async {
@@ -342,6 +358,12 @@ impl Application {
                     Err(e) => Message::Err(e),
                 }
             }),
+            Task::future(async {
+                match AssistantsData::get().await {
+                    Ok(x) => Message::Cache(CacheMessage::SetAssistants(x)),
+                    Err(e) => Message::Err(e),
+                }
+            }),
             Task::future(async {
                 match ModelsData::get(None).await {
                     Ok(x) => Message::Cache(CacheMessage::SetModels(x)),
@@ -4,10 +4,7 @@ use iced::{
     widget::{button, center, column, container, row, rule, scrollable, space, text_input},
 };
 use iced_selection::text;
-use ochat_common::{
-    data::{Data, RequestType},
-    save_token,
-};
+use ochat_common::{data::Data, save_token};
 use ochat_types::{
     WORD_ART,
     user::{SigninData, SignupData, Token},
@@ -123,16 +120,9 @@ impl AuthMessage {
 
                         Task::future(async move {
                             let req = DATA.read().unwrap().to_request();
-                            match req
-                                .make_request::<Token, SigninData>(
-                                    "signin/",
-                                    &data,
-                                    RequestType::Post,
-                                )
-                                .await
-                            {
+                            match req.signin(&data).await {
                                 Ok(jwt) => Message::Auth(AuthMessage::SignedIn(jwt.token)),
-                                Err(e) => Message::Err(e),
+                                Err(e) => Message::Err(e.to_string()),
                             }
                         })
                     }
@@ -161,16 +151,9 @@ impl AuthMessage {
 
                         Task::future(async move {
                             let req = DATA.read().unwrap().to_request();
-                            match req
-                                .make_request::<Token, SignupData>(
-                                    "signup/",
-                                    &data,
-                                    RequestType::Post,
-                                )
-                                .await
-                            {
+                            match req.signup(&data).await {
                                 Ok(jwt) => Message::Auth(AuthMessage::SignedIn(jwt.token)),
-                                Err(e) => Message::Err(e),
+                                Err(e) => Message::Err(e.to_string()),
                             }
                         })
                     }
@@ -6,7 +6,7 @@ use crate::{
         PageMessage,
         home::{
             HomePaneType,
-            panes::PaneMessage,
+            panes::{PaneMessage, data::PromptsData},
             sidebar::{DragItem, SIDEBAR_ROOT_ID, SideBarItems},
         },
     },
@@ -14,10 +14,11 @@ use crate::{
 };
 use iced::{Point, Rectangle, Task, widget::Id as WidgetId, window};
 use iced_drop::zones_on_point;
-use ochat_common::data::RequestType;
+use ochat_common::data::client::Required;
 use ochat_types::{
-    chats::{Chat, ChatData, previews::Preview},
-    folders::{Folder, FolderData, FolderDataBuilder, FolderNameData},
+    chats::{ChatData, export::ExportFormat, import::ImportDataBuilder},
+    folders::{FolderDataBuilder, FolderNameData},
+    trash::TrashKind,
 };
 
 #[derive(Debug, Clone)]
@@ -34,6 +35,7 @@ pub enum HomeMessage {
     DropZones(DragItem, Vec<(WidgetId, Rectangle)>),
     CancelDrag,
     SearchItems(InputMessage),
+    JumpToMessage(String, String),
     SetItems(SideBarItems),
     ExpandItem(String),
     ButtonExpandItem(String),
@@ -44,11 +46,16 @@ pub enum HomeMessage {
     NewFolderToFolder(String),
     FavChat(String),
     ArchiveChat(String),
+    ExportChat(String),
+    ImportChats,
     RemoveChatFromFolder(String, String),
     RemoveFolderFromFolder(String),
     NewFolder,
     SplitDrag(f32),
     DeleteItem(String),
+    RestoreTrash(TrashKind, String),
+    PurgeTrash(TrashKind, String),
+    EmptyTrash,
     Pane(PaneMessage),
     CollapseSideBar,
 }
@@ -104,15 +111,11 @@ impl HomeMessage {
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
                     match req
-                        .make_request::<Folder, FolderNameData>(
-                            &format!("folder/{}/name/", folder_id),
-                            &FolderNameData { name: value },
-                            RequestType::Put,
-                        )
+                        .update_folder_name(&folder_id, &FolderNameData { name: value })
                         .await
                     {
                         Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -228,6 +231,15 @@ impl HomeMessage {
                     }
                 })
             }
+            Self::JumpToMessage(chat, message) => {
+                app.get_home_page(&id).unwrap().panes.jump = Some(message);
+                Task::done(Message::Window(WindowMessage::Page(
+                    id,
+                    PageMessage::Home(HomeMessage::Pane(PaneMessage::Pick(
+                        HomePickingType::ReplaceChat(chat),
+                    ))),
+                )))
+            }
             Self::SetItems(items) => {
                 app.get_home_page(&id).unwrap().side_bar.items = items;
                 Task::none()
@@ -239,50 +251,50 @@ impl HomeMessage {
             }
             Self::DeleteItem(x) => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Option<Preview>, ()>(
-                        &format!("chat/{}", x),
-                        &(),
-                        RequestType::Delete,
-                    )
-                    .await
-                {
+                match req.delete_chat(&x).await {
                     Ok(Some(_)) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                    _ => match req
-                        .make_request::<Option<Folder>, ()>(
-                            &format!("folder/{}", x),
-                            &(),
-                            RequestType::Delete,
-                        )
-                        .await
-                    {
+                    _ => match req.delete_folder(&x).await {
                         Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                        Err(e) => Message::Err(e.to_string()),
+                    },
+                }
+            }),
+            Self::RestoreTrash(kind, x) => Task::future(async move {
+                let req = DATA.read().unwrap().to_request();
+                match req.restore_trash(&kind, &x).await {
+                    Ok(_) if kind == TrashKind::Prompt => match PromptsData::get(None).await {
+                        Ok(prompts) => Message::Batch(vec![
+                            Message::Cache(crate::CacheMessage::SetPrompts(prompts)),
+                            Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                        ]),
                         Err(e) => Message::Err(e),
                     },
+                    Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                    Err(e) => Message::Err(e.to_string()),
+                }
+            }),
+            Self::PurgeTrash(kind, x) => Task::future(async move {
+                let req = DATA.read().unwrap().to_request();
+                match req.purge_trash(&kind, &x).await {
+                    Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                    Err(e) => Message::Err(e.to_string()),
+                }
+            }),
+            Self::EmptyTrash => Task::future(async move {
+                let req = DATA.read().unwrap().to_request();
+                match req.empty_trash().await {
+                    Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::Pane(x) => x.handle(app, id),
             Self::NewChat => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Chat, ChatData>(
-                        "chat/",
-                        &ChatData::default(),
-                        RequestType::Post,
-                    )
-                    .await
-                {
+                match req.create_chat(&ChatData::default()).await.required() {
                     Ok(chat) => Message::Batch(vec![
-                        match req
-                            .make_request::<Preview, ()>(
-                                &format!("preview/{}", chat.id.key().to_string()),
-                                &(),
-                                RequestType::Put,
-                            )
-                            .await
-                        {
+                        match req.update_preview(&chat.id.key().to_string()).await {
                             Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         },
                         Message::Window(WindowMessage::Page(
                             id,
@@ -291,86 +303,111 @@ impl HomeMessage {
                             ))),
                         )),
                     ]),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::FavChat(x) => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Folder, ()>(
-                        &format!("folder/fav/chat/{}", x),
-                        &(),
-                        RequestType::Put,
-                    )
-                    .await
-                {
+                match req.fav_chat(&x).await {
                     Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::ArchiveChat(x) => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Folder, ()>(
-                        &format!("folder/archive/chat/{}", x),
-                        &(),
-                        RequestType::Put,
-                    )
+                match req.archive_chat(&x).await {
+                    Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                    Err(e) => Message::Err(e.to_string()),
+                }
+            }),
+            Self::ExportChat(x) => Task::future(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .set_file_name(format!("chat-{}.md", x))
+                    .add_filter("Markdown", &["md"])
+                    .add_filter("HTML", &["html"])
+                    .add_filter("JSON", &["json"])
+                    .save_file()
                     .await
+                else {
+                    return Message::None;
+                };
+
+                let format = match file
+                    .path()
+                    .extension()
+                    .map(|x| x.to_string_lossy().parse::<ExportFormat>())
                 {
-                    Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                    Err(e) => Message::Err(e),
+                    Some(Ok(x)) => x,
+                    _ => ExportFormat::Markdown,
+                };
+
+                let req = DATA.read().unwrap().to_request();
+                match req.export_chat(&x, &format).await {
+                    Ok(export) => match tokio::fs::write(file.path(), export).await {
+                        Ok(_) => Message::None,
+                        Err(e) => Message::Err(e.to_string()),
+                    },
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
-            Self::RemoveChatFromFolder(folder, chat) => Task::future(async move {
+            Self::ImportChats => Task::future(async move {
+                let Some(file) = rfd::AsyncFileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .pick_file()
+                    .await
+                else {
+                    return Message::None;
+                };
+
+                let data = match serde_json::from_slice(&file.read().await) {
+                    Ok(x) => x,
+                    Err(e) => return Message::Err(e.to_string()),
+                };
+
                 let req = DATA.read().unwrap().to_request();
                 match req
-                    .make_request::<Folder, ()>(
-                        &format!("folder/{}/chat/{}", folder, chat),
-                        &(),
-                        RequestType::Delete,
-                    )
+                    .import_chats(&ImportDataBuilder::default().data(data).build().unwrap())
                     .await
                 {
+                    Ok(result) if result.failed > 0 => Message::Batch(vec![
+                        Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                        Message::Err(format!(
+                            "Imported {} chats, {} failed : {}",
+                            result.imported,
+                            result.failed,
+                            result
+                                .chats
+                                .into_iter()
+                                .filter_map(|x| x.error)
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        )),
+                    ]),
+                    Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                    Err(e) => Message::Err(e.to_string()),
+                }
+            }),
+            Self::RemoveChatFromFolder(folder, chat) => Task::future(async move {
+                let req = DATA.read().unwrap().to_request();
+                match req.remove_folder_chat(&folder, &chat).await {
                     Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::RemoveFolderFromFolder(folder) => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Folder, ()>(
-                        &format!("folder/{}/parent/none", folder),
-                        &(),
-                        RequestType::Put,
-                    )
-                    .await
-                {
+                match req.unparent_folder(&folder).await {
                     Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::NewChatToFolder(x) => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
-                match req
-                    .make_request::<Chat, ChatData>(
-                        "chat/",
-                        &ChatData::default(),
-                        RequestType::Post,
-                    )
-                    .await
-                {
+                match req.create_chat(&ChatData::default()).await.required() {
                     Ok(chat) => Message::Batch(vec![
-                        match req
-                            .make_request::<Option<Folder>, ()>(
-                                &format!("folder/{}/chat/{}", x, chat.id.key().to_string()),
-                                &(),
-                                RequestType::Put,
-                            )
-                            .await
-                        {
+                        match req.add_folder_chat(&x, &chat.id.key().to_string()).await {
                             Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         },
                         Message::Window(WindowMessage::Page(
                             id,
@@ -379,45 +416,34 @@ impl HomeMessage {
                             ))),
                         )),
                     ]),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::NewFolderToFolder(x) => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
                 match req
-                    .make_request::<Folder, FolderData>(
-                        "folder/",
-                        &FolderDataBuilder::default().build().unwrap_or_default(),
-                        RequestType::Post,
-                    )
+                    .create_folder(&FolderDataBuilder::default().build().unwrap_or_default())
                     .await
+                    .required()
                 {
                     Ok(folder) => match req
-                        .make_request::<Option<Folder>, ()>(
-                            &format!("folder/{}/parent/{}", folder.id.key().to_string(), x),
-                            &(),
-                            RequestType::Put,
-                        )
+                        .set_folder_parent(&folder.id.key().to_string(), &x)
                         .await
                     {
                         Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     },
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::NewFolder => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
                 match req
-                    .make_request::<Folder, FolderData>(
-                        "folder/",
-                        &FolderDataBuilder::default().build().unwrap_or_default(),
-                        RequestType::Post,
-                    )
+                    .create_folder(&FolderDataBuilder::default().build().unwrap_or_default())
                     .await
                 {
                     Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::Dropped(from, to) => {
@@ -426,28 +452,14 @@ impl HomeMessage {
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
                     if is_folder {
-                        match req
-                            .make_request::<Option<Folder>, ()>(
-                                &format!("folder/{}/parent/{}", to, from),
-                                &(),
-                                RequestType::Put,
-                            )
-                            .await
-                        {
+                        match req.set_folder_parent(&to, &from).await {
                             Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         }
                     } else {
-                        match req
-                            .make_request::<Option<Folder>, ()>(
-                                &format!("folder/{}/chat/{}", to, from),
-                                &(),
-                                RequestType::Put,
-                            )
-                            .await
-                        {
+                        match req.add_folder_chat(&to, &from).await {
                             Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         }
                     }
                 })
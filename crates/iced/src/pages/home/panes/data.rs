@@ -1,8 +1,10 @@
-use crate::{DATA, RequestType};
+use crate::DATA;
 use base64_stream::base64::{Engine, prelude::BASE64_STANDARD};
 use iced::widget::markdown;
+use ochat_common::data::client::Required;
 use ochat_types::{
-    chats::{Chat, messages::Message},
+    assistants::Assistant,
+    chats::messages::Message,
     files::{B64File, FileType},
     options::{
         GenOptions,
@@ -10,7 +12,7 @@ use ochat_types::{
     },
     prompts::Prompt,
     providers::{
-        hf::{HFModel, HFPullModelStreamResult},
+        hf::{HFModel, HFPullModelStreamResult, ModelType},
         ollama::{OllamaModelsInfo, OllamaPullModelStreamResult},
     },
     settings::SettingsProvider,
@@ -25,6 +27,7 @@ pub struct HomePaneSharedData {
     pub tools: ToolsData,
     pub prompts: PromptsData,
     pub options: OptionsData,
+    pub assistants: AssistantsData,
     pub messages: MessagesData,
 }
 
@@ -125,14 +128,7 @@ impl MessageMk {
         let req = DATA.read().unwrap().to_request();
 
         for file in files {
-            if let Ok(Some(file)) = req
-                .make_request::<Option<B64File>, ()>(
-                    &format!("file/{}", file.trim()),
-                    &(),
-                    RequestType::Get,
-                )
-                .await
-            {
+            if let Ok(Some(file)) = req.get_file(file.trim()).await {
                 message.files.push(file.into());
             }
         }
@@ -149,6 +145,34 @@ impl MessagesData {
         }
     }
 
+    /// The branch from the root through the target message, continued along the default children.
+    pub fn get_msgs_to(&self, root_id: String, target: &str) -> Option<Vec<String>> {
+        let mut stack = vec![vec![root_id]];
+
+        while let Some(mut path) = stack.pop() {
+            let last = path.last().unwrap().trim().to_string();
+
+            if last == target.trim() {
+                let _ = path.pop();
+                path.append(&mut self.get_default_msgs_from_root(last));
+                return Some(path);
+            }
+
+            if let Some(msg) = self.0.get(&last) {
+                for child in msg.base.children.iter().rev() {
+                    let child = child.trim().to_string();
+                    if !path.contains(&child) {
+                        let mut next = path.clone();
+                        next.push(child);
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
     pub fn get_default_msgs_from_root(&self, root_id: String) -> Vec<String> {
         let mut list = vec![root_id];
 
@@ -176,10 +200,7 @@ impl MessagesData {
     pub async fn load_all_messages_from_chat(chat_id: String) -> Result<Vec<MessageMk>, String> {
         let req = DATA.read().unwrap().to_request();
 
-        let chat: Chat = req
-            .make_request(&format!("chat/{}", chat_id), &(), RequestType::Get)
-            .await
-            .map_err(|e| e.to_string())?;
+        let chat = req.get_chat(&chat_id).await.required()?;
 
         if let Some(x) = chat.root {
             Self::load_all_messages_from_root(x).await
@@ -191,11 +212,7 @@ impl MessagesData {
     pub async fn load_all_messages_from_root(root_msg: String) -> Result<Vec<MessageMk>, String> {
         let req = DATA.read().unwrap().to_request();
 
-        let mut messages: Vec<Message> = if let Ok(x) = req
-            .make_request(&format!("message/{}", root_msg), &(), RequestType::Get)
-            .await
-            .map_err(|e| e.to_string())
-        {
+        let mut messages: Vec<Message> = if let Ok(Some(x)) = req.get_message(&root_msg).await {
             vec![x]
         } else {
             Vec::new()
@@ -203,11 +220,7 @@ impl MessagesData {
 
         messages.append(
             &mut req
-                .make_request(
-                    &format!("message/parent/{}/all/", root_msg),
-                    &(),
-                    RequestType::Get,
-                )
+                .get_all_messages_from_root(&root_msg)
                 .await
                 .map_err(|e| e.to_string())?,
         );
@@ -233,6 +246,9 @@ pub struct ModelsData {
 #[derive(Debug, Clone, Default)]
 pub struct PromptsData(pub Vec<Prompt>);
 
+#[derive(Debug, Clone, Default)]
+pub struct AssistantsData(pub Vec<Assistant>);
+
 #[derive(Debug, Clone, Default)]
 pub struct ToolsData();
 
@@ -314,64 +330,40 @@ impl ModelsData {
         let req = DATA.read().unwrap().to_request();
 
         Ok(Self {
-            ollama: req
-                .make_request::<Vec<OllamaModelsInfo>, ()>(
-                    &if let Some(search) = &search {
-                        format!("provider/ollama/model/search/{}", search)
-                    } else {
-                        "provider/ollama/model/all/".to_string()
-                    },
-                    &(),
-                    RequestType::Get,
-                )
-                .await
-                .map(|x| {
-                    if x.len() > 150 {
-                        x[0..=150].to_vec()
-                    } else {
-                        x
-                    }
-                })
-                .map_err(|e| e.to_string())?,
-            hf_text: req
-                .make_request::<Vec<HFModel>, ()>(
-                    &if let Some(search) = &search {
-                        format!("provider/hf/text/model/search/{}", search)
-                    } else {
-                        "provider/hf/text/model/all/".to_string()
-                    },
-                    &(),
-                    RequestType::Get,
-                )
-                .await
-                .map(|x| if x.len() > 75 { x[0..=75].to_vec() } else { x })
-                .map_err(|e| e.to_string())?,
-            hf_stt: req
-                .make_request::<Vec<HFModel>, ()>(
-                    &if let Some(search) = &search {
-                        format!("provider/hf/stt/model/search/{}", search)
-                    } else {
-                        "provider/hf/stt/model/all/".to_string()
-                    },
-                    &(),
-                    RequestType::Get,
-                )
-                .await
-                .map(|x| if x.len() > 75 { x[0..=75].to_vec() } else { x })
-                .map_err(|e| e.to_string())?,
-            hf_tts: req
-                .make_request::<Vec<HFModel>, ()>(
-                    &if let Some(search) = &search {
-                        format!("provider/hf/tts/model/search/{}", search)
-                    } else {
-                        "provider/hf/tts/model/all/".to_string()
-                    },
-                    &(),
-                    RequestType::Get,
-                )
-                .await
-                .map(|x| if x.len() > 75 { x[0..=75].to_vec() } else { x })
-                .map_err(|e| e.to_string())?,
+            ollama: if let Some(search) = &search {
+                req.search_ollama_models(search).await
+            } else {
+                req.list_all_ollama_models().await
+            }
+            .map(|x| {
+                if x.len() > 150 {
+                    x[0..=150].to_vec()
+                } else {
+                    x
+                }
+            })
+            .map_err(|e| e.to_string())?,
+            hf_text: if let Some(search) = &search {
+                req.search_hf_models(&ModelType::Text, search).await
+            } else {
+                req.list_all_hf_models(&ModelType::Text).await
+            }
+            .map(|x| if x.len() > 75 { x[0..=75].to_vec() } else { x })
+            .map_err(|e| e.to_string())?,
+            hf_stt: if let Some(search) = &search {
+                req.search_hf_models(&ModelType::Stt, search).await
+            } else {
+                req.list_all_hf_models(&ModelType::Stt).await
+            }
+            .map(|x| if x.len() > 75 { x[0..=75].to_vec() } else { x })
+            .map_err(|e| e.to_string())?,
+            hf_tts: if let Some(search) = &search {
+                req.search_hf_models(&ModelType::Tts, search).await
+            } else {
+                req.list_all_hf_models(&ModelType::Tts).await
+            }
+            .map(|x| if x.len() > 75 { x[0..=75].to_vec() } else { x })
+            .map_err(|e| e.to_string())?,
         })
     }
 }
@@ -380,28 +372,18 @@ impl OptionsData {
     pub async fn get(search: Option<String>) -> Result<Self, String> {
         let req = DATA.read().unwrap().to_request();
 
-        let options: Vec<GenOptions> = req
-            .make_request(
-                &if let Some(search) = search {
-                    format!("option/search/{}", search)
-                } else {
-                    "option/all/".to_string()
-                },
-                &(),
-                RequestType::Get,
-            )
-            .await
-            .map_err(|e| e.to_string())?;
+        let options = if let Some(search) = search {
+            req.search_gen_options(&search).await
+        } else {
+            req.list_all_gen_options().await
+        }
+        .map_err(|e| e.to_string())?;
 
         let mut value = Vec::new();
 
         for option in options {
-            let models: Vec<GenModelRelationship> = req
-                .make_request(
-                    &format!("option/{}/all/", option.id.key().to_string()),
-                    &(),
-                    RequestType::Get,
-                )
+            let models = req
+                .get_gen_models_from_options(&option.id.key().to_string())
                 .await
                 .map_err(|e| e.to_string())?;
 
@@ -415,21 +397,34 @@ impl OptionsData {
     }
 }
 
+impl AssistantsData {
+    pub async fn get() -> Result<Self, String> {
+        let req = DATA.read().unwrap().to_request();
+
+        Ok(Self(
+            req.list_all_assistants().await.map_err(|e| e.to_string())?,
+        ))
+    }
+
+    /// Finds the assistant a message was addressed to with a leading `@name`.
+    pub fn mentioned(&self, text: &str) -> Option<&Assistant> {
+        let mention = text.trim_start().strip_prefix('@')?;
+        let mention = mention.split_whitespace().next()?.to_lowercase();
+
+        self.0.iter().find(|x| x.mention() == mention)
+    }
+}
+
 impl PromptsData {
     pub async fn get(search: Option<String>) -> Result<Self, String> {
         let req = DATA.read().unwrap().to_request();
 
         Ok(Self(
-            req.make_request::<Vec<Prompt>, ()>(
-                &if let Some(search) = search {
-                    format!("prompt/search/{}", search)
-                } else {
-                    "prompt/all/".to_string()
-                },
-                &(),
-                RequestType::Get,
-            )
-            .await
+            if let Some(search) = search {
+                req.search_prompts(&search).await
+            } else {
+                req.list_all_prompts().await
+            }
             .map_err(|e| e.to_string())?,
         ))
     }
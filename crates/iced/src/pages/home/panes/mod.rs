@@ -15,15 +15,16 @@ use crate::{
             },
         },
     },
+    subscriptions::SubMessage,
     windows::message::WindowMessage,
 };
 use iced::{
     Task,
-    widget::{pane_grid, text_editor},
+    widget::{operation, pane_grid, scrollable, text_editor},
     window,
 };
-use ochat_common::data::RequestType;
-use ochat_types::chats::{Chat, ChatData, previews::Preview};
+use ochat_common::data::client::Required;
+use ochat_types::chats::{Chat, ChatData};
 use std::collections::HashMap;
 
 pub mod data;
@@ -181,6 +182,8 @@ pub struct HomePanes {
     pub focus: Option<pane_grid::Pane>,
     pub panes: pane_grid::State<HomePaneTypeWithId>,
     pub pick: Option<HomePickingType>,
+    /// Message to scroll to once the next chat is loaded.
+    pub jump: Option<String>,
 }
 
 impl HomePanes {
@@ -192,6 +195,7 @@ impl HomePanes {
             focus: Some(focus.clone()),
             panes,
             pick: None,
+            jump: None,
         }
     }
 }
@@ -232,26 +236,16 @@ impl PaneMessage {
             Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
 
-                if let Ok(chats) = req
-                    .make_request::<Vec<Chat>, ()>("chat/all/", &(), RequestType::Get)
-                    .await
-                {
+                if let Ok(chats) = req.list_all_chats().await {
                     if chats.len() > 0 {
                         return Message::Batch(vec![
                             match req
-                                .make_request::<Preview, ()>(
-                                    &format!(
-                                        "preview/{}",
-                                        chats.first().unwrap().id.key().to_string()
-                                    ),
-                                    &(),
-                                    RequestType::Put,
-                                )
+                                .update_preview(&chats.first().unwrap().id.key().to_string())
                                 .await
                                 .map(|_| Message::Cache(CacheMessage::ResetSideBarItems))
                             {
                                 Ok(x) => x,
-                                Err(e) => Message::Err(e),
+                                Err(e) => Message::Err(e.to_string()),
                             },
                             Message::Window(WindowMessage::Page(
                                 id,
@@ -264,25 +258,11 @@ impl PaneMessage {
                     }
                 }
 
-                match req
-                    .make_request::<Chat, ChatData>(
-                        "chat/",
-                        &ChatData::default(),
-                        RequestType::Post,
-                    )
-                    .await
-                {
+                match req.create_chat(&ChatData::default()).await.required() {
                     Ok(chat) => Message::Batch(vec![
-                        match req
-                            .make_request::<Preview, ()>(
-                                &format!("preview/{}", chat.id.key().to_string()),
-                                &(),
-                                RequestType::Put,
-                            )
-                            .await
-                        {
+                        match req.update_preview(&chat.id.key().to_string()).await {
                             Ok(_) => Message::Cache(CacheMessage::ResetSideBarItems),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         },
                         Message::Window(WindowMessage::Page(
                             id,
@@ -292,7 +272,7 @@ impl PaneMessage {
                             ))),
                         )),
                     ]),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             })
         }
@@ -343,7 +323,9 @@ impl PaneMessage {
                 Task::none()
             }
             Self::UnPick => {
-                app.get_home_page(&id).unwrap().panes.pick = None;
+                let page = app.get_home_page(&id).unwrap();
+                page.panes.pick = None;
+                page.panes.jump = None;
                 Task::none()
             }
             Self::Dragged(pane_grid::DragEvent::Dropped { pane, target }) => {
@@ -531,12 +513,9 @@ impl PaneMessage {
             PaneMessage::ReplaceChat(pane, chat_id) => Task::future(async move {
                 let req = DATA.read().unwrap().to_request();
 
-                let chat: Chat = match req
-                    .make_request(&format!("chat/{}", chat_id), &(), RequestType::Get)
-                    .await
-                {
+                let chat = match req.get_chat(&chat_id).await.required() {
                     Ok(x) => x,
-                    Err(e) => return Message::Err(e),
+                    Err(e) => return Message::Err(e.to_string()),
                 };
 
                 let msgs = if let Some(x) = chat.root.clone() {
@@ -554,17 +533,33 @@ impl PaneMessage {
                 ))
             }),
             PaneMessage::ChatLoaded(pane, chat, messages) => {
+                let loaded: Vec<String> = messages
+                    .iter()
+                    .map(|x| x.base.id.key().to_string())
+                    .collect();
                 app.cache.home_shared.messages.push(messages);
+                let jump = app.get_home_page(&id).unwrap().panes.jump.take();
 
                 let messages = if let Some(root) = chat.root.clone() {
-                    app.cache
-                        .home_shared
-                        .messages
-                        .get_default_msgs_from_root(root)
+                    jump.as_ref()
+                        .and_then(|x| app.cache.home_shared.messages.get_msgs_to(root.clone(), x))
+                        .unwrap_or_else(|| {
+                            app.cache
+                                .home_shared
+                                .messages
+                                .get_default_msgs_from_root(root)
+                        })
                 } else {
                     Vec::new()
                 };
 
+                let jump = jump.and_then(|x| {
+                    messages
+                        .iter()
+                        .position(|y| y == x.trim())
+                        .map(|i| (i, messages.len()))
+                });
+
                 app.view_data.counter += 1;
                 let count = app.view_data.counter;
 
@@ -573,7 +568,9 @@ impl PaneMessage {
                     ChatsView {
                         window_id: Some(id),
                         input: text_editor::Content::default(),
-                        models: if let Some(model) =
+                        models: if !chat.models.is_empty() {
+                            chat.models.clone()
+                        } else if let Some(model) =
                             app.cache.client_settings.default_provider.clone()
                         {
                             vec![model]
@@ -584,14 +581,30 @@ impl PaneMessage {
                         } else {
                             Vec::new()
                         },
-                        tools: app.cache.client_settings.default_tools.clone(),
+                        tools: if chat.tools.is_empty() {
+                            app.cache.client_settings.default_tools.clone()
+                        } else {
+                            chat.tools.clone()
+                        },
                         files: Vec::new(),
                         recording: None,
                         start: 0,
+                        assistant: app
+                            .cache
+                            .home_shared
+                            .assistants
+                            .0
+                            .iter()
+                            .find(|x| {
+                                chat.models == [x.model.clone()]
+                                    && chat.system_prompt == x.system_prompt
+                            })
+                            .cloned(),
                         messages,
                         chat,
                         edits: HashMap::new(),
                         expanded_messages: Vec::new(),
+                        expanded_metrics: Vec::new(),
                         prompts: PromptsData::default(),
                         selected_prompt: None,
                     },
@@ -605,7 +618,38 @@ impl PaneMessage {
 
                 page.panes.pick = None;
                 page.panes.focus = Some(pane);
-                Task::none()
+
+                // Pick up answers that are still being generated on the server.
+                let attach = Task::future(async move {
+                    let req = DATA.read().unwrap().to_request();
+
+                    match req.list_text_jobs().await {
+                        Ok(jobs) => Message::Batch(
+                            jobs.into_iter()
+                                .filter(|x| loaded.contains(&x.message))
+                                .map(|x| {
+                                    Message::Subscription(SubMessage::AttachMessage(x.message))
+                                })
+                                .collect(),
+                        ),
+                        Err(e) => Message::Err(e.to_string()),
+                    }
+                });
+
+                match jump {
+                    // The chat is anchored to the bottom, so the offset counts up from the last message.
+                    Some((index, len)) => Task::batch([
+                        operation::snap_to(
+                            format!("chat-{}", count),
+                            scrollable::RelativeOffset {
+                                x: 0.0,
+                                y: 1.0 - index as f32 / len.saturating_sub(1).max(1) as f32,
+                            },
+                        ),
+                        attach,
+                    ]),
+                    None => attach,
+                }
             }
         }
     }
@@ -3,7 +3,6 @@ use iced::{
     widget::{column, container, pick_list, rule, scrollable},
 };
 use iced_selection::text;
-use ochat_common::data::RequestType;
 use ochat_types::{
     chats::messages::Role,
     generation::{
@@ -129,6 +128,8 @@ impl CallViewMessage {
                         text: txt,
                         files: Vec::new(),
                         role: Role::User,
+                        id: None,
+                        func_calls: Vec::new(),
                     });
 
                     ChatQueryData {
@@ -137,23 +138,17 @@ impl CallViewMessage {
                         tools: view.tools.clone(),
                         provider: view.model.as_ref().unwrap().provider.clone(),
                         model: view.model.as_ref().unwrap().model.clone(),
+                        options: None,
                     }
                 };
 
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<ChatResponse, ChatQueryData>(
-                            "generation/text/run/",
-                            &query,
-                            RequestType::Get,
-                        )
-                        .await
-                    {
+                    match req.run_text(&query).await {
                         Ok(message) => Message::HomePaneView(HomePaneViewMessage::Call(
                             CallViewMessage::ResponseGenerated(message),
                         )),
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -172,6 +167,8 @@ impl CallViewMessage {
                         text: response.content.clone(),
                         files: Vec::new(),
                         role: Role::AI,
+                        id: None,
+                        func_calls: Vec::new(),
                     });
 
                     TtsQueryData {
@@ -182,18 +179,11 @@ impl CallViewMessage {
 
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<TtsResponse, TtsQueryData>(
-                            "generation/tts/run/",
-                            &query,
-                            RequestType::Get,
-                        )
-                        .await
-                    {
+                    match req.run_tts(&query).await {
                         Ok(data) => Message::HomePaneView(HomePaneViewMessage::Call(
                             CallViewMessage::StartPlaying(data),
                         )),
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -13,7 +13,7 @@ use crate::{
         },
     },
     style,
-    subscriptions::SubMessage,
+    subscriptions::{SubMessage, message::MessageGen},
     utils::get_path_assets,
     windows::message::WindowMessage,
 };
@@ -23,7 +23,7 @@ use iced::{
     clipboard,
     widget::{
         button, center, column, container, image, lazy, mouse_area, pick_list, row, rule,
-        scrollable, space, stack, svg, text_editor,
+        scrollable, space, stack, svg, text_editor, text_input,
     },
     window,
 };
@@ -32,19 +32,19 @@ use iced_selection::{markdown, text};
 use ochat_common::{
     convert_file_to_b64, convert_image_to_b64,
     data::{
-        RequestType,
+        client::Required,
         start::{self, Section},
     },
 };
 use ochat_types::{
+    assistants::Assistant,
     chats::{
-        Chat,
-        messages::{MessageData, MessageDataBuilder, ModelData, Role},
-        previews::Preview,
+        Chat, ChatConfig,
+        messages::{MessageData, MessageDataBuilder, MessageRating, ModelData, Rating, Role},
     },
-    files::{B64File, B64FileData, B64FileDataBuilder, DBFile, FileType},
-    generation::text::{ChatQueryData, ChatQueryMessage},
-    settings::SettingsProvider,
+    files::{B64File, B64FileDataBuilder, FileType},
+    generation::text::{ChatQueryData, ChatQueryMessage, ContextTrim, FunctionCall},
+    settings::{ContextStrategy, SettingsProvider},
 };
 use std::{collections::HashMap, path::Path, sync::Arc};
 
@@ -58,6 +58,9 @@ const IMAGE_FORMATS: [&str; 15] = [
     "tiff", "webp",
 ];
 
+/// Characters of a tool result shown before it gets cut off, scraped pages can be huge.
+const CALL_RESULT_PREVIEW: usize = 2000;
+
 const DOC_FORMATS: [&str; 14] = [
     "pdf", "docx", "doc", "xlsx", "ppt", "pptx", "html", "xml", "txt", "csv", "tsv", "rtf", "odt",
     "md",
@@ -72,11 +75,14 @@ pub struct ChatsView {
     pub models: Vec<SettingsProvider>,
     pub edits: HashMap<String, text_editor::Content>,
     pub expanded_messages: Vec<String>,
+    pub expanded_metrics: Vec<String>,
     pub prompts: PromptsData,
     pub selected_prompt: Option<String>,
     pub tools: Vec<String>,
     pub messages: Vec<String>,
     pub chat: Chat,
+    /// The assistant whose model and settings the chat was last set to.
+    pub assistant: Option<Assistant>,
     pub start: usize,
 }
 
@@ -98,6 +104,10 @@ pub enum ChatsViewMessage {
     Regenerate(String),
     Branch(String),
     Expand(String),
+    ExpandMetrics(String),
+    Rate(String, Rating),
+    UpdateRatingComment(String, String),
+    SubmitRatingComment(String),
     Edit(String),
     EditAction(String, text_editor::Action),
     SubmitEdit(String),
@@ -107,6 +117,7 @@ pub enum ChatsViewMessage {
     PrevMessage(String),
     ChangeModel(usize, SettingsProvider),
     RemoveModel(usize),
+    ApplyAssistant(Assistant),
     ChangeStart(usize),
     OpenCall,
     #[cfg(feature = "sound")]
@@ -294,10 +305,7 @@ impl ChatsViewMessage {
                 Task::batch(files.into_iter().map(|file| {
                     Task::future(async move {
                         let req = DATA.read().unwrap().to_request();
-                        match req
-                            .make_request::<DBFile, B64FileData>(&"file/", &file, RequestType::Post)
-                            .await
-                        {
+                        match req.create_file(&file).await.required() {
                             Ok(x) => Message::HomePaneView(HomePaneViewMessage::Chats(
                                 id,
                                 ChatsViewMessage::FileUploaded(ViewFile::from(B64File {
@@ -308,7 +316,7 @@ impl ChatsViewMessage {
                                     id: x.id,
                                 })),
                             )),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         }
                     })
                 }))
@@ -356,34 +364,18 @@ impl ChatsViewMessage {
 
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<ochat_types::chats::messages::Message, MessageData>(
-                            &if let Some(parent) = &parent {
-                                format!("message/parent/{}", parent)
-                            } else {
-                                "message/".to_string()
-                            },
-                            &user_message,
-                            RequestType::Post,
-                        )
-                        .await
+                    match if let Some(parent) = &parent {
+                        req.create_message_with_parent(parent, &user_message).await
+                    } else {
+                        req.create_message(&user_message).await
+                    }
+                    .required()
                     {
                         Ok(x) => {
                             let msg = if parent.is_none() {
-                                match req
-                                    .make_request::<Chat, ()>(
-                                        &format!(
-                                            "chat/{}/root/{}",
-                                            chat_id,
-                                            x.id.key().to_string()
-                                        ),
-                                        &(),
-                                        RequestType::Put,
-                                    )
-                                    .await
-                                {
+                                match req.set_chat_root(&chat_id, &x.id.key().to_string()).await {
                                     Ok(_) => Message::None,
-                                    Err(e) => Message::Err(e),
+                                    Err(e) => Message::Err(e.to_string()),
                                 }
                             } else {
                                 Message::None
@@ -397,7 +389,7 @@ impl ChatsViewMessage {
                                 msg,
                             ])
                         }
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -448,33 +440,61 @@ impl ChatsViewMessage {
                     })
                     .collect();
 
+                // Mentioning an assistant hands this turn to it instead of the chat's models.
+                let assistant = app
+                    .cache
+                    .home_shared
+                    .assistants
+                    .mentioned(&user_message.base.content)
+                    .cloned();
+                let view = app.get_chats_view(&id).unwrap();
+                let messages = view.with_system_prompt(messages, assistant.as_ref());
+                let (models, tools, options) = match assistant {
+                    Some(x) => (vec![x.model], x.tools, x.options),
+                    None => (
+                        view.models.clone(),
+                        view.tools.clone(),
+                        view.chat.options.clone(),
+                    ),
+                };
                 let req = DATA.read().unwrap().to_request();
-                let tools = app.get_chats_view(&id).unwrap().tools.clone();
-                Task::batch(app.get_chats_view(&id).unwrap().models.clone().into_iter().map(|x| {
+                Task::batch(models.into_iter().map(|x| {
                     let user_message = user_message.base.id.key().to_string();
                     let messages = messages.clone();
                     let req = req.clone();
                     let tools = tools.clone();
+                    let options = options.clone();
                     Task::future(async move {
                         let message = MessageDataBuilder::default()
                             .content(String::new())
                             .role(Role::AI)
-                            .model(Some(ModelData {provider : x.provider.trim().to_string(), model: x.model.clone()}))
+                            .model(Some(ModelData {
+                                provider: x.provider.trim().to_string(),
+                                model: x.model.clone(),
+                            }))
                             .build()
                             .unwrap();
                         match req
-                            .make_request::<ochat_types::chats::messages::Message, MessageData>(
-                                &format!("message/parent/{}", &user_message),
-                                &message,
-                                RequestType::Post,
-                            )
+                            .create_message_with_parent(&user_message, &message)
                             .await
+                            .required()
                         {
                             Ok(message) => Message::HomePaneView(HomePaneViewMessage::Chats(
                                 id,
-                                ChatsViewMessage::AIMessageUploaded(user_message, MessageMk::get(message).await, Some(ChatQueryData { force_disable_tools: false, provider: x.provider.trim().to_string(), model: x.model, tools, messages }))
+                                ChatsViewMessage::AIMessageUploaded(
+                                    user_message,
+                                    MessageMk::get(message).await,
+                                    Some(ChatQueryData {
+                                        force_disable_tools: false,
+                                        provider: x.provider.trim().to_string(),
+                                        model: x.model,
+                                        tools,
+                                        messages,
+                                        options,
+                                    }),
+                                ),
                             )),
-                            Err(e) => Message::Err(e)
+                            Err(e) => Message::Err(e.to_string()),
                         }
                     })
                 }))
@@ -537,6 +557,86 @@ impl ChatsViewMessage {
 
                 Task::none()
             }
+            Self::Rate(message_id, rating) => {
+                let Some(message) = app.cache.home_shared.messages.0.get_mut(&message_id) else {
+                    return Task::none();
+                };
+
+                // Picking the current rating again clears it.
+                message.base.rating = if message
+                    .base
+                    .rating
+                    .as_ref()
+                    .is_some_and(|x| x.rating == rating)
+                {
+                    None
+                } else {
+                    Some(MessageRating {
+                        rating,
+                        comment: message.base.rating.take().and_then(|x| x.comment),
+                    })
+                };
+
+                let rating = message.base.rating.clone();
+
+                Task::future(async move {
+                    let req = DATA.read().unwrap().to_request();
+                    let result = match rating {
+                        Some(rating) => req.rate_message(&message_id, &rating).await,
+                        None => req.remove_message_rating(&message_id).await,
+                    };
+
+                    match result {
+                        Ok(_) => Message::None,
+                        Err(e) => Message::Err(e.to_string()),
+                    }
+                })
+            }
+            Self::UpdateRatingComment(message_id, comment) => {
+                if let Some(rating) = app
+                    .cache
+                    .home_shared
+                    .messages
+                    .0
+                    .get_mut(&message_id)
+                    .and_then(|x| x.base.rating.as_mut())
+                {
+                    rating.comment = Some(comment).filter(|x| !x.is_empty());
+                }
+
+                Task::none()
+            }
+            Self::SubmitRatingComment(message_id) => {
+                let Some(rating) = app
+                    .cache
+                    .home_shared
+                    .messages
+                    .0
+                    .get(&message_id)
+                    .and_then(|x| x.base.rating.clone())
+                else {
+                    return Task::none();
+                };
+
+                Task::future(async move {
+                    let req = DATA.read().unwrap().to_request();
+                    match req.rate_message(&message_id, &rating).await {
+                        Ok(_) => Message::None,
+                        Err(e) => Message::Err(e.to_string()),
+                    }
+                })
+            }
+            Self::ExpandMetrics(x) => {
+                let view = app.get_chats_view(&id).unwrap();
+
+                if view.expanded_metrics.contains(&x) {
+                    let _ = view.expanded_metrics.retain(|y| y != &x);
+                } else {
+                    let _ = view.expanded_metrics.push(x);
+                }
+
+                Task::none()
+            }
             Self::Edit(x) => {
                 let text = app
                     .cache
@@ -583,24 +683,31 @@ impl ChatsViewMessage {
 
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<ochat_types::chats::messages::Message, MessageData>(
-                            &format!("message/{}", message_id),
-                            &message.into(),
-                            RequestType::Put,
-                        )
-                        .await
-                    {
+                    match req.update_message(&message_id, &message.into()).await {
                         Ok(_) => Message::None,
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
             Self::Regenerate(message_id) => {
                 let messages = app.get_chats_view(&id).unwrap().messages.clone();
-                let tools = app.get_chats_view(&id).unwrap().tools.clone();
-                let index = messages.iter().position(|x| x == &message_id).unwrap();
+                let mut index = messages.iter().position(|x| x == &message_id).unwrap();
+
+                // An answer that called tools hangs off its function message, regenerating it
+                // starts again from the user's message.
+                while index > 1
+                    && app
+                        .cache
+                        .home_shared
+                        .messages
+                        .0
+                        .get(&messages[index - 1])
+                        .is_some_and(|x| x.base.role == Role::Function)
+                {
+                    index -= 1;
+                }
 
+                let from = messages[index].clone();
                 let parent = messages[index - 1].clone();
                 let messages: Vec<ChatQueryMessage> = messages[0..index]
                     .iter()
@@ -617,13 +724,25 @@ impl ChatsViewMessage {
                     })
                     .collect();
 
-                let model = app
-                    .get_chats_view(&id)
-                    .unwrap()
-                    .models
-                    .first()
-                    .unwrap()
-                    .clone();
+                // Regenerating an answer to an @mention goes back to the same assistant.
+                let assistant = app
+                    .cache
+                    .home_shared
+                    .messages
+                    .0
+                    .get(&parent)
+                    .and_then(|x| app.cache.home_shared.assistants.mentioned(&x.base.content))
+                    .cloned();
+                let view = app.get_chats_view(&id).unwrap();
+                let messages = view.with_system_prompt(messages, assistant.as_ref());
+                let (model, tools, options) = match assistant {
+                    Some(x) => (x.model, x.tools, x.options),
+                    None => (
+                        view.models.first().unwrap().clone(),
+                        view.tools.clone(),
+                        view.chat.options.clone(),
+                    ),
+                };
 
                 Task::future(async move {
                     let message = MessageDataBuilder::default()
@@ -634,20 +753,14 @@ impl ChatsViewMessage {
 
                     let req = DATA.read().unwrap().to_request();
                     match req
-                        .make_request::<ochat_types::chats::messages::Message, MessageData>(
-                            &format!("message/parent/{}", &parent),
-                            &message,
-                            RequestType::Post,
-                        )
+                        .create_message_with_parent(&parent, &message)
                         .await
+                        .required()
                     {
                         Ok(message) => Message::Batch(vec![
                             Message::HomePaneView(HomePaneViewMessage::Chats(
                                 id,
-                                ChatsViewMessage::SwitchMessage(
-                                    message_id,
-                                    message.id.key().to_string(),
-                                ),
+                                ChatsViewMessage::SwitchMessage(from, message.id.key().to_string()),
                             )),
                             Message::HomePaneView(HomePaneViewMessage::Chats(
                                 id,
@@ -660,11 +773,12 @@ impl ChatsViewMessage {
                                         model: model.model,
                                         tools,
                                         messages,
+                                        options,
                                     }),
                                 ),
                             )),
                         ]),
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -694,25 +808,11 @@ impl ChatsViewMessage {
 
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<Chat, Vec<MessageData>>(
-                            "chat/branch/",
-                            &messages,
-                            RequestType::Post,
-                        )
-                        .await
-                    {
+                    match req.branch_new_chat(&messages).await.required() {
                         Ok(chat) => Message::Batch(vec![
-                            match req
-                                .make_request::<Preview, ()>(
-                                    &format!("preview/{}", chat.id.key().to_string()),
-                                    &(),
-                                    RequestType::Put,
-                                )
-                                .await
-                            {
+                            match req.update_preview(&chat.id.key().to_string()).await {
                                 Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
-                                Err(e) => Message::Err(e),
+                                Err(e) => Message::Err(e.to_string()),
                             },
                             Message::Window(WindowMessage::Page(
                                 window_id,
@@ -721,7 +821,7 @@ impl ChatsViewMessage {
                                 ))),
                             )),
                         ]),
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -736,7 +836,7 @@ impl ChatsViewMessage {
                 } {
                     app.get_chats_view(&id).unwrap().models.push(model);
                 }
-                Task::none()
+                Self::save_config(app, id)
             }
             Self::ChangeModel(index, model) => {
                 *app.get_chats_view(&id)
@@ -744,15 +844,26 @@ impl ChatsViewMessage {
                     .models
                     .get_mut(index)
                     .unwrap() = model;
-                Task::none()
+                Self::save_config(app, id)
             }
             Self::RemoveModel(index) => {
                 let view = app.get_chats_view(&id).unwrap();
 
                 if view.models.len() > 1 {
                     let _ = view.models.remove(index);
+                    Self::save_config(app, id)
+                } else {
+                    Task::none()
                 }
-                Task::none()
+            }
+            Self::ApplyAssistant(assistant) => {
+                let view = app.get_chats_view(&id).unwrap();
+                view.models = vec![assistant.model.clone()];
+                view.tools = assistant.tools.clone();
+                view.chat.system_prompt = assistant.system_prompt.clone();
+                view.chat.options = assistant.options.clone();
+                view.assistant = Some(assistant);
+                Self::save_config(app, id)
             }
             Self::ChangeStart(index) => {
                 app.get_chats_view(&id).unwrap().start = index;
@@ -796,6 +907,23 @@ impl ChatsViewMessage {
         }
     }
 
+    fn save_config(app: &mut Application, id: u32) -> Task<Message> {
+        let view = app.get_chats_view(&id).unwrap();
+        view.chat.models = view.models.clone();
+        view.chat.tools = view.tools.clone();
+
+        let chat_id = view.chat.id.key().to_string();
+        let config = ChatConfig::from(&view.chat);
+
+        Task::future(async move {
+            let req = DATA.read().unwrap().to_request();
+            match req.set_chat_config(&chat_id, &config).await {
+                Ok(_) => Message::None,
+                Err(e) => Message::Err(e.to_string()),
+            }
+        })
+    }
+
     fn get_window_id(app: &mut Application, id: u32) -> window::Id {
         if let Some(x) = app.get_chats_view(&id).unwrap().window_id {
             x.clone()
@@ -884,6 +1012,32 @@ pub fn get_command_input(input: &str) -> Option<&str> {
 }
 
 impl ChatsView {
+    fn with_system_prompt(
+        &self,
+        mut messages: Vec<ChatQueryMessage>,
+        assistant: Option<&Assistant>,
+    ) -> Vec<ChatQueryMessage> {
+        let prompt = match assistant {
+            Some(x) => x.system_prompt.clone(),
+            None => self.chat.system_prompt.clone(),
+        };
+
+        if let Some(prompt) = prompt.filter(|x| !x.trim().is_empty()) {
+            messages.insert(
+                0,
+                ChatQueryMessage {
+                    text: prompt,
+                    files: Vec::new(),
+                    role: Role::System,
+                    id: None,
+                    func_calls: Vec::new(),
+                },
+            );
+        }
+
+        messages
+    }
+
     pub fn view_message<'a>(
         id: u32,
         theme: &Theme,
@@ -891,6 +1045,7 @@ impl ChatsView {
         can_change: bool,
         edit: Option<&'a text_editor::Content>,
         expanded: bool,
+        metrics_expanded: bool,
     ) -> Element<'a, Message> {
         let header = container(
             row(if edit.is_some() {
@@ -949,6 +1104,34 @@ impl ChatsView {
                             )))
                             .into(),
                     ]);
+
+                    for (rating, path) in [
+                        (Rating::Up, "thumbs_up.svg"),
+                        (Rating::Down, "thumbs_down.svg"),
+                    ] {
+                        let button = if message
+                            .base
+                            .rating
+                            .as_ref()
+                            .is_some_and(|x| x.rating == rating)
+                        {
+                            style::svg_button::primary(path, BODY_SIZE)
+                        } else {
+                            style::svg_button::text(path, BODY_SIZE)
+                        };
+
+                        widgets.push(
+                            button
+                                .on_press(Message::HomePaneView(HomePaneViewMessage::Chats(
+                                    id,
+                                    ChatsViewMessage::Rate(
+                                        message.base.id.key().to_string(),
+                                        rating,
+                                    ),
+                                )))
+                                .into(),
+                        );
+                    }
                 }
 
                 widgets.append(&mut vec![
@@ -1028,6 +1211,19 @@ impl ChatsView {
                     ))
                 })
                 .into()
+        } else if !message.base.func_calls.is_empty() {
+            column(message.base.func_calls.iter().map(|x| {
+                Self::view_function_call(
+                    x,
+                    expanded,
+                    Message::HomePaneView(HomePaneViewMessage::Chats(
+                        id,
+                        ChatsViewMessage::Expand(message.base.id.key().to_string()),
+                    )),
+                )
+            }))
+            .spacing(5)
+            .into()
         } else {
             markdown::view(message.content.items(), style::markdown::main(theme))
                 .map(Message::UriClicked)
@@ -1075,6 +1271,134 @@ impl ChatsView {
             col = col.push(thinking);
         }
 
+        if let Some(rating) = message.base.rating.as_ref().filter(|_| edit.is_none()) {
+            let message_id = message.base.id.key().to_string();
+
+            col = col.push(style::svg_input::text(
+                Some(String::from("edit.svg")),
+                text_input(
+                    "Say why (optional)...",
+                    rating.comment.as_deref().unwrap_or_default(),
+                )
+                .on_input(move |x| {
+                    Message::HomePaneView(HomePaneViewMessage::Chats(
+                        id,
+                        ChatsViewMessage::UpdateRatingComment(message_id.clone(), x),
+                    ))
+                })
+                .on_submit(Message::HomePaneView(HomePaneViewMessage::Chats(
+                    id,
+                    ChatsViewMessage::SubmitRatingComment(message.base.id.key().to_string()),
+                ))),
+                BODY_SIZE,
+            ));
+        }
+
+        if let Some(metrics) = &message.base.metrics {
+            let on_press = Message::HomePaneView(HomePaneViewMessage::Chats(
+                id,
+                ChatsViewMessage::ExpandMetrics(message.base.id.key().to_string()),
+            ));
+
+            let footer: Element<'a, Message> = if metrics_expanded {
+                let or_unknown = |x: Option<String>| x.unwrap_or(String::from("Unknown"));
+                let rows = [
+                    (
+                        "Prompt tokens",
+                        or_unknown(metrics.prompt_tokens.map(|x| x.to_string())),
+                    ),
+                    (
+                        "Completion tokens",
+                        or_unknown(metrics.completion_tokens.map(|x| x.to_string())),
+                    ),
+                    (
+                        "Time to first token",
+                        or_unknown(metrics.time_to_first_token.map(|x| format!("{}ms", x))),
+                    ),
+                    ("Duration", format!("{}ms", metrics.duration)),
+                    (
+                        "Tokens per second",
+                        or_unknown(metrics.tokens_per_second.map(|x| format!("{:.1}", x))),
+                    ),
+                    ("Finish reason", or_unknown(metrics.finish_reason.clone())),
+                    (
+                        "Context",
+                        match &metrics.context {
+                            Some(x) => format!(
+                                "~{} of {} tokens, {} messages left out",
+                                x.prompt_tokens, x.context_window, x.trimmed
+                            ),
+                            None => String::from("Full history"),
+                        },
+                    ),
+                    (
+                        "Options",
+                        if metrics.options.is_empty() {
+                            String::from("Defaults")
+                        } else {
+                            metrics
+                                .options
+                                .iter()
+                                .map(|x| format!("{} = {}", x.key, x.value))
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        },
+                    ),
+                    (
+                        "Unsupported options",
+                        if metrics.unsupported_options.is_empty() {
+                            String::from("None")
+                        } else {
+                            metrics
+                                .unsupported_options
+                                .iter()
+                                .map(|x| x.to_string())
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        },
+                    ),
+                ];
+
+                mouse_area(
+                    container(
+                        column(rows.into_iter().map(|(name, value)| {
+                            row![
+                                text(name).size(BODY_SIZE).font(get_bold_font()),
+                                text(value).size(BODY_SIZE)
+                            ]
+                            .spacing(10)
+                            .into()
+                        }))
+                        .spacing(5),
+                    )
+                    .padding(10)
+                    .width(Length::Fill)
+                    .style(style::container::back),
+                )
+                .on_press(on_press)
+                .into()
+            } else {
+                let mut summary = Vec::new();
+
+                if let Some(tokens) = metrics.completion_tokens {
+                    summary.push(format!("{} tokens", tokens));
+                }
+
+                if let Some(speed) = metrics.tokens_per_second {
+                    summary.push(format!("{:.1} tokens/s", speed));
+                }
+
+                summary.push(format!("{:.2}s", metrics.duration as f64 / 1000.0));
+
+                button(text(summary.join(" · ")).size(BODY_SIZE))
+                    .style(style::button::transparent_back_white_text)
+                    .on_press(on_press)
+                    .into()
+            };
+
+            col = col.push(footer);
+        }
+
         container(col)
             .padding(10)
             .style(style::container::chat_back)
@@ -1262,8 +1586,29 @@ impl ChatsView {
             .style(style::container::bottom_input_back)
         });
 
+        let assistants: Element<'a, Message> = if app.cache.home_shared.assistants.0.is_empty() {
+            space().into()
+        } else {
+            pick_list(
+                app.cache.home_shared.assistants.0.clone(),
+                self.assistant.clone(),
+                move |x| {
+                    Message::HomePaneView(HomePaneViewMessage::Chats(
+                        id,
+                        ChatsViewMessage::ApplyAssistant(x),
+                    ))
+                },
+            )
+            .placeholder("Assistant")
+            .style(style::pick_list::main)
+            .menu_style(style::menu::main)
+            .text_size(BODY_SIZE)
+            .into()
+        };
+
         let models = container(
             row![
+                assistants,
                 scrollable::Scrollable::new(
                     row(self
                         .models
@@ -1338,6 +1683,17 @@ impl ChatsView {
                 .width(Length::Fill)
                 .into(),
             false => {
+                // Only the latest answer tells where the history currently gets cut.
+                let trim = self.messages.iter().rev().find_map(|x| {
+                    app.cache
+                        .home_shared
+                        .messages
+                        .0
+                        .get(x.trim())
+                        .and_then(|x| x.base.metrics.as_ref())
+                        .and_then(|x| x.context.as_ref())
+                });
+
                 let mut can_change = false;
                 let mut col = column(self.messages.iter().filter_map(|x| {
                     let x = x.trim();
@@ -1345,14 +1701,37 @@ impl ChatsView {
                         let cur_change = can_change;
                         can_change = msg.base.children.len() > 1;
 
-                        Some(Self::view_message(
+                        let message = Self::view_message(
                             id.clone(),
                             &app.theme(),
                             msg,
                             cur_change,
                             self.edits.get(x),
                             self.expanded_messages.contains(&x.to_string()),
-                        ))
+                            self.expanded_metrics.contains(&x.to_string()),
+                        );
+
+                        let message = match app
+                            .subscriptions
+                            .message_gens
+                            .values()
+                            .find(|y| y.id == x)
+                            .filter(|y| y.status.is_some() || !y.citations.is_empty())
+                        {
+                            Some(generation) => column![message, Self::view_status(generation)]
+                                .spacing(5)
+                                .into(),
+                            None => message,
+                        };
+
+                        Some(match trim {
+                            Some(trim) if trim.first_kept.as_deref() == Some(x) => {
+                                column![Self::view_context_trim(trim), message]
+                                    .spacing(20)
+                                    .into()
+                            }
+                            _ => message,
+                        })
                     } else {
                         None
                     }
@@ -1361,6 +1740,7 @@ impl ChatsView {
 
                 col = col.push(space().height(if self.files.is_empty() { 130 } else { 250 }));
                 scrollable::Scrollable::new(col)
+                    .id(format!("chat-{}", id))
                     .direction(scrollable::Direction::Vertical(scrollable::Scrollbar::new()))
                     .anchor_bottom()
                     .height(Length::Fill)
@@ -1371,6 +1751,109 @@ impl ChatsView {
         container(stack([body, column![space::vertical(), input].into()])).into()
     }
 
+    fn view_function_call<'a>(
+        call: &'a FunctionCall,
+        expanded: bool,
+        on_press: Message,
+    ) -> Element<'a, Message> {
+        let title = button(
+            row![
+                svg(svg::Handle::from_path(get_path_assets("tools.svg"))).width(BODY_SIZE),
+                text(&call.name).size(BODY_SIZE)
+            ]
+            .align_y(Vertical::Center)
+            .spacing(10),
+        )
+        .padding(10)
+        .style(style::button::chosen_chat)
+        .on_press(on_press)
+        .width(Length::Fill);
+
+        if !expanded {
+            return title.into();
+        }
+
+        let mut result = call.result_text();
+        if let Some((i, _)) = result.char_indices().nth(CALL_RESULT_PREVIEW) {
+            result.truncate(i);
+            result.push_str("...");
+        }
+
+        column![
+            title,
+            container(
+                column![
+                    text("Arguments").size(BODY_SIZE).font(get_bold_font()),
+                    text(serde_json::to_string_pretty(&call.args).unwrap_or_default())
+                        .size(BODY_SIZE),
+                    text("Result").size(BODY_SIZE).font(get_bold_font()),
+                    text(result).size(BODY_SIZE),
+                ]
+                .spacing(5),
+            )
+            .padding(10)
+            .width(Length::Fill)
+            .style(style::container::back),
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    fn view_status<'a>(generation: &'a MessageGen) -> Element<'a, Message> {
+        let mut col = column![].spacing(5);
+
+        if let Some(status) = &generation.status {
+            col = col.push(
+                row![
+                    svg(svg::Handle::from_path(get_path_assets("tools.svg"))).width(BODY_SIZE),
+                    text(format!("{}...", status)).size(BODY_SIZE)
+                ]
+                .align_y(Vertical::Center)
+                .spacing(10),
+            );
+        }
+
+        if !generation.citations.is_empty() {
+            col = col.push(text("Sources").size(BODY_SIZE).font(get_bold_font()));
+            col = col.push(
+                column(generation.citations.iter().map(|x| {
+                    button(text(x.title.as_ref().unwrap_or(&x.url)).size(BODY_SIZE))
+                        .style(style::button::transparent_translucent)
+                        .on_press(Message::UriClicked(x.url.clone()))
+                        .into()
+                }))
+                .spacing(2),
+            );
+        }
+
+        container(col)
+            .padding(10)
+            .width(Length::Fill)
+            .style(style::container::back)
+            .into()
+    }
+
+    fn view_context_trim<'a>(trim: &ContextTrim) -> Element<'a, Message> {
+        let label = format!(
+            "{} earlier messages {} to fit the {} token context",
+            trim.trimmed,
+            match trim.strategy {
+                ContextStrategy::Summarize => "summarized",
+                _ => "dropped",
+            },
+            trim.context_window
+        );
+
+        row![
+            rule::horizontal(1).style(style::rule::translucent::text),
+            text(label).size(BODY_SIZE),
+            rule::horizontal(1).style(style::rule::translucent::text),
+        ]
+        .spacing(10)
+        .align_y(Vertical::Center)
+        .into()
+    }
+
     fn view_commands<'a>(
         prompts: &'a PromptsData,
         id: u32,
@@ -1405,6 +1888,49 @@ impl ChatsView {
     }
 
     fn view_start<'a>(&'a self, id: u32) -> Element<'a, Message> {
+        let starter = |x: String| -> Element<'a, Message> {
+            button(
+                text(x.clone())
+                    .style(style::text::translucent::text)
+                    .align_x(Horizontal::Left)
+                    .width(Length::Fill)
+                    .size(SUB_HEADING_SIZE),
+            )
+            .padding(10)
+            .style(style::button::transparent_translucent)
+            .on_press(Message::HomePaneView(HomePaneViewMessage::Chats(
+                id,
+                ChatsViewMessage::InputAction(text_editor::Action::Edit(text_editor::Edit::Paste(
+                    Arc::new(x),
+                ))),
+            )))
+            .into()
+        };
+
+        if let Some(assistant) = self.assistant.as_ref().filter(|x| !x.starters.is_empty()) {
+            let title = text(assistant.to_string())
+                .font(get_bold_font())
+                .size(HEADER_SIZE)
+                .style(style::text::primary)
+                .align_x(Horizontal::Left);
+
+            return center(
+                container(
+                    column![
+                        title,
+                        rule::horizontal(1).style(style::rule::translucent::primary),
+                        column(assistant.starters.iter().cloned().map(starter)),
+                    ]
+                    .spacing(20)
+                    .align_x(Horizontal::Left),
+                )
+                .max_width(800)
+                .padding(Padding::new(20.0))
+                .style(style::container::neutral_back),
+            )
+            .into();
+        }
+
         let title = text("How can I help?")
             .font(get_bold_font())
             .size(HEADER_SIZE)
@@ -1440,30 +1966,7 @@ impl ChatsView {
 
         let section: Section = start::SECTIONS[self.start].clone();
 
-        let prompts = column(
-            section
-                .prompts
-                .iter()
-                .map(|x| {
-                    button(
-                        text(*x)
-                            .style(style::text::translucent::text)
-                            .align_x(Horizontal::Left)
-                            .width(Length::Fill)
-                            .size(SUB_HEADING_SIZE),
-                    )
-                    .padding(10)
-                    .style(style::button::transparent_translucent)
-                    .on_press(Message::HomePaneView(HomePaneViewMessage::Chats(
-                        id,
-                        ChatsViewMessage::InputAction(text_editor::Action::Edit(
-                            text_editor::Edit::Paste(Arc::new(x.to_string())),
-                        )),
-                    )))
-                    .into()
-                })
-                .collect::<Vec<Element<Message>>>(),
-        );
+        let prompts = column(section.prompts.iter().map(|x| starter(x.to_string())));
 
         center(
             container(
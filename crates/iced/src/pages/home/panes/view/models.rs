@@ -16,7 +16,7 @@ use iced::{
     },
 };
 use iced_selection::{markdown, text};
-use ochat_common::{data::RequestType, print_data_size};
+use ochat_common::print_data_size;
 use ochat_types::{
     providers::{
         Provider, ProviderType,
@@ -187,16 +187,15 @@ impl ModelsViewMessage {
                     Task::future(async move {
                         let req = DATA.read().unwrap().to_request();
                         match req
-                            .make_request(
+                            .fetch_hf_model_details(
                                 &if page == Page::HfText {
-                                    format!("provider/hf/text/model/{}", x)
+                                    ModelType::Text
                                 } else if page == Page::HfStt {
-                                    format!("provider/hf/stt/model/{}", x)
+                                    ModelType::Stt
                                 } else {
-                                    format!("provider/hf/tts/model/{}", x)
+                                    ModelType::Tts
                                 },
-                                &(),
-                                RequestType::Get,
+                                &x,
                             )
                             .await
                         {
@@ -204,7 +203,7 @@ impl ModelsViewMessage {
                                 id,
                                 ModelsViewMessage::SetHFExpand(x.clone(), y),
                             )),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         }
                     })
                 }
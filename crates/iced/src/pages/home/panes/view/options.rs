@@ -17,12 +17,9 @@ use iced::{
     },
 };
 use iced_selection::text;
-use ochat_common::data::RequestType;
+use ochat_common::data::client::Required;
 use ochat_types::{
-    options::{
-        GenOption, GenOptions, GenOptionsData,
-        relationships::{GenModelRelationship, GenModelRelationshipData},
-    },
+    options::{GenOption, GenOptionsData, relationships::GenModelRelationshipData},
     settings::SettingsProvider,
 };
 use std::collections::HashMap;
@@ -246,16 +243,9 @@ impl OptionsViewMessage {
                     let option: GenOptionsData = option.option.into();
                     let mut tasks = Vec::new();
 
-                    match req
-                        .make_request::<GenOptions, GenOptionsData>(
-                            &format!("option/{}", option_id),
-                            &option,
-                            RequestType::Put,
-                        )
-                        .await
-                    {
+                    match req.update_gen_options(&option_id, &option).await {
                         Ok(_) => {}
-                        Err(e) => tasks.push(Message::Err(e)),
+                        Err(e) => tasks.push(Message::Err(e.to_string())),
                     }
 
                     for relationship in added_relationships {
@@ -263,49 +253,23 @@ impl OptionsViewMessage {
                         let relationship: GenModelRelationshipData = relationship.into();
 
                         match if let Some(id) = id {
-                            req
-                                .make_request::<Option<GenModelRelationship>, GenModelRelationshipData>(
-                                    &format!("option/relationship/{}", id),
-                                    &relationship,
-                                    RequestType::Put,
-                                )
-                                .await
+                            req.update_gen_models(&id, &relationship).await
                         } else {
-                            req
-                                .make_request::<Option<GenModelRelationship>, GenModelRelationshipData>(
-                                    "option/relationship/",
-                                    &relationship,
-                                    RequestType::Post,
-                                )
-                                .await
+                            req.add_gen_models(&relationship).await
                         } {
                             Ok(_) => {}
-                            Err(e) => tasks.push(Message::Err(e)),
+                            Err(e) => tasks.push(Message::Err(e.to_string())),
                         }
                     }
 
                     for relationship in removed_relationships {
-                        match req
-                            .make_request::<Option<GenModelRelationship>, ()>(
-                                &format!("option/relationship/{}", relationship),
-                                &(),
-                                RequestType::Delete,
-                            )
-                            .await
-                        {
+                        match req.delete_gen_models(&relationship).await {
                             Ok(_) => {}
-                            Err(e) => tasks.push(Message::Err(e)),
+                            Err(e) => tasks.push(Message::Err(e.to_string())),
                         }
                     }
 
-                    match req
-                        .make_request::<Vec<GenModelRelationship>, ()>(
-                            &format!("option/{}/all/", option_id),
-                            &(),
-                            RequestType::Get,
-                        )
-                        .await
-                    {
+                    match req.get_gen_models_from_options(&option_id).await {
                         Ok(x) => tasks.push(Message::HomePaneView(HomePaneViewMessage::Options(
                             id,
                             OptionsViewMessage::SetUpdatedRelationships(
@@ -313,7 +277,7 @@ impl OptionsViewMessage {
                                 x.into_iter().map(|x| x.into()).collect(),
                             ),
                         ))),
-                        Err(e) => tasks.push(Message::Err(e)),
+                        Err(e) => tasks.push(Message::Err(e.to_string())),
                     }
 
                     Message::Batch(tasks)
@@ -391,15 +355,12 @@ impl OptionsViewMessage {
                 let req = DATA.read().unwrap().to_request();
 
                 match req
-                    .make_request::<GenOptions, GenOptionsData>(
-                        "option/",
-                        &GenOptionsData {
-                            name: String::from("New Options"),
-                            ..Default::default()
-                        },
-                        RequestType::Post,
-                    )
+                    .add_gen_options(&GenOptionsData {
+                        name: String::from("New Options"),
+                        ..Default::default()
+                    })
                     .await
+                    .required()
                 {
                     Ok(x) => Message::HomePaneView(HomePaneViewMessage::Options(
                         id,
@@ -408,7 +369,7 @@ impl OptionsViewMessage {
                             models: Vec::new(),
                         }),
                     )),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::Delete(x) => {
@@ -421,16 +382,9 @@ impl OptionsViewMessage {
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
 
-                    match req
-                        .make_request::<GenOptions, ()>(
-                            &format!("option/{}", x),
-                            &(),
-                            RequestType::Delete,
-                        )
-                        .await
-                    {
+                    match req.delete_gen_options(&x).await {
                         Ok(_) => Message::None,
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
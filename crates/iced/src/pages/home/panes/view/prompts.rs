@@ -15,7 +15,7 @@ use iced::{
     },
 };
 use iced_selection::text;
-use ochat_common::{data::RequestType, load_from_file};
+use ochat_common::{data::client::Required, load_from_file};
 use ochat_types::prompts::{Prompt, PromptData, PromptDataBuilder};
 use std::collections::HashMap;
 
@@ -168,16 +168,9 @@ impl PromptsViewMessage {
                     let req = DATA.read().unwrap().to_request();
                     let prompt: PromptData = prompt.into();
 
-                    match req
-                        .make_request::<Prompt, PromptData>(
-                            &format!("prompt/{}", prompt_id),
-                            &prompt,
-                            RequestType::Put,
-                        )
-                        .await
-                    {
+                    match req.update_prompt(&prompt_id, &prompt).await {
                         Ok(_) => Message::None,
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -254,19 +247,12 @@ impl PromptsViewMessage {
                             tasks.push(Task::future(async move {
                                 let req = DATA.read().unwrap().to_request();
 
-                                match req
-                                    .make_request::<Prompt, PromptData>(
-                                        "prompt/",
-                                        &prompt,
-                                        RequestType::Post,
-                                    )
-                                    .await
-                                {
+                                match req.add_prompt(&prompt).await.required() {
                                     Ok(x) => Message::HomePaneView(HomePaneViewMessage::Prompts(
                                         id,
                                         PromptsViewMessage::AddPrompt(x),
                                     )),
-                                    Err(e) => Message::Err(e),
+                                    Err(e) => Message::Err(e.to_string()),
                                 }
                             }));
                         }
@@ -283,23 +269,22 @@ impl PromptsViewMessage {
                 let req = DATA.read().unwrap().to_request();
 
                 match req
-                    .make_request::<Prompt, PromptData>(
-                        "prompt/",
+                    .add_prompt(
                         &PromptDataBuilder::default()
                             .title(String::from("New Prompt"))
                             .command(String::new())
                             .content(String::new())
                             .build()
                             .unwrap(),
-                        RequestType::Post,
                     )
                     .await
+                    .required()
                 {
                     Ok(x) => Message::HomePaneView(HomePaneViewMessage::Prompts(
                         id,
                         PromptsViewMessage::AddPrompt(x),
                     )),
-                    Err(e) => Message::Err(e),
+                    Err(e) => Message::Err(e.to_string()),
                 }
             }),
             Self::Delete(x) => {
@@ -312,16 +297,9 @@ impl PromptsViewMessage {
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
 
-                    match req
-                        .make_request::<Prompt, ()>(
-                            &format!("prompt/{}", x),
-                            &(),
-                            RequestType::Delete,
-                        )
-                        .await
-                    {
-                        Ok(_) => Message::None,
-                        Err(e) => Message::Err(e),
+                    match req.delete_prompt(&x).await {
+                        Ok(_) => Message::Cache(crate::CacheMessage::ResetSideBarItems),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -14,18 +14,21 @@ use iced::{
     },
 };
 use iced_selection::text;
-use ochat_common::data::RequestType;
+use ochat_common::{delete_token, save_token};
 use ochat_types::{
     providers::{Provider, ProviderData, ProviderDataBuilder, ProviderType},
-    settings::{SettingsData, SettingsProvider, SettingsProviderBuilder},
+    settings::{ContextStrategy, SettingsData, SettingsProvider, SettingsProviderBuilder},
     surreal::RecordId,
+    user::{ChangePasswordData, Session},
 };
-use serde_json::Value;
 
 #[derive(Debug, Clone)]
 pub struct SettingsView {
     pub instance_url: String,
     pub provider_inputs: Vec<ProviderData>,
+    pub old_password: String,
+    pub new_password: String,
+    pub sessions: Vec<Session>,
 }
 
 impl Default for SettingsView {
@@ -33,6 +36,9 @@ impl Default for SettingsView {
         Self {
             instance_url: String::from("http://localhost:1212/api"),
             provider_inputs: Vec::new(),
+            old_password: String::new(),
+            new_password: String::new(),
+            sessions: Vec::new(),
         }
     }
 }
@@ -44,6 +50,8 @@ pub enum SettingsViewMessage {
     UpdateProviderType(usize, ProviderType),
     UpdateProviderKey(usize, String),
     UpdateHfToken(String),
+    UpdateContextStrategy(ContextStrategy),
+    UpdateContextWindow(String),
     UpdatePreviewModel(SettingsProvider),
     UpdateDefaultModel(SettingsProvider),
     UpdateSttModel(SettingsProvider),
@@ -53,6 +61,14 @@ pub enum SettingsViewMessage {
     RemoveProviderInput(usize),
     AddProvider(usize),
     AddProviderInput,
+    UpdateOldPassword(String),
+    UpdateNewPassword(String),
+    ChangePassword,
+    LoadSessions,
+    SessionsLoaded(Vec<Session>),
+    RevokeSession(RecordId),
+    RefreshSession,
+    Logout,
 }
 
 impl SettingsViewMessage {
@@ -97,24 +113,12 @@ impl SettingsViewMessage {
                     .remove(index);
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<Option<Provider>, ProviderData>(
-                            "provider/",
-                            &input,
-                            RequestType::Post,
-                        )
-                        .await
-                    {
+                    match req.add_provider(&input).await {
                         Ok(Some(provider)) => {
-                            let provider_models: Result<Vec<Value>, String> = req
-                                .make_request(
-                                    &format!("provider/{}/model/all/", provider.id.key()),
-                                    &(),
-                                    RequestType::Get,
-                                )
-                                .await;
-
-                            match provider_models {
+                            match req
+                                .list_all_provider_models(&provider.id.key().to_string())
+                                .await
+                            {
                                 Ok(provider_models) => {
                                     let mut models = Vec::new();
 
@@ -122,20 +126,20 @@ impl SettingsViewMessage {
                                         models.push(
                                             SettingsProviderBuilder::default()
                                                 .provider(provider.id.key().to_string())
-                                                .model(model["id"].as_str().unwrap().to_string())
+                                                .model(model.id)
                                                 .build()
                                                 .unwrap(),
                                         );
                                     }
                                     DATA.write().unwrap().models.append(&mut models);
                                 }
-                                Err(e) => return Message::Err(e),
+                                Err(e) => return Message::Err(e.to_string()),
                             }
 
                             DATA.write().unwrap().providers.push(provider);
                             Message::None
                         }
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                         _ => Message::None,
                     }
                 })
@@ -149,16 +153,9 @@ impl SettingsViewMessage {
 
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<Value, ()>(
-                            &format!("provider/{}", id.key().to_string()),
-                            &(),
-                            RequestType::Delete,
-                        )
-                        .await
-                    {
+                    match req.delete_provider(&id.key().to_string()).await {
                         Ok(_) => Message::None,
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -173,6 +170,21 @@ impl SettingsViewMessage {
                 app.cache.settings.hf_token = if token.is_empty() { None } else { Some(token) };
                 Task::future(save_settings(app.cache.settings.clone()))
             }
+            Self::UpdateContextStrategy(strategy) => {
+                app.cache.settings.context_strategy = Some(strategy);
+                Task::future(save_settings(app.cache.settings.clone()))
+            }
+            Self::UpdateContextWindow(window) => {
+                let window = window.trim();
+                app.cache.settings.context_window = if window.is_empty() {
+                    Some(0)
+                } else if let Ok(x) = window.parse() {
+                    Some(x)
+                } else {
+                    return Task::none();
+                };
+                Task::future(save_settings(app.cache.settings.clone()))
+            }
             Self::UpdatePreviewModel(model) => UpdateModel!(model, previews_provider),
             Self::UpdateDefaultModel(model) => {
                 app.cache.client_settings.default_provider = Some(model);
@@ -199,6 +211,86 @@ impl SettingsViewMessage {
                     instance,
                 )))
             }
+            Self::UpdateOldPassword(password) => {
+                app.get_settings_view(&id).unwrap().old_password = password;
+                Task::none()
+            }
+            Self::UpdateNewPassword(password) => {
+                app.get_settings_view(&id).unwrap().new_password = password;
+                Task::none()
+            }
+            Self::ChangePassword => {
+                let view = app.get_settings_view(&id).unwrap();
+                let data = ChangePasswordData {
+                    old_password: std::mem::take(&mut view.old_password),
+                    new_password: std::mem::take(&mut view.new_password),
+                };
+
+                Task::future(async move {
+                    let req = DATA.read().unwrap().to_request();
+                    match req.change_password(&data).await {
+                        Ok(_) => Message::HomePaneView(HomePaneViewMessage::Settings(
+                            id,
+                            SettingsViewMessage::LoadSessions,
+                        )),
+                        Err(e) => Message::Err(e.to_string()),
+                    }
+                })
+            }
+            Self::LoadSessions => Task::future(async move {
+                let req = DATA.read().unwrap().to_request();
+                match req.list_all_sessions().await {
+                    Ok(sessions) => Message::HomePaneView(HomePaneViewMessage::Settings(
+                        id,
+                        SettingsViewMessage::SessionsLoaded(sessions),
+                    )),
+                    Err(e) => Message::Err(e.to_string()),
+                }
+            }),
+            Self::SessionsLoaded(sessions) => {
+                app.get_settings_view(&id).unwrap().sessions = sessions;
+                Task::none()
+            }
+            Self::RevokeSession(session) => {
+                app.get_settings_view(&id)
+                    .unwrap()
+                    .sessions
+                    .retain(|x| x.id != session);
+
+                Task::future(async move {
+                    let req = DATA.read().unwrap().to_request();
+                    match req.revoke_session(&session.key().to_string()).await {
+                        Ok(_) => Message::None,
+                        Err(e) => Message::Err(e.to_string()),
+                    }
+                })
+            }
+            Self::RefreshSession => Task::future(async move {
+                let req = DATA.read().unwrap().to_request();
+                match req.refresh().await {
+                    Ok(jwt) => {
+                        save_token(&jwt);
+                        DATA.write().unwrap().jwt = Some(jwt.token);
+                        Message::HomePaneView(HomePaneViewMessage::Settings(
+                            id,
+                            SettingsViewMessage::LoadSessions,
+                        ))
+                    }
+                    Err(e) => Message::Err(e.to_string()),
+                }
+            }),
+            Self::Logout => Task::future(async move {
+                let req = DATA.read().unwrap().to_request();
+                let result = req.logout().await;
+
+                delete_token();
+                DATA.write().unwrap().jwt = None;
+
+                match result {
+                    Ok(_) => Message::None,
+                    Err(e) => Message::Err(e.to_string()),
+                }
+            }),
             Self::RemoveProviderInput(index) => {
                 let _ = app
                     .get_settings_view(&id)
@@ -214,12 +306,9 @@ impl SettingsViewMessage {
 async fn save_settings(settings: SettingsData) -> Message {
     let req = DATA.read().unwrap().to_request();
 
-    match req
-        .make_request::<Value, SettingsData>("settings/", &settings, RequestType::Put)
-        .await
-    {
+    match req.update_settings(&settings).await {
         Ok(_) => Message::None,
-        Err(e) => Message::Err(e),
+        Err(e) => Message::Err(e.to_string()),
     }
 }
 
@@ -321,6 +410,130 @@ impl SettingsView {
             .into()
     }
 
+    fn view_session<'a>(id: u32, session: &'a Session) -> Element<'a, Message> {
+        let mut name = row![
+            style::svg_button::danger("delete.svg", SUB_HEADING_SIZE).on_press(
+                Message::HomePaneView(HomePaneViewMessage::Settings(
+                    id,
+                    SettingsViewMessage::RevokeSession(session.id.clone()),
+                ))
+            ),
+            text(session.device.as_deref().unwrap_or("Unknown Device"))
+                .size(SUB_HEADING_SIZE)
+                .style(style::text::primary)
+        ]
+        .align_y(Vertical::Center);
+
+        if session.current {
+            name = name.push(text("(This Device)").size(BODY_SIZE));
+        }
+
+        let time = text(format!(
+            "Signed in {}",
+            session.time.0.format("%Y-%m-%d %H:%M")
+        ))
+        .size(BODY_SIZE);
+
+        let last_used = text(match &session.last_used {
+            Some(x) => format!("Last used {}", x.0.format("%Y-%m-%d %H:%M")),
+            None => String::from("Never used"),
+        })
+        .size(BODY_SIZE);
+
+        container(column![name, time, last_used])
+            .padding(Padding::new(20.0))
+            .style(style::container::neutral_back)
+            .into()
+    }
+
+    fn view_account<'a>(&'a self, id: u32) -> Element<'a, Message> {
+        let old_password = text_input("Enter your current password...", &self.old_password)
+            .on_input(move |x| {
+                Message::HomePaneView(HomePaneViewMessage::Settings(
+                    id,
+                    SettingsViewMessage::UpdateOldPassword(x),
+                ))
+            })
+            .size(SUB_HEADING_SIZE)
+            .secure(true)
+            .style(style::text_input::input);
+
+        let new_password = text_input("Enter a new password...", &self.new_password)
+            .on_input(move |x| {
+                Message::HomePaneView(HomePaneViewMessage::Settings(
+                    id,
+                    SettingsViewMessage::UpdateNewPassword(x),
+                ))
+            })
+            .on_submit(Message::HomePaneView(HomePaneViewMessage::Settings(
+                id,
+                SettingsViewMessage::ChangePassword,
+            )))
+            .size(SUB_HEADING_SIZE)
+            .secure(true)
+            .style(style::text_input::input);
+
+        let password = row![
+            old_password,
+            new_password,
+            style::svg_button::primary("save.svg", SUB_HEADING_SIZE).on_press(
+                Message::HomePaneView(HomePaneViewMessage::Settings(
+                    id,
+                    SettingsViewMessage::ChangePassword,
+                ))
+            ),
+        ]
+        .spacing(5)
+        .align_y(Vertical::Center);
+
+        let actions = row![
+            button(text("Refresh Session").size(BODY_SIZE))
+                .on_press(Message::HomePaneView(HomePaneViewMessage::Settings(
+                    id,
+                    SettingsViewMessage::RefreshSession,
+                )))
+                .style(style::button::rounded_primary),
+            button(text("Log Out").size(BODY_SIZE))
+                .on_press(Message::HomePaneView(HomePaneViewMessage::Settings(
+                    id,
+                    SettingsViewMessage::Logout,
+                )))
+                .style(style::button::rounded_primary),
+        ]
+        .spacing(5);
+
+        let header = row![
+            text("Sessions").size(BODY_SIZE).style(style::text::primary),
+            style::svg_button::primary("restart.svg", BODY_SIZE).on_press(Message::HomePaneView(
+                HomePaneViewMessage::Settings(id, SettingsViewMessage::LoadSessions)
+            ))
+        ]
+        .width(Length::Shrink)
+        .align_y(Vertical::Center);
+
+        let sessions: Element<'a, Message> = if self.sessions.is_empty() {
+            text("Press refresh to list your sessions.")
+                .size(BODY_SIZE)
+                .into()
+        } else {
+            scrollable::Scrollable::new(
+                row(self
+                    .sessions
+                    .iter()
+                    .map(|session| Self::view_session(id, session)))
+                .spacing(5),
+            )
+            .direction(scrollable::Direction::Horizontal(
+                scrollable::Scrollbar::new(),
+            ))
+            .into()
+        };
+
+        column![password, actions, header, sessions]
+            .spacing(5)
+            .into()
+    }
+
     pub fn themes<'a>(current: Theme) -> Element<'a, Message> {
         let swatch = |color| {
             container(space::horizontal())
@@ -427,6 +640,37 @@ impl SettingsView {
         .secure(true)
         .style(style::text_input::input);
 
+        let context_strategy = pick_list(
+            ContextStrategy::ALL,
+            app.cache.settings.context_strategy,
+            move |x| {
+                Message::HomePaneView(HomePaneViewMessage::Settings(
+                    id,
+                    SettingsViewMessage::UpdateContextStrategy(x),
+                ))
+            },
+        )
+        .style(style::pick_list::main)
+        .menu_style(style::menu::main);
+
+        let context_window = text_input(
+            "Context window for models that do not report one...",
+            &app.cache
+                .settings
+                .context_window
+                .filter(|x| *x > 0)
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+        )
+        .on_input(move |x| {
+            Message::HomePaneView(HomePaneViewMessage::Settings(
+                id,
+                SettingsViewMessage::UpdateContextWindow(x),
+            ))
+        })
+        .size(SUB_HEADING_SIZE)
+        .style(style::text_input::input);
+
         let providers = {
             let header = row![
                 text("Providers")
@@ -558,10 +802,16 @@ impl SettingsView {
                 column![
                     sub_heading("Instance Url"),
                     ochat,
+                    sub_heading("Account"),
+                    self.view_account(id),
                     sub_heading("Models Download Path"),
                     models_path,
                     sub_heading("Hugging Face Token"),
                     hf_token,
+                    sub_heading("Context Strategy"),
+                    row![context_strategy, context_window]
+                        .spacing(10)
+                        .align_y(Vertical::Center),
                     providers,
                     model_column,
                     sub_heading("Decorations"),
@@ -1,6 +1,6 @@
 use crate::{
     Application, DATA, InputMessage, Message,
-    font::{HEADER_SIZE, SUB_HEADING_SIZE, get_bold_font},
+    font::{BODY_SIZE, HEADER_SIZE, SUB_HEADING_SIZE, get_bold_font},
     pages::{
         PageMessage,
         home::{
@@ -15,18 +15,22 @@ use crate::{
     windows::message::WindowMessage,
 };
 use iced::{
-    Element, Length, Padding, Theme,
+    Element, Font, Length, Padding, Theme,
     alignment::{Horizontal, Vertical},
     widget::{
         Button, Id as WidgetId, button, center_x, column, container, hover, markdown, mouse_area,
-        right, row, rule, space, svg, text_input,
+        rich_text, right, row, rule, space, span, svg, text_input,
     },
     window::{self},
 };
 use iced_drop::droppable;
 use iced_selection::text;
-use ochat_common::data::RequestType;
-use ochat_types::{chats::previews::Preview, folders::Folder, surreal::RecordId};
+use ochat_types::{
+    chats::{messages::MessageSearchResult, previews::Preview},
+    folders::Folder,
+    surreal::RecordId,
+    trash::{TrashItem, TrashKind},
+};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -85,6 +89,8 @@ pub enum SideBarItem {
 #[derive(Debug, Clone, Default)]
 pub struct SideBarItems {
     pub items: Vec<SideBarItem>,
+    pub matches: Vec<MessageSearchResult>,
+    pub trash: Vec<TrashItem>,
 }
 
 impl SideBarItem {
@@ -361,14 +367,14 @@ impl SideBarItems {
     pub async fn get(search: Option<String>) -> Result<Self, String> {
         let req = DATA.read().unwrap().to_request();
 
+        let previews = req.list_all_previews().await?;
+        let folders = req.list_all_folders().await?;
+        let trash = req.list_trash().await?;
+        let preview_ids: Vec<RecordId> = previews.iter().map(|x| x.id.clone()).collect();
+        let mut matches = Vec::new();
+
         let mut items = {
             let mut list = Vec::new();
-            let previews = req
-                .make_request::<Vec<Preview>, ()>("preview/all/", &(), RequestType::Get)
-                .await?;
-            let folders = req
-                .make_request::<Vec<Folder>, ()>("folder/all/", &(), RequestType::Get)
-                .await?;
 
             for folder_data in folders.iter().filter(|x| x.parent.is_none()).cloned() {
                 let mut folder_item = SideBarItem::Folder {
@@ -408,11 +414,7 @@ impl SideBarItems {
 
         if let Some(search) = search {
             let mut wanted: Vec<RecordId> = req
-                .make_request::<Vec<Preview>, ()>(
-                    &format!("preview/search/{}", search),
-                    &(),
-                    RequestType::Get,
-                )
+                .search_previews(&search)
                 .await?
                 .into_iter()
                 .map(|x| x.id)
@@ -420,24 +422,31 @@ impl SideBarItems {
 
             wanted.append(
                 &mut req
-                    .make_request::<Vec<Folder>, ()>(
-                        &format!("folder/search/{}", search),
-                        &(),
-                        RequestType::Get,
-                    )
+                    .search_folders(&search)
                     .await?
                     .into_iter()
                     .map(|x| x.id)
                     .collect(),
             );
 
+            matches = req.search_messages(&search).await?;
+            wanted.extend(
+                preview_ids
+                    .into_iter()
+                    .filter(|x| matches.iter().any(|y| y.chat == x.key().to_string().trim())),
+            );
+
             items.retain(|x| x.contains_any(&wanted));
 
             for child in items.iter_mut() {
                 child.filter_contains_any(&wanted);
             }
         }
-        Ok(Self { items })
+        Ok(Self {
+            items,
+            matches,
+            trash,
+        })
     }
 
     pub fn parent_id_of(&self, target: &str) -> Option<String> {
@@ -486,6 +495,7 @@ impl DragItem {
 }
 
 pub const SIDEBAR_ROOT_ID: &str = "sidebar-root";
+pub const TRASH_ID: &str = "trash";
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum IsInSpecial {
     Fav,
@@ -523,6 +533,165 @@ impl HomeSideBar {
             .into()
     }
 
+    fn view_matches<'a>(&'a self, id: window::Id) -> Element<'a, Message> {
+        column(self.items.matches.iter().map(|x| {
+            let mut spans: Vec<iced::widget::text::Span<'a, (), Font>> = Vec::new();
+            let mut last = 0;
+
+            for highlight in &x.highlights {
+                if highlight.start < last || highlight.end > x.snippet.len() {
+                    continue;
+                }
+
+                spans.push(span(&x.snippet[last..highlight.start]));
+                spans.push(
+                    span(&x.snippet[highlight.start..highlight.end])
+                        .font(get_bold_font())
+                        .underline(true),
+                );
+                last = highlight.end;
+            }
+            spans.push(span(&x.snippet[last..]));
+
+            button(
+                column![
+                    text(if x.thinking {
+                        format!("{} (Thinking)", x.role)
+                    } else {
+                        x.role.to_string()
+                    })
+                    .font(get_bold_font())
+                    .size(BODY_SIZE),
+                    rich_text(spans).size(BODY_SIZE),
+                ]
+                .spacing(2),
+            )
+            .width(Length::Fill)
+            .style(style::button::transparent_back_white_text)
+            .on_press(Message::Window(WindowMessage::Page(
+                id,
+                PageMessage::Home(HomeMessage::JumpToMessage(
+                    x.chat.clone(),
+                    x.message.clone(),
+                )),
+            )))
+            .into()
+        }))
+        .spacing(5)
+        .into()
+    }
+
+    fn view_trash<'a>(&'a self, app: &'a Application, id: window::Id) -> Element<'a, Message> {
+        let trash = &app.cache.side_bar_items.trash;
+
+        let header = container(
+            row![
+                svg(svg::Handle::from_path(get_path_assets(
+                    "delete.svg".to_string()
+                )))
+                .style(style::svg::text)
+                .width(SUB_HEADING_SIZE),
+                text(format!("Trash ({})", trash.len())).size(BODY_SIZE),
+            ]
+            .align_y(Vertical::Center)
+            .spacing(5)
+            .padding(5)
+            .width(Length::Fill),
+        )
+        .max_height(HEADER_SIZE * 2);
+
+        let mut hover_buttons = row![].align_y(Vertical::Center).spacing(5);
+
+        if !trash.is_empty() {
+            hover_buttons = hover_buttons.push(
+                style::svg_button::danger("delete.svg", SUB_HEADING_SIZE)
+                    .height(Length::Fill)
+                    .on_press(Message::Window(WindowMessage::Page(
+                        id,
+                        PageMessage::Home(HomeMessage::EmptyTrash),
+                    ))),
+            );
+        }
+
+        let header: Element<'a, Message> = container(hover(
+            mouse_area(header).on_press(Message::Window(WindowMessage::Page(
+                id,
+                PageMessage::Home(HomeMessage::ExpandItem(TRASH_ID.to_string())),
+            ))),
+            right(hover_buttons).align_y(Vertical::Center),
+        ))
+        .max_height(HEADER_SIZE * 2)
+        .into();
+
+        let mut body = column![header].spacing(10);
+
+        if self.expanded.iter().any(|x| x == TRASH_ID) && !trash.is_empty() {
+            body = body.push(
+                container(
+                    row![
+                        space().width(10),
+                        column(trash.iter().map(|x| {
+                            let item = container(
+                                row![
+                                    svg(svg::Handle::from_path(get_path_assets(
+                                        match x.kind {
+                                            TrashKind::Chat => "chat.svg",
+                                            TrashKind::Folder => "folder.svg",
+                                            TrashKind::Prompt => "prompt.svg",
+                                        }
+                                        .to_string()
+                                    )))
+                                    .style(style::svg::text)
+                                    .width(SUB_HEADING_SIZE),
+                                    text(x.name.as_str()).size(BODY_SIZE),
+                                ]
+                                .align_y(Vertical::Center)
+                                .spacing(5)
+                                .padding(5)
+                                .width(Length::Fill),
+                            )
+                            .max_height(HEADER_SIZE * 2);
+
+                            let buttons = row![
+                                style::svg_button::text("restart.svg", SUB_HEADING_SIZE)
+                                    .height(Length::Fill)
+                                    .on_press(Message::Window(WindowMessage::Page(
+                                        id,
+                                        PageMessage::Home(HomeMessage::RestoreTrash(
+                                            x.kind,
+                                            x.id.clone(),
+                                        )),
+                                    ))),
+                                style::svg_button::danger("delete.svg", SUB_HEADING_SIZE)
+                                    .height(Length::Fill)
+                                    .on_press(Message::Window(WindowMessage::Page(
+                                        id,
+                                        PageMessage::Home(HomeMessage::PurgeTrash(
+                                            x.kind,
+                                            x.id.clone(),
+                                        )),
+                                    ))),
+                            ]
+                            .align_y(Vertical::Center)
+                            .spacing(5);
+
+                            container(hover(item, right(buttons).align_y(Vertical::Center)))
+                                .max_height(HEADER_SIZE * 2)
+                                .into()
+                        }))
+                        .spacing(0)
+                    ]
+                    .spacing(5),
+                )
+                .padding(Padding::from([6.0, 0.0]))
+                .style(iced::widget::container::transparent),
+            );
+            body = body.push(rule::horizontal(1).style(style::rule::translucent::text))
+        }
+
+        body.into()
+    }
+
     fn view_item<'a>(
         &'a self,
         id: window::Id,
@@ -640,13 +809,26 @@ impl HomeSideBar {
                         ))),
                 );
             }
-        } else if is_in_special == IsInSpecial::None {
+        } else {
+            if is_in_special == IsInSpecial::None {
+                hover_buttons = hover_buttons.push(
+                    style::svg_button::text("thumbs_up.svg", SUB_HEADING_SIZE)
+                        .height(Length::Fill)
+                        .on_press(Message::Window(WindowMessage::Page(
+                            id,
+                            PageMessage::Home(HomeMessage::FavChat(
+                                item.get_record_id().key().to_string(),
+                            )),
+                        ))),
+                );
+            }
+
             hover_buttons = hover_buttons.push(
-                style::svg_button::text("thumbs_up.svg", SUB_HEADING_SIZE)
+                style::svg_button::text("save_as.svg", SUB_HEADING_SIZE)
                     .height(Length::Fill)
                     .on_press(Message::Window(WindowMessage::Page(
                         id,
-                        PageMessage::Home(HomeMessage::FavChat(
+                        PageMessage::Home(HomeMessage::ExportChat(
                             item.get_record_id().key().to_string(),
                         )),
                     ))),
@@ -836,6 +1018,14 @@ impl HomeSideBar {
             .style(style::button::rounded_primary_blend)
             .padding(Padding::from(10));
 
+        let import_chats = style::svg_button::text("file_open.svg", HEADER_SIZE)
+            .on_press(Message::Window(WindowMessage::Page(
+                id,
+                PageMessage::Home(HomeMessage::ImportChats),
+            )))
+            .style(style::button::rounded_primary_blend)
+            .padding(Padding::from(10));
+
         let search = style::svg_input::primary(
             Some(String::from("search.svg")),
             text_input("Search chats...", &self.search)
@@ -879,12 +1069,29 @@ impl HomeSideBar {
             column![
                 name,
                 center_x(
-                    row![new_chat, new_folder]
+                    row![new_chat, new_folder, import_chats]
                         .spacing(10)
                         .align_y(Vertical::Center)
                 ),
                 search,
                 previews,
+                if self.search.is_empty() {
+                    self.view_trash(app, id)
+                } else {
+                    Element::from(space())
+                },
+                if self.search.is_empty() || self.items.matches.is_empty() {
+                    Element::from(space())
+                } else {
+                    column![
+                        text("Messages")
+                            .font(get_bold_font())
+                            .size(SUB_HEADING_SIZE),
+                        self.view_matches(id)
+                    ]
+                    .spacing(5)
+                    .into()
+                },
                 space::vertical()
             ]
             .spacing(10)
@@ -16,13 +16,11 @@ use iced::{
     window,
 };
 use iced_selection::text;
-use ochat_common::data::RequestType;
 use ochat_types::{
     providers::{Provider, ProviderData, ProviderDataBuilder, ProviderType},
     settings::{SettingsData, SettingsProvider, SettingsProviderBuilder},
     surreal::RecordId,
 };
-use serde_json::Value;
 
 #[derive(Debug, Clone)]
 pub struct SetupPage {
@@ -99,24 +97,12 @@ impl SetupMessage {
                     .remove(index);
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<Option<Provider>, ProviderData>(
-                            "provider/",
-                            &input,
-                            RequestType::Post,
-                        )
-                        .await
-                    {
+                    match req.add_provider(&input).await {
                         Ok(Some(provider)) => {
-                            let provider_models: Result<Vec<Value>, String> = req
-                                .make_request(
-                                    &format!("provider/{}/model/all/", provider.id.key()),
-                                    &(),
-                                    RequestType::Get,
-                                )
-                                .await;
-
-                            match provider_models {
+                            match req
+                                .list_all_provider_models(&provider.id.key().to_string())
+                                .await
+                            {
                                 Ok(provider_models) => {
                                     let mut models = Vec::new();
 
@@ -124,19 +110,19 @@ impl SetupMessage {
                                         models.push(
                                             SettingsProviderBuilder::default()
                                                 .provider(provider.id.key().to_string())
-                                                .model(model["id"].as_str().unwrap().to_string())
+                                                .model(model.id)
                                                 .build()
                                                 .unwrap(),
                                         );
                                     }
                                     DATA.write().unwrap().models.append(&mut models);
                                 }
-                                Err(e) => return Message::Err(e),
+                                Err(e) => return Message::Err(e.to_string()),
                             }
                             DATA.write().unwrap().providers.push(provider);
                             Message::None
                         }
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                         _ => Message::None,
                     }
                 })
@@ -150,16 +136,9 @@ impl SetupMessage {
 
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
-                    match req
-                        .make_request::<Value, ()>(
-                            &format!("provider/{}", id.key().to_string()),
-                            &(),
-                            RequestType::Delete,
-                        )
-                        .await
-                    {
+                    match req.delete_provider(&id.key().to_string()).await {
                         Ok(_) => Message::None,
-                        Err(e) => Message::Err(e),
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
@@ -213,12 +192,9 @@ impl SetupMessage {
 
 async fn save_settings(settings: SettingsData) -> Message {
     let req = DATA.read().unwrap().to_request();
-    match req
-        .make_request::<Value, SettingsData>("settings/", &settings, RequestType::Put)
-        .await
-    {
+    match req.update_settings(&settings).await {
         Ok(_) => Message::None,
-        Err(e) => Message::Err(e),
+        Err(e) => Message::Err(e.to_string()),
     }
 }
 
@@ -81,23 +81,10 @@ pub fn pull_stream(
     let req = DATA.read().unwrap().to_request();
 
     sipper(async move |mut output| {
-        let mut response = req
-            .get_client()
-            .post(&format!(
-                "{0}/provider/hf/{1}/model/{2}/{3}",
-                req.url,
-                match model_type {
-                    ModelType::Text => "text",
-                    ModelType::Stt => "stt",
-                    ModelType::Tts => "tts",
-                },
-                model,
-                name
-            ))
-            .send()
-            .await
-            .unwrap()
-            .bytes_stream();
+        let mut response = match req.pull_hf_model(&model_type, &model, &name).await {
+            Ok(x) => x.bytes_stream(),
+            Err(e) => return Err(e.to_string()),
+        };
 
         while let Some(status) = response.next().await {
             let _ = match serde_json::from_slice::<HFPullModelStreamResult>(&status.unwrap()) {
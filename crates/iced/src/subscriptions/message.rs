@@ -4,13 +4,19 @@ use iced::{
     futures::StreamExt,
     task::{Straw, sipper},
 };
-use ochat_types::generation::text::{ChatQueryData, ChatResponse, ChatStreamResult};
+use ochat_types::generation::text::{
+    ChatQueryData, ChatResponse, ChatStreamResult, Citation, TextJobData,
+};
 
 #[derive(Debug, Clone)]
 pub struct MessageGen {
     pub id: String,
-    pub query: ChatQueryData,
+    /// Starts a new job when set, otherwise attaches to the one already running.
+    pub query: Option<ChatQueryData>,
     pub state: ChatStreamResult,
+    /// What the server is busy with while no text is arriving, like running a tool.
+    pub status: Option<String>,
+    pub citations: Vec<Citation>,
 }
 
 pub enum MessageGenUpdate {
@@ -19,11 +25,13 @@ pub enum MessageGenUpdate {
 }
 
 impl MessageGen {
-    pub fn new(id: String, query: ChatQueryData) -> Self {
+    pub fn new(id: String, query: Option<ChatQueryData>) -> Self {
         Self {
             id,
             query,
             state: ChatStreamResult::Idle,
+            status: None,
+            citations: Vec::new(),
         }
     }
 
@@ -31,7 +39,7 @@ impl MessageGen {
         match self.state {
             ChatStreamResult::Err(_) | ChatStreamResult::Finished | ChatStreamResult::Idle => {
                 let (task, _handle) = Task::sip(
-                    gen_stream(self.query.clone()),
+                    gen_stream(self.id.clone(), self.query.clone()),
                     MessageGenUpdate::Generating,
                     MessageGenUpdate::Finished,
                 )
@@ -50,18 +58,28 @@ impl MessageGen {
     }
 }
 
-pub fn gen_stream(query: ChatQueryData) -> impl Straw<(), ChatStreamResult, String> {
+pub fn gen_stream(
+    message: String,
+    query: Option<ChatQueryData>,
+) -> impl Straw<(), ChatStreamResult, String> {
     let req = DATA.read().unwrap().to_request();
 
     sipper(async move |mut output| {
-        let mut response = req
-            .get_client()
-            .get(&format!("{}/generation/text/stream/", req.url))
-            .json(&query)
-            .send()
-            .await
-            .unwrap()
-            .bytes_stream();
+        if let Some(query) = query {
+            let data = TextJobData {
+                message: message.clone(),
+                query,
+            };
+
+            if let Err(e) = req.start_text_job(&data).await {
+                return Err(e.to_string());
+            }
+        }
+
+        let mut response = match req.attach_text_job(&message).await {
+            Ok(x) => x.bytes_stream(),
+            Err(e) => return Err(e.to_string()),
+        };
 
         while let Some(status) = response.next().await {
             let _ = match serde_json::from_slice::<ChatStreamResult>(&status.unwrap()) {
@@ -2,6 +2,7 @@
 use crate::subscriptions::{player::PlayerFinish, recorder::RecorderFinish};
 use crate::{
     Application, CacheMessage, DATA, Message, PopUp,
+    pages::home::panes::data::MessageMk,
     subscriptions::{
         hf_pull::{HFPull, HFPullUpdate},
         message::{MessageGen, MessageGenUpdate},
@@ -9,11 +10,12 @@ use crate::{
     },
 };
 use iced::{Subscription, Task, widget::markdown, window};
-use ochat_common::data::{Data, RequestType};
+use ochat_common::data::Data;
 #[cfg(feature = "sound")]
 use ochat_types::generation::tts::TtsResponse;
 use ochat_types::{
-    chats::{messages::MessageData, previews::Preview},
+    chats::messages::Role,
+    errors::ErrorCode,
     generation::text::{ChatQueryData, ChatStreamResult},
     providers::{
         hf::{HFModel, HFPullModelStreamResult, ModelType},
@@ -41,6 +43,7 @@ pub mod recorder;
 #[derive(Debug, Clone)]
 pub enum SubMessage {
     GenMessage(String, ChatQueryData),
+    AttachMessage(String),
     GeneratingMessage(u32, ChatStreamResult),
     StopGenMessage(u32),
     OllamaPull(OllamaModelsInfo, SettingsProvider),
@@ -67,26 +70,21 @@ pub enum SubMessage {
 impl SubMessage {
     pub fn handle(self, app: &mut Application) -> Task<Message> {
         match self {
-            Self::GenMessage(x, query) => {
-                let id = app.subscriptions.counter.clone();
-                app.subscriptions.counter += 1;
-                app.subscriptions
-                    .message_gens
-                    .insert(id, MessageGen::new(x, query));
+            Self::GenMessage(x, query) => start_message_gen(app, MessageGen::new(x, Some(query))),
+            Self::AttachMessage(x) => {
+                if app.subscriptions.message_gens.values().any(|y| y.id == x) {
+                    return Task::none();
+                }
 
-                app.subscriptions
-                    .message_gens
-                    .get_mut(&id)
-                    .unwrap()
-                    .start()
-                    .map(move |x| {
-                        let x = match x {
-                            MessageGenUpdate::Generating(x) => x,
-                            MessageGenUpdate::Finished(Ok(_)) => ChatStreamResult::Finished,
-                            MessageGenUpdate::Finished(Err(e)) => ChatStreamResult::Err(e),
-                        };
-                        Message::Subscription(SubMessage::GeneratingMessage(id, x))
-                    })
+                // The job replays everything generated so far, so start from an empty message.
+                if let Some(msg) = app.cache.home_shared.messages.0.get_mut(&x) {
+                    msg.base.content.clear();
+                    msg.base.thinking = None;
+                    msg.content = markdown::Content::parse("");
+                    msg.thinking = None;
+                }
+
+                start_message_gen(app, MessageGen::new(x, None))
             }
             Self::GeneratingMessage(id, ChatStreamResult::Finished) => {
                 let message_id = if let Some(x) = app.subscriptions.message_gens.remove(&id) {
@@ -107,16 +105,9 @@ impl SubMessage {
                     let req = DATA.read().unwrap().to_request();
 
                     if let Some(id) = chat_id {
-                        match req
-                            .make_request::<Preview, ()>(
-                                &format!("preview/{}", id),
-                                &(),
-                                RequestType::Put,
-                            )
-                            .await
-                        {
+                        match req.update_preview(&id).await {
                             Ok(_) => Message::Cache(CacheMessage::ResetSideBarItems),
-                            Err(e) => Message::Err(e),
+                            Err(e) => Message::Err(e.to_string()),
                         }
                     } else {
                         Message::None
@@ -131,7 +122,8 @@ impl SubMessage {
                     return Task::none();
                 };
 
-                let (id, msg) = if let Some(msg) = app.cache.home_shared.messages.0.get_mut(&key) {
+                // The server job has already saved the result.
+                if let Some(msg) = app.cache.home_shared.messages.0.get_mut(&key) {
                     msg.content = markdown::Content::parse(&result.content);
                     msg.thinking = result
                         .thinking
@@ -139,33 +131,47 @@ impl SubMessage {
                         .map(|x| markdown::Content::parse(&x));
                     msg.base.content = result.content;
                     msg.base.thinking = result.thinking;
-                    (
-                        msg.base.id.key().to_string(),
-                        Into::<MessageData>::into(msg.base.clone()),
-                    )
-                } else {
+                    msg.base.metrics = result.metrics;
+                }
+
+                let parent = app
+                    .cache
+                    .home_shared
+                    .messages
+                    .0
+                    .values()
+                    .find(|x| x.base.children.contains(&key))
+                    .map(|x| x.base.id.key().to_string());
+
+                let Some(parent) = parent.filter(|_| !result.func_calls.is_empty()) else {
                     return Task::none();
                 };
 
+                // The server put the tool calls in a message of their own above the answer.
                 Task::future(async move {
                     let req = DATA.read().unwrap().to_request();
 
-                    match req
-                        .make_request::<ochat_types::chats::messages::Message, MessageData>(
-                            &format!("message/{}", id),
-                            &msg,
-                            RequestType::Put,
-                        )
-                        .await
-                    {
-                        Ok(_) => Message::None,
-                        Err(e) => Message::Err(e),
+                    match req.list_all_messages_from_parent(&parent).await {
+                        Ok(x) => match x
+                            .into_iter()
+                            .find(|x| x.role == Role::Function && x.children.contains(&key))
+                        {
+                            Some(x) => Message::Cache(CacheMessage::InsertMessageAbove(
+                                key,
+                                MessageMk::get(x).await,
+                            )),
+                            None => Message::None,
+                        },
+                        Err(e) => Message::Err(e.to_string()),
                     }
                 })
             }
             Self::GeneratingMessage(id, ChatStreamResult::Generating(result)) => {
                 let key = if let Some(x) = app.subscriptions.message_gens.get_mut(&id) {
                     x.progress(ChatStreamResult::Generating(result.clone()));
+                    if !result.content.is_empty() {
+                        x.status = None;
+                    }
                     x.id.clone()
                 } else {
                     return Task::none();
@@ -191,6 +197,32 @@ impl SubMessage {
 
                 Task::none()
             }
+            Self::GeneratingMessage(
+                id,
+                result @ (ChatStreamResult::ToolCall(_)
+                | ChatStreamResult::ToolResult(_)
+                | ChatStreamResult::Citations(_)
+                | ChatStreamResult::Status(_)),
+            ) => {
+                let Some(x) = app.subscriptions.message_gens.get_mut(&id) else {
+                    return Task::none();
+                };
+
+                match result {
+                    ChatStreamResult::ToolCall(call) => x.status = Some(call.describe()),
+                    ChatStreamResult::Citations(citations) => {
+                        for citation in citations {
+                            if !x.citations.contains(&citation) {
+                                x.citations.push(citation);
+                            }
+                        }
+                    }
+                    ChatStreamResult::Status(status) => x.status = Some(status),
+                    _ => {}
+                }
+
+                Task::none()
+            }
             Self::GeneratingMessage(id, result) => {
                 if let ChatStreamResult::Err(e) = &result {
                     app.add_popup(PopUp::Err(e.to_string()));
@@ -202,8 +234,18 @@ impl SubMessage {
                 Task::none()
             }
             Self::StopGenMessage(id) => {
-                app.subscriptions.message_gens.remove(&id);
-                Task::none()
+                let Some(message) = app.subscriptions.message_gens.remove(&id) else {
+                    return Task::none();
+                };
+
+                Task::future(async move {
+                    let req = DATA.read().unwrap().to_request();
+
+                    match req.cancel_text_job(&message.id).await {
+                        Err(e) if !e.is(ErrorCode::NotFound) => Message::Err(e.to_string()),
+                        _ => Message::None,
+                    }
+                })
             }
             Self::OllamaPull(data, model) => {
                 let id = app.subscriptions.counter.clone();
@@ -464,6 +506,26 @@ impl SubMessage {
     }
 }
 
+fn start_message_gen(app: &mut Application, message: MessageGen) -> Task<Message> {
+    let id = app.subscriptions.counter.clone();
+    app.subscriptions.counter += 1;
+    app.subscriptions.message_gens.insert(id, message);
+
+    app.subscriptions
+        .message_gens
+        .get_mut(&id)
+        .unwrap()
+        .start()
+        .map(move |x| {
+            let x = match x {
+                MessageGenUpdate::Generating(x) => x,
+                MessageGenUpdate::Finished(Ok(_)) => ChatStreamResult::Finished,
+                MessageGenUpdate::Finished(Err(e)) => ChatStreamResult::Err(e),
+            };
+            Message::Subscription(SubMessage::GeneratingMessage(id, x))
+        })
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Subscriptions {
     pub counter: u32,
@@ -75,16 +75,10 @@ pub fn pull_stream(
     let req = DATA.read().unwrap().to_request();
 
     sipper(async move |mut output| {
-        let mut response = req
-            .get_client()
-            .post(&format!(
-                "{0}/provider/{1}/model/{2}",
-                req.url, provider, model
-            ))
-            .send()
-            .await
-            .unwrap()
-            .bytes_stream();
+        let mut response = match req.pull_ollama_model(&provider, &model).await {
+            Ok(x) => x.bytes_stream(),
+            Err(e) => return Err(e.to_string()),
+        };
 
         while let Some(status) = response.next().await {
             let _ = match serde_json::from_slice::<OllamaPullModelStreamResult>(&status.unwrap()) {
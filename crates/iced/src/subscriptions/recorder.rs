@@ -4,11 +4,7 @@ use iced::{
     futures::StreamExt,
     task::{Straw, sipper},
 };
-use ochat_common::data::RequestType;
-use ochat_types::{
-    generation::stt::{SttQueryData, SttResponse},
-    settings::SettingsProvider,
-};
+use ochat_types::{generation::stt::SttQueryData, settings::SettingsProvider};
 use std::{
     fmt::Debug,
     rc::Rc,
@@ -101,14 +97,7 @@ pub fn record_stream(model: Option<SettingsProvider>) -> impl Straw<(), Recorder
             spec: ochat_types::generation::SoundSpec { sample_rate: 16000 },
         };
 
-        let text = req
-            .make_request::<SttResponse, SttQueryData>(
-                &"generation/stt/run/",
-                &final_data,
-                RequestType::Get,
-            )
-            .await?
-            .text;
+        let text = req.run_stt(&final_data).await?.text;
 
         let _ = output.send(RecorderState::Completed(text)).await;
 
@@ -0,0 +1,60 @@
+use crate::backend::{CONN, errors::ServerError};
+use axum::{Json, extract::Path};
+use ochat_types::assistants::{Assistant, AssistantData};
+pub(crate) const ASSISTANTS_TABLE: &str = "assistants";
+
+pub mod route;
+
+pub async fn define_assistants() -> Result<(), ServerError> {
+    let _ = CONN
+        .query(&format!(
+            "
+DEFINE TABLE IF NOT EXISTS {0} SCHEMAFULL
+    PERMISSIONS FOR select, update, delete WHERE user_id = record::id($auth.id) FOR create FULL;
+DEFINE FIELD IF NOT EXISTS user_id ON TABLE {0} TYPE string DEFAULT ALWAYS record::id($auth.id);
+DEFINE FIELD IF NOT EXISTS name ON TABLE {0} TYPE string;
+DEFINE FIELD IF NOT EXISTS avatar ON TABLE {0} TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS model ON TABLE {0} TYPE object;
+DEFINE FIELD IF NOT EXISTS model.provider ON TABLE {0} TYPE string;
+DEFINE FIELD IF NOT EXISTS model.model ON TABLE {0} TYPE string;
+DEFINE FIELD IF NOT EXISTS system_prompt ON TABLE {0} TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS options ON TABLE {0} TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS tools ON TABLE {0} TYPE array<string> DEFAULT [];
+DEFINE FIELD IF NOT EXISTS starters ON TABLE {0} TYPE array<string> DEFAULT [];
+",
+            ASSISTANTS_TABLE,
+        ))
+        .await?;
+    Ok(())
+}
+
+pub async fn add_assistant(
+    Json(assistant): Json<AssistantData>,
+) -> Result<Json<Option<Assistant>>, ServerError> {
+    Ok(Json(
+        CONN.create(ASSISTANTS_TABLE).content(assistant).await?,
+    ))
+}
+
+pub async fn update_assistant(
+    id: Path<String>,
+    Json(assistant): Json<AssistantData>,
+) -> Result<Json<Option<Assistant>>, ServerError> {
+    Ok(Json(
+        CONN.update((ASSISTANTS_TABLE, id.trim()))
+            .content(assistant)
+            .await?,
+    ))
+}
+
+pub async fn get_assistant(id: Path<String>) -> Result<Json<Option<Assistant>>, ServerError> {
+    Ok(Json(CONN.select((ASSISTANTS_TABLE, id.trim())).await?))
+}
+
+pub async fn delete_assistant(id: Path<String>) -> Result<Json<Option<Assistant>>, ServerError> {
+    Ok(Json(CONN.delete((ASSISTANTS_TABLE, id.trim())).await?))
+}
+
+pub async fn list_all_assistants() -> Result<Json<Vec<Assistant>>, ServerError> {
+    Ok(Json(CONN.select(ASSISTANTS_TABLE).await?))
+}
@@ -0,0 +1,17 @@
+use crate::backend::assistants;
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/assistant/", post(assistants::add_assistant))
+        .route("/assistant/all/", get(assistants::list_all_assistants))
+        .route(
+            "/assistant/{id}",
+            get(assistants::get_assistant)
+                .put(assistants::update_assistant)
+                .delete(assistants::delete_assistant),
+        )
+}
@@ -0,0 +1,526 @@
+use crate::backend::{
+    CONN,
+    chats::{
+        get_chat, list_all_chats,
+        messages::{
+            get_all_messages_from_root, get_default_message_list_from_parent, get_message_tree,
+            read_message,
+        },
+        previews::PREVIEW_TABLE,
+    },
+    errors::ServerError,
+    files::get_file,
+};
+use axum::{
+    Json,
+    extract::Path,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use ochat_types::{
+    chats::{
+        export::{
+            CHAT_EXPORT_VERSION, ChatExport, DatasetFormat, DatasetMessage, DpoRecord,
+            ExportFormat, SftRecord,
+        },
+        messages::{Message, Rating, Role},
+        previews::Preview,
+    },
+    files::{B64File, FileType},
+};
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, html::push_html};
+use std::{collections::HashMap, fmt::Write};
+
+pub async fn get_chat_export(id: &str) -> Result<ChatExport, ServerError> {
+    let Some(chat) = get_chat(Path(id.to_string())).await?.0 else {
+        return Err(ServerError::NotFound(format!(
+            "Chat '{}' does not exist.",
+            id
+        )));
+    };
+
+    let preview: Option<Preview> = CONN.select((PREVIEW_TABLE, id.trim())).await?;
+    let mut messages = Vec::new();
+
+    if let Some(root) = &chat.root {
+        if let Some(x) = read_message(Path(root.clone())).await?.0 {
+            messages.push(x);
+        }
+        messages.append(&mut get_all_messages_from_root(Path(root.clone())).await?.0);
+    }
+
+    Ok(ChatExport {
+        version: CHAT_EXPORT_VERSION,
+        title: preview.map(|x| x.text),
+        files: get_files(&messages).await?,
+        messages,
+        chat,
+    })
+}
+
+async fn get_files(messages: &[Message]) -> Result<Vec<B64File>, ServerError> {
+    let mut files: Vec<B64File> = Vec::new();
+
+    for id in messages.iter().flat_map(|x| x.files.iter()) {
+        if files.iter().any(|x| x.id.key().to_string() == id.trim()) {
+            continue;
+        }
+
+        if let Some(file) = get_file(Path(id.clone())).await?.0 {
+            files.push(file);
+        }
+    }
+
+    Ok(files)
+}
+
+async fn get_active_branch(export: &ChatExport) -> Result<Vec<Message>, ServerError> {
+    Ok(match &export.chat.root {
+        Some(root) => {
+            get_default_message_list_from_parent(Path(root.clone()))
+                .await?
+                .0
+        }
+        None => Vec::new(),
+    })
+}
+
+fn get_title(export: &ChatExport) -> String {
+    export
+        .title
+        .clone()
+        .filter(|x| !x.trim().is_empty())
+        .unwrap_or(String::from("New Chat"))
+}
+
+fn get_author(message: &Message) -> String {
+    match (&message.role, &message.model) {
+        (Role::AI, Some(model)) => {
+            format!("{} ({} : {})", message.role, model.provider, model.model)
+        }
+        _ => message.role.to_string(),
+    }
+}
+
+fn find_file<'a>(export: &'a ChatExport, id: &str) -> Option<&'a B64File> {
+    export
+        .files
+        .iter()
+        .find(|x| x.id.key().to_string() == id.trim())
+}
+
+pub fn chat_to_markdown(export: &ChatExport, messages: &[Message]) -> String {
+    let mut out = format!("# {}\n\n", get_title(export));
+
+    for message in messages {
+        let _ = writeln!(
+            out,
+            "## {}\n\n_{}_\n",
+            get_author(message),
+            message.time.0.format("%Y-%m-%d %H:%M")
+        );
+
+        if let Some(thinking) = message.thinking.as_ref().filter(|x| !x.trim().is_empty()) {
+            let _ = writeln!(
+                out,
+                "<details>\n<summary>Thinking</summary>\n\n{}\n\n</details>\n",
+                thinking.trim()
+            );
+        }
+
+        let _ = writeln!(out, "{}\n", message.content.trim());
+
+        for file in message.files.iter().filter_map(|x| find_file(export, x)) {
+            let _ = writeln!(out, "> Attached {} : `{}`\n", file.file_type, file.filename);
+        }
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether opening the url would run a script instead of going somewhere.
+fn is_script_url(url: &str) -> bool {
+    let url = url
+        .chars()
+        .filter(|x| !x.is_whitespace() && !x.is_control())
+        .collect::<String>()
+        .to_lowercase();
+
+    ["javascript:", "vbscript:", "data:text/html"]
+        .iter()
+        .any(|x| url.starts_with(x))
+}
+
+fn markdown_to_html(text: &str) -> String {
+    let parser = Parser::new_ext(
+        text,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS,
+    )
+    .map(|mut event| {
+        if let Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) = &mut event
+            && is_script_url(dest_url)
+        {
+            *dest_url = CowStr::Borrowed("");
+        }
+
+        match event {
+            Event::Html(x) | Event::InlineHtml(x) => Event::Text(x),
+            x => x,
+        }
+    });
+
+    let mut html = String::new();
+    push_html(&mut html, parser);
+    html
+}
+
+/// The mime type a browser can play the file as, files of any other type are only offered as a download.
+fn get_mime(file: &B64File) -> Option<&'static str> {
+    let extension = file.filename.rsplit_once('.')?.1.to_lowercase();
+
+    Some(match (&file.file_type, extension.as_str()) {
+        (FileType::Image, "png") => "image/png",
+        (FileType::Image, "jpg" | "jpeg") => "image/jpeg",
+        (FileType::Image, "gif") => "image/gif",
+        (FileType::Image, "webp") => "image/webp",
+        (FileType::Image, "bmp") => "image/bmp",
+        (FileType::Audio, "mp3") => "audio/mpeg",
+        (FileType::Audio, "wav") => "audio/wav",
+        (FileType::Audio, "ogg") => "audio/ogg",
+        (FileType::Audio, "flac") => "audio/flac",
+        (FileType::Audio, "m4a") => "audio/mp4",
+        (FileType::Video, "mp4") => "video/mp4",
+        (FileType::Video, "webm") => "video/webm",
+        (FileType::Video, "ogv") => "video/ogg",
+        _ => return None,
+    })
+}
+
+fn file_to_html(file: &B64File) -> String {
+    let data = escape_html(&file.b64data);
+
+    match (&file.file_type, get_mime(file)) {
+        (FileType::Image, Some(mime)) => format!(
+            "<img src=\"data:{};base64,{}\" alt=\"{}\">",
+            mime,
+            data,
+            escape_html(&file.filename)
+        ),
+        (FileType::Audio, Some(mime)) => format!(
+            "<audio controls src=\"data:{};base64,{}\"></audio>",
+            mime, data
+        ),
+        (FileType::Video, Some(mime)) => format!(
+            "<video controls src=\"data:{};base64,{}\"></video>",
+            mime, data
+        ),
+        _ => format!(
+            "<a download=\"{0}\" href=\"data:application/octet-stream;base64,{1}\">{0}</a>",
+            escape_html(&file.filename),
+            data
+        ),
+    }
+}
+
+pub fn chat_to_html(export: &ChatExport, messages: &[Message]) -> String {
+    let title = escape_html(&get_title(export));
+    let mut body = String::new();
+
+    for message in messages {
+        let _ = write!(
+            body,
+            "<article class=\"{}\">\n<header><strong>{}</strong><time>{}</time></header>\n",
+            match message.role {
+                Role::User => "user",
+                Role::AI => "ai",
+                Role::Function => "function",
+                Role::System => "system",
+            },
+            escape_html(&get_author(message)),
+            message.time.0.format("%Y-%m-%d %H:%M")
+        );
+
+        if let Some(thinking) = message.thinking.as_ref().filter(|x| !x.trim().is_empty()) {
+            let _ = write!(
+                body,
+                "<details><summary>Thinking</summary>\n{}</details>\n",
+                markdown_to_html(thinking)
+            );
+        }
+
+        body.push_str(&markdown_to_html(&message.content));
+
+        for file in message.files.iter().filter_map(|x| find_file(export, x)) {
+            let _ = writeln!(body, "<figure>{}</figure>", file_to_html(file));
+        }
+
+        body.push_str("</article>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{0}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 860px; margin: 2em auto; padding: 0 1em; background: #1e1e2e; color: #cdd6f4; }}
+article {{ border-radius: 8px; padding: 0.5em 1em; margin: 1em 0; background: #313244; }}
+article.user {{ background: #45475a; }}
+header {{ display: flex; justify-content: space-between; opacity: 0.8; }}
+pre {{ overflow-x: auto; background: #11111b; padding: 0.5em; border-radius: 4px; }}
+img, video {{ max-width: 100%; }}
+a {{ color: #89b4fa; }}
+details {{ opacity: 0.7; }}
+</style>
+</head>
+<body>
+<h1>{0}</h1>
+{1}</body>
+</html>
+",
+        title, body
+    )
+}
+
+pub async fn export_chat(
+    Path((id, format)): Path<(String, ExportFormat)>,
+) -> Result<Response, ServerError> {
+    let export = get_chat_export(&id).await?;
+    let filename = format!(
+        "attachment; filename=\"chat-{}.{}\"",
+        id.trim(),
+        format.extension()
+    );
+
+    Ok(match format {
+        ExportFormat::Json => {
+            ([(header::CONTENT_DISPOSITION, filename)], Json(export)).into_response()
+        }
+        ExportFormat::Markdown => (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    String::from("text/markdown; charset=utf-8"),
+                ),
+                (header::CONTENT_DISPOSITION, filename),
+            ],
+            chat_to_markdown(&export, &get_active_branch(&export).await?),
+        )
+            .into_response(),
+        ExportFormat::Html => (
+            [
+                (
+                    header::CONTENT_TYPE,
+                    String::from("text/html; charset=utf-8"),
+                ),
+                (header::CONTENT_DISPOSITION, filename),
+            ],
+            chat_to_html(&export, &get_active_branch(&export).await?),
+        )
+            .into_response(),
+    })
+}
+
+fn has_rating(message: &Message, rating: Rating) -> bool {
+    message.role == Role::AI && message.rating.as_ref().is_some_and(|x| x.rating == rating)
+}
+
+/// Walks up from the message to the root, returning the conversation in order.
+fn get_conversation<'a>(
+    tree: &'a HashMap<String, Message>,
+    parents: &HashMap<String, String>,
+    id: &str,
+) -> Vec<&'a Message> {
+    let mut conversation = Vec::new();
+    let mut current = Some(id.trim().to_string());
+
+    while let Some(id) = current
+        && conversation.len() < tree.len()
+    {
+        if let Some(x) = tree.get(&id) {
+            conversation.push(x);
+        }
+        current = parents.get(&id).cloned();
+    }
+
+    conversation.reverse();
+    conversation
+}
+
+/// Builds a JSONL fine-tuning dataset from the rated AI messages of every chat.
+pub async fn export_dataset(Path(format): Path<DatasetFormat>) -> Result<Response, ServerError> {
+    let mut lines = Vec::new();
+
+    for chat in list_all_chats().await?.0 {
+        let Some(root) = &chat.root else {
+            continue;
+        };
+
+        let tree = get_message_tree(root).await?;
+        let parents: HashMap<String, String> = tree
+            .iter()
+            .flat_map(|(id, x)| {
+                x.children
+                    .iter()
+                    .map(|y| (y.trim().to_string(), id.clone()))
+            })
+            .collect();
+
+        let mut messages: Vec<&Message> = tree.values().collect();
+        messages.sort_by(|a, b| a.time.0.cmp(&b.time.0));
+
+        let to_dataset = |messages: &[&Message]| {
+            let mut dataset: Vec<DatasetMessage> = chat
+                .system_prompt
+                .iter()
+                .filter(|x| !x.trim().is_empty())
+                .map(|x| DatasetMessage {
+                    role: String::from("system"),
+                    content: x.clone(),
+                })
+                .collect();
+            dataset.extend(messages.iter().map(|x| DatasetMessage::from(*x)));
+            dataset
+        };
+
+        for message in messages.iter() {
+            let id = message.id.key().to_string();
+
+            match format {
+                DatasetFormat::Dpo => {
+                    let rated = |rating: Rating| {
+                        message
+                            .children
+                            .iter()
+                            .filter_map(|x| tree.get(x.trim()))
+                            .filter(|x| has_rating(x, rating))
+                            .collect::<Vec<&Message>>()
+                    };
+
+                    let (chosen, rejected) = (rated(Rating::Up), rated(Rating::Down));
+                    if chosen.is_empty() || rejected.is_empty() {
+                        continue;
+                    }
+
+                    let prompt = to_dataset(&get_conversation(&tree, &parents, &id));
+                    for chosen in chosen.iter() {
+                        for rejected in rejected.iter() {
+                            lines.push(serde_json::to_string(&DpoRecord {
+                                prompt: prompt.clone(),
+                                chosen: vec![DatasetMessage::from(*chosen)],
+                                rejected: vec![DatasetMessage::from(*rejected)],
+                            })?);
+                        }
+                    }
+                }
+                DatasetFormat::Sft => {
+                    if !has_rating(message, Rating::Up) {
+                        continue;
+                    }
+
+                    let conversation = get_conversation(&tree, &parents, &id);
+                    if conversation.iter().any(|x| has_rating(x, Rating::Down)) {
+                        continue;
+                    }
+
+                    lines.push(serde_json::to_string(&SftRecord {
+                        messages: to_dataset(&conversation),
+                    })?);
+                }
+            }
+        }
+    }
+
+    let mut body = lines.join("\n");
+    if !body.is_empty() {
+        body.push('\n');
+    }
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                String::from("application/jsonl; charset=utf-8"),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.jsonl\"", format),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(filename: &str, file_type: FileType) -> B64File {
+        B64File {
+            user_id: String::new(),
+            b64data: String::from("AAAA"),
+            filename: filename.to_string(),
+            file_type,
+            id: ("files", "test").into(),
+        }
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn detects_script_urls() {
+        assert!(is_script_url("javascript:alert(1)"));
+        assert!(is_script_url(" JavaScript:alert(1)"));
+        assert!(is_script_url("java\tscript:alert(1)"));
+        assert!(is_script_url("vbscript:msgbox"));
+        assert!(is_script_url("data:text/html;base64,AAAA"));
+        assert!(!is_script_url("https://example.com"));
+        assert!(!is_script_url("data:image/png;base64,AAAA"));
+    }
+
+    #[test]
+    fn strips_script_links_and_raw_html() {
+        let html = markdown_to_html("[click](javascript:alert(1)) <script>alert(1)</script>");
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+
+        let html = markdown_to_html("![image](javascript:alert(1))");
+        assert!(!html.contains("javascript:"));
+
+        assert!(
+            markdown_to_html("[link](https://example.com)")
+                .contains("href=\"https://example.com\"")
+        );
+    }
+
+    #[test]
+    fn plays_only_known_media() {
+        assert_eq!(get_mime(&file("a.PNG", FileType::Image)), Some("image/png"));
+        assert_eq!(
+            get_mime(&file("a.mp3", FileType::Audio)),
+            Some("audio/mpeg")
+        );
+        assert_eq!(get_mime(&file("a.png", FileType::Audio)), None);
+        assert_eq!(get_mime(&file("image", FileType::Image)), None);
+
+        let html = file_to_html(&file("\"><script>.txt", FileType::File));
+        assert!(html.starts_with("<a download=\"&quot;&gt;&lt;script&gt;.txt\""));
+    }
+}
@@ -0,0 +1,696 @@
+use crate::backend::{
+    chats::{
+        branch_new_chat,
+        messages::{create_message_with_parent, get_default_message_list_from_parent},
+        previews::set_preview,
+        update_chat,
+    },
+    errors::ServerError,
+    files::create_file,
+    folders::{add_folder_chat, create_folder, get_folder_from_name},
+};
+use axum::{Json, extract::Path};
+use ochat_types::{
+    chats::{
+        Chat, ChatData,
+        export::{CHAT_EXPORT_VERSION, ChatExport},
+        import::{ImportData, ImportFormat, ImportResult, ImportedChat},
+        messages::{MessageData, ModelData, Role},
+    },
+    files::B64FileData,
+    folders::{Folder, FolderData},
+    surreal::Datetime,
+};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Largest import accepted, whole histories with their files go well past axum's 2 MB default.
+pub const IMPORT_BODY_LIMIT: usize = 512 * 1024 * 1024;
+
+/// A message tree read from an export, keyed by the ids the export used.
+#[derive(Default)]
+struct ImportTree {
+    title: Option<String>,
+    time: Option<Datetime>,
+    current: Option<String>,
+    roots: Vec<String>,
+    nodes: HashMap<String, ImportNode>,
+    files: Vec<(String, B64FileData)>,
+}
+
+#[derive(Default)]
+struct ImportNode {
+    message: Option<MessageData>,
+    children: Vec<String>,
+}
+
+impl ImportTree {
+    /// Skips nodes without a message, handing their children to the closest kept parent.
+    fn kept(&self, keys: &[String]) -> Vec<String> {
+        let mut kept = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&String> = keys.iter().rev().collect();
+
+        while let Some(key) = stack.pop() {
+            if !seen.insert(key) {
+                continue;
+            }
+
+            match self.nodes.get(key) {
+                Some(node) if node.message.is_some() => kept.push(key.clone()),
+                Some(node) => stack.extend(node.children.iter().rev()),
+                None => {}
+            }
+        }
+
+        kept
+    }
+
+    fn children(&self, key: &str) -> Vec<String> {
+        self.nodes
+            .get(key)
+            .map(|x| self.kept(&x.children))
+            .unwrap_or_default()
+    }
+
+    /// The branch leading to the current message, or the first branch when there is none.
+    fn active_path(&self) -> Vec<String> {
+        let roots = self.kept(&self.roots);
+        let mut parents: HashMap<String, String> = HashMap::new();
+        let mut queue: VecDeque<String> = roots.iter().cloned().collect();
+
+        while let Some(key) = queue.pop_front() {
+            for child in self.children(&key) {
+                if !parents.contains_key(&child) && !roots.contains(&child) {
+                    let _ = parents.insert(child.clone(), key.clone());
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        let mut path = Vec::new();
+
+        if let Some(mut key) = self
+            .current
+            .clone()
+            .filter(|x| parents.contains_key(x) || roots.contains(x))
+        {
+            path.push(key.clone());
+            while let Some(parent) = parents.get(&key) {
+                path.push(parent.clone());
+                key = parent.clone();
+            }
+            path.reverse();
+        } else if let Some(root) = roots.first() {
+            path.push(root.clone());
+            while let Some(child) = self
+                .children(path.last().unwrap())
+                .into_iter()
+                .find(|x| !path.contains(x))
+            {
+                path.push(child);
+            }
+        }
+
+        path
+    }
+
+    fn message(&self, key: &str, files: &HashMap<String, String>) -> MessageData {
+        let mut message = self
+            .nodes
+            .get(key)
+            .and_then(|x| x.message.clone())
+            .unwrap_or_default();
+
+        message.children.clear();
+        message.files = message
+            .files
+            .iter()
+            .filter_map(|x| files.get(x.trim()).cloned())
+            .collect();
+        message
+    }
+}
+
+fn get_str(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn get_children(value: &Value, key: &str) -> Vec<String> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(|x| {
+            x.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn get_time(value: &Value, key: &str) -> Option<Datetime> {
+    let secs = value.get(key).and_then(Value::as_f64)?;
+    // Open WebUI stores some times in milliseconds.
+    Datetime::from_timestamp(if secs > 1e11 { secs / 1000.0 } else { secs })
+}
+
+fn get_role(role: &str) -> Role {
+    match role.trim().to_lowercase().as_str() {
+        "assistant" | "ai" | "model" => Role::AI,
+        "system" | "developer" => Role::System,
+        "tool" | "function" => Role::Function,
+        _ => Role::User,
+    }
+}
+
+fn detect(value: &Value) -> ImportFormat {
+    if value.get("mapping").is_some() {
+        ImportFormat::ChatGPT
+    } else if value.get("version").is_some() && value.get("messages").is_some() {
+        ImportFormat::Ochat
+    } else {
+        ImportFormat::OpenWebUI
+    }
+}
+
+fn parse(value: &Value, format: ImportFormat) -> Result<ImportTree, String> {
+    match format {
+        ImportFormat::Auto => parse(value, detect(value)),
+        ImportFormat::ChatGPT => parse_chatgpt(value),
+        ImportFormat::OpenWebUI => parse_open_webui(value),
+        ImportFormat::Ochat => parse_ochat(value),
+    }
+}
+
+fn parse_chatgpt(value: &Value) -> Result<ImportTree, String> {
+    let Some(mapping) = value.get("mapping").and_then(Value::as_object) else {
+        return Err(String::from(
+            "Missing 'mapping', not a ChatGPT conversation.",
+        ));
+    };
+
+    let mut tree = ImportTree {
+        title: get_str(value, "title"),
+        time: get_time(value, "create_time"),
+        current: get_str(value, "current_node"),
+        ..Default::default()
+    };
+
+    for (key, node) in mapping {
+        if node.get("parent").and_then(Value::as_str).is_none() {
+            tree.roots.push(key.clone());
+        }
+
+        let message = node.get("message").filter(|x| !x.is_null()).and_then(|x| {
+            let content = x.get("content")?;
+            let text = match content.get("parts").and_then(Value::as_array) {
+                Some(parts) => parts
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<&str>>()
+                    .join("\n"),
+                None => get_str(content, "text").unwrap_or_default(),
+            };
+
+            let hidden = x
+                .pointer("/metadata/is_visually_hidden_from_conversation")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+
+            if hidden || text.trim().is_empty() {
+                return None;
+            }
+
+            let role = get_role(
+                x.pointer("/author/role")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default(),
+            );
+
+            Some(MessageData {
+                model: x
+                    .pointer("/metadata/model_slug")
+                    .and_then(Value::as_str)
+                    .filter(|_| role == Role::AI)
+                    .map(|model| ModelData {
+                        provider: String::from("ChatGPT"),
+                        model: model.to_string(),
+                    }),
+                time: get_time(x, "create_time"),
+                content: text,
+                role,
+                ..Default::default()
+            })
+        });
+
+        let _ = tree.nodes.insert(
+            key.clone(),
+            ImportNode {
+                message,
+                children: get_children(node, "children"),
+            },
+        );
+    }
+
+    Ok(tree)
+}
+
+fn parse_open_webui(value: &Value) -> Result<ImportTree, String> {
+    let chat = value.get("chat").unwrap_or(value);
+
+    let mut tree = ImportTree {
+        title: get_str(value, "title").or(get_str(chat, "title")),
+        time: get_time(value, "created_at").or(get_time(chat, "timestamp")),
+        ..Default::default()
+    };
+
+    let messages: Vec<&Value> = match chat.get("history") {
+        Some(history) => {
+            tree.current = get_str(history, "currentId");
+            history
+                .get("messages")
+                .and_then(Value::as_object)
+                .map(|x| x.values().collect())
+                .unwrap_or_default()
+        }
+        None => chat
+            .get("messages")
+            .and_then(Value::as_array)
+            .map(|x| x.iter().collect())
+            .unwrap_or_default(),
+    };
+
+    if messages.is_empty() {
+        return Err(String::from(
+            "Missing 'history' and 'messages', not an Open WebUI chat.",
+        ));
+    }
+
+    for message in messages {
+        let Some(id) = get_str(message, "id") else {
+            continue;
+        };
+
+        if message.get("parentId").and_then(Value::as_str).is_none() {
+            tree.roots.push(id.clone());
+        }
+
+        let role = get_role(&get_str(message, "role").unwrap_or_default());
+
+        let _ = tree.nodes.insert(
+            id,
+            ImportNode {
+                message: Some(MessageData {
+                    content: get_str(message, "content").unwrap_or_default(),
+                    model: get_str(message, "model")
+                        .filter(|_| role == Role::AI)
+                        .map(|model| ModelData {
+                            provider: String::from("Open WebUI"),
+                            model,
+                        }),
+                    time: get_time(message, "timestamp"),
+                    role,
+                    ..Default::default()
+                }),
+                children: get_children(message, "childrenIds"),
+            },
+        );
+    }
+
+    Ok(tree)
+}
+
+fn parse_ochat(value: &Value) -> Result<ImportTree, String> {
+    let export: ChatExport = serde_json::from_value(value.clone()).map_err(|e| e.to_string())?;
+
+    if export.version > CHAT_EXPORT_VERSION {
+        return Err(format!(
+            "Export version {} is newer than the supported version {}.",
+            export.version, CHAT_EXPORT_VERSION
+        ));
+    }
+
+    let mut tree = ImportTree {
+        title: export.title,
+        time: Some(export.chat.time),
+        roots: export.chat.root.into_iter().collect(),
+        files: export
+            .files
+            .into_iter()
+            .map(|x| {
+                (
+                    x.id.key().to_string(),
+                    B64FileData {
+                        user_id: None,
+                        b64data: x.b64data,
+                        filename: x.filename,
+                        file_type: x.file_type,
+                    },
+                )
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    for message in export.messages {
+        let id = message.id.key().to_string();
+        let children = message.children.clone();
+
+        let _ = tree.nodes.insert(
+            id,
+            ImportNode {
+                message: Some(message.into()),
+                children,
+            },
+        );
+    }
+
+    Ok(tree)
+}
+
+async fn import_tree(tree: ImportTree) -> Result<(Chat, String), ServerError> {
+    let path = tree.active_path();
+
+    if path.is_empty() {
+        return Err(ServerError::Validation(String::from(
+            "The chat has no messages.",
+        )));
+    }
+
+    let mut files = HashMap::new();
+    for (id, file) in tree.files.iter().cloned() {
+        if let Some(x) = create_file(Json(file)).await?.0 {
+            let _ = files.insert(id, x.id.key().to_string());
+        }
+    }
+
+    let Some(mut chat) =
+        branch_new_chat(Json(path.iter().map(|x| tree.message(x, &files)).collect()))
+            .await?
+            .0
+    else {
+        return Err(ServerError::Unknown(String::from(
+            "Failed to create the chat.",
+        )));
+    };
+
+    let chat_id = chat.id.key().to_string();
+    let mut ids: HashMap<String, String> = path
+        .iter()
+        .cloned()
+        .zip(
+            get_default_message_list_from_parent(Path(chat.root.clone().unwrap_or_default()))
+                .await?
+                .0
+                .into_iter()
+                .map(|x| x.id.key().to_string()),
+        )
+        .collect();
+
+    // The active branch was created first so it stays the default one, every other branch is added after it.
+    let mut queue = VecDeque::from([path[0].clone()]);
+    let mut seen = HashSet::new();
+
+    while let Some(key) = queue.pop_front() {
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+
+        let Some(parent) = ids.get(&key).cloned() else {
+            continue;
+        };
+
+        for child in tree.children(&key) {
+            if !ids.contains_key(&child)
+                && let Some(x) = create_message_with_parent(
+                    Path(parent.clone()),
+                    Json(tree.message(&child, &files)),
+                )
+                .await?
+                .0
+            {
+                let _ = ids.insert(child.clone(), x.id.key().to_string());
+            }
+            queue.push_back(child);
+        }
+    }
+
+    if let Some(time) = tree.time.clone()
+        && let Some(x) = update_chat(
+            Path(chat_id.clone()),
+            Json(ChatData {
+                root: chat.root.clone(),
+                time: Some(time),
+                ..Default::default()
+            }),
+        )
+        .await?
+        .0
+    {
+        chat = x;
+    }
+
+    let title = tree
+        .title
+        .clone()
+        .filter(|x| !x.trim().is_empty())
+        .unwrap_or_else(|| {
+            let text = tree.message(&path[0], &files).content;
+            text.lines()
+                .find(|x| !x.trim().is_empty())
+                .unwrap_or("Imported Chat")
+                .trim()
+                .chars()
+                .take(40)
+                .collect()
+        });
+
+    let _ = set_preview(&chat_id, title.clone(), chat.time.clone()).await?;
+
+    Ok((chat, title))
+}
+
+async fn get_import_folder(name: &str) -> Result<Folder, ServerError> {
+    if let Some(folder) = get_folder_from_name(name).await? {
+        return Ok(folder);
+    }
+
+    create_folder(Json(FolderData {
+        name: name.to_string(),
+        ..Default::default()
+    }))
+    .await?
+    .0
+    .ok_or_else(|| ServerError::Unknown(format!("Failed to create folder '{}'.", name)))
+}
+
+async fn import_chat(
+    value: &Value,
+    format: ImportFormat,
+    folder: Option<&Folder>,
+) -> Result<(Chat, String), String> {
+    let (chat, title) = import_tree(parse(value, format)?)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(folder) = folder {
+        let _ = add_folder_chat(Path((
+            folder.id.key().to_string(),
+            chat.id.key().to_string(),
+        )))
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok((chat, title))
+}
+
+pub async fn import_chats(
+    Json(import): Json<ImportData>,
+) -> Result<Json<ImportResult>, ServerError> {
+    let folder = match import.folder.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => Some(get_import_folder(name).await?),
+        _ => None,
+    };
+
+    let items = match import.data {
+        Value::Array(x) => x,
+        x => vec![x],
+    };
+
+    let mut result = ImportResult::default();
+
+    for item in items {
+        match import_chat(&item, import.format, folder.as_ref()).await {
+            Ok((chat, title)) => {
+                result.imported += 1;
+                result.chats.push(ImportedChat {
+                    title: Some(title),
+                    chat: Some(chat),
+                    error: None,
+                });
+            }
+            Err(e) => {
+                result.failed += 1;
+                result.chats.push(ImportedChat {
+                    title: get_str(&item, "title"),
+                    chat: None,
+                    error: Some(e),
+                });
+            }
+        }
+    }
+
+    Ok(Json(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ochat_types::chats::messages::Message;
+    use serde_json::json;
+
+    fn active_contents(tree: &ImportTree) -> Vec<String> {
+        tree.active_path()
+            .iter()
+            .map(|x| tree.message(x, &HashMap::new()).content)
+            .collect()
+    }
+
+    fn message(id: &str, role: Role, content: &str, children: &[&str]) -> Message {
+        Message {
+            content: content.to_string(),
+            files: Vec::new(),
+            children: children.iter().map(|x| x.to_string()).collect(),
+            model: None,
+            thinking: None,
+            role,
+            time: Datetime::default(),
+            metrics: None,
+            rating: None,
+            func_calls: Vec::new(),
+            id: ("messages", id).into(),
+        }
+    }
+
+    #[test]
+    fn parses_chatgpt_conversations() {
+        let value = json!({
+            "title": "Trip",
+            "create_time": 1700000000.0,
+            "current_node": "b2",
+            "mapping": {
+                "root": { "parent": null, "message": null, "children": ["system"] },
+                "system": {
+                    "parent": "root",
+                    "message": {
+                        "author": { "role": "system" },
+                        "content": { "parts": ["hidden"] },
+                        "metadata": { "is_visually_hidden_from_conversation": true }
+                    },
+                    "children": ["u"]
+                },
+                "u": {
+                    "parent": "system",
+                    "message": { "author": { "role": "user" }, "content": { "parts": ["Where to?"] } },
+                    "children": ["b1", "b2"]
+                },
+                "b1": {
+                    "parent": "u",
+                    "message": { "author": { "role": "assistant" }, "content": { "parts": ["Paris"] } },
+                    "children": []
+                },
+                "b2": {
+                    "parent": "u",
+                    "message": {
+                        "author": { "role": "assistant" },
+                        "content": { "parts": ["Rome"] },
+                        "metadata": { "model_slug": "gpt-4o" }
+                    },
+                    "children": []
+                }
+            }
+        });
+
+        assert_eq!(detect(&value), ImportFormat::ChatGPT);
+        let tree = parse(&value, ImportFormat::Auto).unwrap();
+        assert_eq!(tree.title.as_deref(), Some("Trip"));
+        assert_eq!(active_contents(&tree), vec!["Where to?", "Rome"]);
+        assert_eq!(tree.children("u"), vec!["b1", "b2"]);
+
+        let answer = tree.message("b2", &HashMap::new());
+        assert_eq!(answer.role, Role::AI);
+        assert_eq!(answer.model.map(|x| x.model).as_deref(), Some("gpt-4o"));
+
+        assert!(parse_chatgpt(&json!({})).is_err());
+    }
+
+    #[test]
+    fn parses_open_webui_chats() {
+        let value = json!({
+            "title": "Recipe",
+            "chat": {
+                "history": {
+                    "currentId": "a1",
+                    "messages": {
+                        "u": { "id": "u", "parentId": null, "role": "user", "content": "Soup?", "childrenIds": ["a1", "a2"] },
+                        "a1": { "id": "a1", "parentId": "u", "role": "assistant", "content": "Tomato", "model": "llama3", "childrenIds": [] },
+                        "a2": { "id": "a2", "parentId": "u", "role": "assistant", "content": "Onion", "childrenIds": [] }
+                    }
+                }
+            }
+        });
+
+        assert_eq!(detect(&value), ImportFormat::OpenWebUI);
+        let tree = parse(&value, ImportFormat::Auto).unwrap();
+        assert_eq!(tree.title.as_deref(), Some("Recipe"));
+        assert_eq!(active_contents(&tree), vec!["Soup?", "Tomato"]);
+
+        let flat = json!({
+            "messages": [
+                { "id": "u", "role": "user", "content": "Hi", "childrenIds": ["a"] },
+                { "id": "a", "parentId": "u", "role": "assistant", "content": "Hello", "childrenIds": [] }
+            ]
+        });
+        assert_eq!(
+            active_contents(&parse_open_webui(&flat).unwrap()),
+            vec!["Hi", "Hello"]
+        );
+
+        assert!(parse_open_webui(&json!({ "title": "Empty" })).is_err());
+    }
+
+    #[test]
+    fn parses_ochat_exports() {
+        let export = ChatExport {
+            version: CHAT_EXPORT_VERSION,
+            chat: Chat {
+                user_id: String::from("user"),
+                root: Some(String::from("u")),
+                time: Datetime::default(),
+                models: Vec::new(),
+                tools: Vec::new(),
+                system_prompt: None,
+                options: None,
+                id: ("chats", "chat").into(),
+            },
+            title: Some(String::from("Maths")),
+            messages: vec![
+                message("u", Role::User, "1 + 1?", &["a"]),
+                message("a", Role::AI, "2", &[]),
+            ],
+            files: Vec::new(),
+        };
+        let mut value = serde_json::to_value(&export).unwrap();
+
+        assert_eq!(detect(&value), ImportFormat::Ochat);
+        let tree = parse(&value, ImportFormat::Auto).unwrap();
+        assert_eq!(tree.title.as_deref(), Some("Maths"));
+        assert_eq!(active_contents(&tree), vec!["1 + 1?", "2"]);
+        assert!(tree.message("u", &HashMap::new()).children.is_empty());
+
+        value["version"] = json!(CHAT_EXPORT_VERSION + 1);
+        assert!(parse_ochat(&value).is_err());
+    }
+}
@@ -1,10 +1,22 @@
-use crate::backend::{CONN, errors::ServerError};
-use async_recursion::async_recursion;
+use crate::backend::{
+    CONN,
+    chats::{CHAT_TABLE, list_all_chats},
+    errors::ServerError,
+    files::delete_unused_files,
+    query::Query,
+};
 use axum::{Json, extract::Path};
-use ochat_types::chats::messages::{Message, MessageData};
+use ochat_types::chats::messages::{
+    Message, MessageData, MessageRating, MessageSearchResult, Role, SearchHighlight,
+};
+use ochat_types::generation::text::FunctionCall;
 use ochat_types::surreal::Datetime;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-const MESSAGE_TABLE: &str = "messages";
+pub(crate) const MESSAGE_TABLE: &str = "messages";
+const SEARCH_LIMIT: usize = 50;
+const SNIPPET_RADIUS: usize = 80;
 
 pub async fn define_messages() -> Result<(), ServerError> {
     let _ = CONN
@@ -32,6 +44,14 @@ pub async fn create_message(
         chat.time = Some(Datetime::default());
     }
     let chat: Option<Message> = CONN.create(MESSAGE_TABLE).content(chat).await?;
+
+    if let Some(chat) = &chat {
+        Query::new("UPDATE type::thing($tb, $id) SET root = $id;")
+            .record(MESSAGE_TABLE, &chat.id.key().to_string())
+            .execute()
+            .await?;
+    }
+
     Ok(Json(chat))
 }
 
@@ -45,18 +65,117 @@ pub async fn create_message_with_parent(
     let chat: Option<Message> = CONN.create(MESSAGE_TABLE).content(chat).await?;
 
     if let Some(chat) = &chat {
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET children += '{2}';",
-            MESSAGE_TABLE,
-            parent.trim(),
-            chat.id.key().to_string().trim(),
-        ))
+        Query::new(
+            "
+LET $root = (SELECT VALUE root FROM type::thing($tb, $id))[0] ?? $id;
+UPDATE type::thing($tb, $id) SET children += $child;
+UPDATE type::thing($tb, $child) SET root = $root;
+",
+        )
+        .record(MESSAGE_TABLE, &parent)
+        .bind_id("child", &chat.id.key().to_string())
+        .execute()
         .await?;
     }
 
     Ok(Json(chat))
 }
 
+/// Records the tool calls made while generating `id` as a `Role::Function` message
+/// placed between it and its parent, so they are replayed with the rest of the history.
+pub(crate) async fn insert_function_message(
+    id: &str,
+    func_calls: Vec<FunctionCall>,
+) -> Result<Option<Message>, ServerError> {
+    let id = id.trim();
+    let tree = get_message_tree(id).await?;
+    let (Some(message), Some(parent)) = (
+        tree.get(id),
+        tree.values()
+            .find(|x| x.children.iter().any(|y| y.trim() == id)),
+    ) else {
+        return Ok(None);
+    };
+
+    let function: Option<Message> = CONN
+        .create(MESSAGE_TABLE)
+        .content(MessageData {
+            content: func_calls
+                .iter()
+                .map(|x| x.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "),
+            model: message.model.clone(),
+            children: vec![id.to_string()],
+            time: Some(message.time.clone()),
+            role: Role::Function,
+            func_calls,
+            ..Default::default()
+        })
+        .await?;
+
+    let Some(function) = function else {
+        return Ok(None);
+    };
+
+    // Swapped in place, as other answers to the same parent may be inserting their calls too.
+    Query::new(
+        "
+LET $root = (SELECT VALUE root FROM type::thing($tb, $id))[0] ?? $id;
+UPDATE type::thing($tb, $id) SET children = children.map(|$c| IF $c = $child THEN $function ELSE $c END);
+UPDATE type::thing($tb, $function) SET root = $root;
+",
+    )
+    .record(MESSAGE_TABLE, &parent.id.key().to_string())
+    .bind_id("child", id)
+    .bind_id("function", &function.id.key().to_string())
+    .execute()
+    .await?;
+
+    Ok(Some(function))
+}
+
+/// Whether `id` belongs to one of the signed in user's chats, as `messages` itself is open to everyone.
+pub(crate) async fn is_own_message(id: &str) -> Result<bool, ServerError> {
+    let owned: Option<bool> = Query::new(
+        "RETURN (SELECT VALUE root FROM type::thing($tb, $id))[0] IN (SELECT VALUE root FROM type::table($chat_tb) WHERE root != NONE);",
+    )
+    .record(MESSAGE_TABLE, id)
+    .bind_id("chat_tb", CHAT_TABLE)
+    .take()
+    .await?;
+
+    Ok(owned.unwrap_or_default())
+}
+
+/// Fails with `NotFound` unless `id` belongs to one of the signed in user's chats.
+async fn check_own_message(id: &str) -> Result<(), ServerError> {
+    if is_own_message(id).await? {
+        Ok(())
+    } else {
+        Err(ServerError::NotFound(format!(
+            "Message '{}' does not exist.",
+            id.trim()
+        )))
+    }
+}
+
+/// The ones of `ids` that belong to the signed in user's chats.
+pub(crate) async fn get_own_messages(ids: Vec<String>) -> Result<Vec<String>, ServerError> {
+    Query::new(
+        "
+LET $roots = SELECT VALUE root FROM type::table($chat_tb) WHERE root != NONE;
+LET $things = $ids.map(|$id| type::thing($tb, $id));
+SELECT VALUE record::id(id) FROM $things WHERE root IN $roots;
+",
+    )
+    .table(MESSAGE_TABLE)
+    .bind_id("chat_tb", CHAT_TABLE)
+    .bind("ids", ids)
+    .take_at(2)
+    .await
+}
+
 pub async fn read_message(id: Path<String>) -> Result<Json<Option<Message>>, ServerError> {
     Ok(Json(CONN.select((MESSAGE_TABLE, id.trim())).await?))
 }
@@ -76,71 +195,345 @@ pub async fn update_message(
     Ok(Json(chat))
 }
 
+pub async fn rate_message(
+    id: Path<String>,
+    Json(rating): Json<MessageRating>,
+) -> Result<Json<Option<Message>>, ServerError> {
+    check_own_message(&id).await?;
+    Ok(Json(
+        Query::new("UPDATE type::thing($tb, $id) SET rating = $rating;")
+            .record(MESSAGE_TABLE, &id)
+            .bind("rating", rating)
+            .take()
+            .await?,
+    ))
+}
+
+pub async fn remove_message_rating(id: Path<String>) -> Result<Json<Option<Message>>, ServerError> {
+    check_own_message(&id).await?;
+    Ok(Json(
+        Query::new("UPDATE type::thing($tb, $id) SET rating = NONE;")
+            .record(MESSAGE_TABLE, &id)
+            .take()
+            .await?,
+    ))
+}
+
 pub async fn list_all_messages() -> Result<Json<Vec<Message>>, ServerError> {
     Ok(Json(CONN.select(MESSAGE_TABLE).await?))
 }
 
 pub async fn delete_message(id: Path<String>) -> Result<Json<Option<Message>>, ServerError> {
-    Ok(Json(CONN.delete((MESSAGE_TABLE, id.trim())).await?))
+    check_own_message(&id).await?;
+    Ok(Json(delete_message_tree(&id).await?))
+}
+
+/// Deletes the message with every reply below it, unlinks it from its parent and drops files nothing else uses.
+pub(crate) async fn delete_message_tree(id: &str) -> Result<Option<Message>, ServerError> {
+    let mut tree = get_message_tree(id).await?;
+    let ids: Vec<String> = take_descendants(&mut tree, id)
+        .into_iter()
+        .map(|x| x.id.key().to_string())
+        .collect();
+
+    let mut response = Query::new(
+        "
+LET $root = (SELECT VALUE root FROM type::thing($tb, $id))[0] ?? $id;
+UPDATE type::table($tb) SET children -= $id WHERE root = $root AND children CONTAINS $id;
+UPDATE type::table($chat_tb) SET root = NONE WHERE root = $id;
+DELETE type::table($tb) WHERE root = $root AND record::id(id) IN $ids RETURN BEFORE;
+DELETE type::thing($tb, $id) RETURN BEFORE;
+",
+    )
+    .record(MESSAGE_TABLE, id)
+    .bind_id("chat_tb", CHAT_TABLE)
+    .bind("ids", ids)
+    .run()
+    .await?;
+
+    let mut deleted: Vec<Message> = response.take(3)?;
+    let message: Option<Message> = response.take(4)?;
+    deleted.extend(message.clone());
+
+    let _ = delete_unused_files(deleted.into_iter().flat_map(|x| x.files).collect()).await?;
+    Ok(message)
+}
+
+/// Loads every message sharing the tree of the given one with a single indexed query.
+pub(crate) async fn get_message_tree(id: &str) -> Result<HashMap<String, Message>, ServerError> {
+    let messages: Vec<Message> = Query::new(
+        "
+LET $root = (SELECT VALUE root FROM type::thing($tb, $id))[0] ?? $id;
+SELECT * FROM type::table($tb) WHERE root = $root;
+",
+    )
+    .record(MESSAGE_TABLE, id)
+    .take_at(1)
+    .await?;
+
+    Ok(messages
+        .into_iter()
+        .map(|x| (x.id.key().to_string(), x))
+        .collect())
 }
 
 pub async fn list_all_messages_from_parent(
     parent: Path<String>,
 ) -> Result<Json<Vec<Message>>, ServerError> {
-    let parent = read_message(parent).await?.0.unwrap();
-    let mut messages = Vec::new();
-
-    for child in parent.children {
-        if let Some(x) = read_message(Path(child.clone())).await?.0 {
-            messages.push(x)
-        }
-    }
+    let mut tree = get_message_tree(&parent).await?;
+    let children = match tree.get(parent.trim()) {
+        Some(x) => x.children.clone(),
+        _ => return Ok(Json(Vec::new())),
+    };
 
-    Ok(Json(messages))
+    Ok(Json(
+        children
+            .iter()
+            .filter_map(|x| tree.remove(x.trim()))
+            .collect(),
+    ))
 }
 
-#[async_recursion]
 pub async fn get_all_messages_from_root(
     id: Path<String>,
 ) -> Result<Json<Vec<Message>>, ServerError> {
-    let mut messages = list_all_messages_from_parent(Path(id.clone())).await?.0;
-    let mut fin: Vec<Message> = Vec::new();
-    for message in messages.iter().filter(|x| x.id.key().to_string() != id.0) {
-        let mut extra = get_all_messages_from_root(Path(message.id.key().to_string()))
-            .await?
-            .0;
-        fin.append(&mut extra);
+    let mut tree = get_message_tree(&id).await?;
+    Ok(Json(take_descendants(&mut tree, &id)))
+}
+
+/// Removes every message below `id` from the tree, breadth first.
+fn take_descendants(tree: &mut HashMap<String, Message>, id: &str) -> Vec<Message> {
+    let mut queue: VecDeque<String> = match tree.remove(id.trim()) {
+        Some(x) => x.children.into(),
+        _ => return Vec::new(),
+    };
+    let mut messages = Vec::new();
+
+    while let Some(child) = queue.pop_front() {
+        if let Some(x) = tree.remove(child.trim()) {
+            queue.extend(x.children.iter().cloned());
+            messages.push(x);
+        }
     }
 
-    fin.append(&mut messages);
-    Ok(Json(fin))
+    messages
 }
 
 pub async fn get_default_message_list_from_parent(
     id: Path<String>,
 ) -> Result<Json<Vec<Message>>, ServerError> {
-    let mut list = match read_message(Path(id.clone())).await?.0 {
+    let mut tree = get_message_tree(&id).await?;
+    let mut list = match tree.remove(id.trim()) {
         Some(x) => vec![x],
         _ => return Ok(Json(Vec::new())),
     };
 
-    loop {
-        if list.last().unwrap().children.is_empty() {
-            break;
-        }
+    while let Some(x) = list
+        .last()
+        .and_then(|x| x.children.first())
+        .and_then(|x| tree.remove(x.trim()))
+    {
+        list.push(x);
+    }
+
+    Ok(Json(list))
+}
 
-        if let Some(x) = read_message(Path(list.last().unwrap().children.first().unwrap().clone()))
+/// Messages written before they stored their tree `root` are linked to it by walking down from each chat,
+/// run once as a migration step.
+pub async fn backfill_message_roots() -> Result<(), ServerError> {
+    let unlinked: HashSet<String> =
+        Query::new("SELECT VALUE record::id(id) FROM type::table($tb) WHERE root = NONE;")
+            .table(MESSAGE_TABLE)
+            .take::<Vec<String>>()
             .await?
-            .0
-        {
-            if list.contains(&x) {
-                break;
-            }
-            list.push(x);
-        } else {
-            break;
+            .into_iter()
+            .collect();
+
+    if unlinked.is_empty() {
+        return Ok(());
+    }
+
+    let roots: Vec<String> =
+        Query::new("SELECT VALUE root FROM type::table($tb) WHERE root != NONE;")
+            .table(CHAT_TABLE)
+            .take()
+            .await?;
+
+    for root in roots.into_iter().filter(|x| unlinked.contains(x.trim())) {
+        let mut level = vec![root.trim().to_string()];
+
+        while !level.is_empty() {
+            let children: Vec<Vec<String>> = Query::new(
+                "UPDATE type::table($tb) SET root = $root WHERE root = NONE AND record::id(id) IN $ids RETURN VALUE children;",
+            )
+            .table(MESSAGE_TABLE)
+            .bind_id("root", &root)
+            .bind("ids", level)
+            .take()
+            .await?;
+
+            level = children.into_iter().flatten().collect();
         }
     }
 
-    Ok(Json(list))
+    Ok(())
+}
+
+fn get_terms(search: &str) -> Vec<String> {
+    search
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_ascii_lowercase())
+        .collect()
+}
+
+fn get_snippet(text: &str, terms: &[String]) -> (String, Vec<SearchHighlight>) {
+    // Ascii lowercasing keeps byte offsets lined up with the original text.
+    let lower = text.to_ascii_lowercase();
+    let first = terms
+        .iter()
+        .filter_map(|x| lower.find(x.as_str()))
+        .min()
+        .unwrap_or_default();
+
+    let mut start = first.saturating_sub(SNIPPET_RADIUS);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    let mut end = (first + SNIPPET_RADIUS).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let mut highlights: Vec<SearchHighlight> = terms
+        .iter()
+        .flat_map(|term| {
+            lower[start..end]
+                .match_indices(term.as_str())
+                .map(|(i, x)| SearchHighlight {
+                    start: i,
+                    end: i + x.len(),
+                })
+        })
+        .collect();
+    highlights.sort_by_key(|x| x.start);
+
+    let mut merged: Vec<SearchHighlight> = Vec::new();
+    for highlight in highlights {
+        match merged.last_mut() {
+            Some(last) if highlight.start <= last.end => last.end = last.end.max(highlight.end),
+            _ => merged.push(highlight),
+        }
+    }
+
+    (text[start..end].to_string(), merged)
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    id: String,
+    /// A match belongs to the chat whose root it shares.
+    root: String,
+    content: String,
+    thinking: Option<String>,
+    role: Role,
+}
+
+pub async fn search_messages(
+    search: Path<String>,
+) -> Result<Json<Vec<MessageSearchResult>>, ServerError> {
+    let chats: HashMap<String, String> = list_all_chats()
+        .await?
+        .0
+        .into_iter()
+        .filter_map(|x| Some((x.root?.trim().to_string(), x.id.key().to_string())))
+        .collect();
+
+    // Narrowed to the user's own chats before the limit, as every user's messages share the table.
+    let messages: Vec<SearchHit> = Query::new(
+        "SELECT record::id(id) AS id, root, content, thinking, role, (search::score(1) ?? 0) + (search::score(2) ?? 0) AS score FROM type::table($tb) WHERE root IN $roots AND (content @1@ $search OR thinking @2@ $search) ORDER BY score DESC LIMIT $limit;",
+    )
+    .table(MESSAGE_TABLE)
+    .bind("roots", chats.keys().cloned().collect::<Vec<String>>())
+    .bind_str("search", &search)
+    .bind("limit", SEARCH_LIMIT)
+    .take()
+    .await?;
+
+    let terms = get_terms(&search);
+    let mut results = Vec::new();
+
+    for message in messages {
+        let Some(chat) = chats.get(message.root.trim()).cloned() else {
+            continue;
+        };
+
+        let content = message.content.to_ascii_lowercase();
+        let thinking = match &message.thinking {
+            Some(_) if terms.iter().any(|x| content.contains(x.as_str())) => None,
+            x => x.as_ref(),
+        };
+
+        let (snippet, highlights) = get_snippet(thinking.unwrap_or(&message.content), &terms);
+
+        results.push(MessageSearchResult {
+            message: message.id,
+            chat,
+            role: message.role,
+            snippet,
+            highlights,
+            thinking: thinking.is_some(),
+        });
+    }
+
+    Ok(Json(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlighted(text: &str, search: &str) -> Vec<String> {
+        let (snippet, highlights) = get_snippet(text, &get_terms(search));
+        highlights
+            .into_iter()
+            .map(|x| snippet[x.start..x.end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn highlights_terms_in_non_ascii_text() {
+        for padding in 0..4 {
+            let text = format!(
+                "{}{} Straße NEEDLE über {}",
+                "a".repeat(padding),
+                "é".repeat(60),
+                "ü".repeat(60)
+            );
+            let (snippet, _) = get_snippet(&text, &get_terms("needle"));
+
+            assert!(text.contains(&snippet));
+            assert!(snippet.len() <= SNIPPET_RADIUS * 2 + 2);
+            assert_eq!(highlighted(&text, "needle"), vec!["NEEDLE"]);
+            assert_eq!(
+                highlighted(&text, "straße needle"),
+                vec!["Straße", "NEEDLE"]
+            );
+        }
+    }
+
+    #[test]
+    fn merges_overlapping_highlights() {
+        assert_eq!(highlighted("a token here", "token ok"), vec!["token"]);
+        assert_eq!(highlighted("日本語のテキスト", "テキ"), vec!["テキ"]);
+    }
+
+    #[test]
+    fn shows_the_start_without_a_match() {
+        let (snippet, highlights) = get_snippet("naïve café", &get_terms("tea"));
+        assert_eq!(snippet, "naïve café");
+        assert!(highlights.is_empty());
+    }
 }
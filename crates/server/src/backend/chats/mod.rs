@@ -1,3 +1,5 @@
+pub mod export;
+pub mod import;
 pub mod messages;
 pub mod previews;
 pub mod route;
@@ -5,15 +7,18 @@ pub mod route;
 use crate::backend::{
     CONN,
     chats::{
-        messages::{create_message, create_message_with_parent},
+        messages::{create_message, create_message_with_parent, delete_message_tree},
         previews::PREVIEW_TABLE,
     },
     errors::ServerError,
+    folders::{FOLDER_TABLE, get_hidden_by_trash},
+    query::Query,
+    trash::trash_record,
 };
 use axum::{Json, extract::Path};
-use ochat_types::chats::{Chat, ChatData, messages::MessageData, previews::Preview};
+use ochat_types::chats::{Chat, ChatConfig, ChatData, messages::MessageData, previews::Preview};
 
-const CHAT_TABLE: &str = "chats";
+pub(crate) const CHAT_TABLE: &str = "chats";
 
 pub async fn define_chats() -> Result<(), ServerError> {
     let _ = CONN
@@ -42,9 +47,8 @@ pub async fn branch_new_chat(
     let mut parent = root.id.key().to_string();
 
     let chat = create_chat(Json(ChatData {
-        user_id: None,
         root: Some(parent.clone()),
-        time: None,
+        ..Default::default()
     }))
     .await?;
 
@@ -72,14 +76,11 @@ pub async fn set_chat_root(
     Path((id, root)): Path<(String, String)>,
 ) -> Result<Json<Option<Chat>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET root = '{2}';",
-            CHAT_TABLE,
-            id.trim(),
-            root.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new("UPDATE type::thing($tb, $id) SET root = $root;")
+            .record(CHAT_TABLE, &id)
+            .bind_id("root", &root)
+            .take()
+            .await?,
     ))
 }
 
@@ -92,10 +93,60 @@ pub async fn update_chat(
     ))
 }
 
+pub async fn get_chat_config(id: Path<String>) -> Result<Json<Option<ChatConfig>>, ServerError> {
+    Ok(Json(get_chat(id).await?.0.as_ref().map(ChatConfig::from)))
+}
+
+/// Replaces the models, tools, system prompt and options of the chat, leaving the rest untouched.
+pub async fn set_chat_config(
+    id: Path<String>,
+    Json(config): Json<ChatConfig>,
+) -> Result<Json<Option<Chat>>, ServerError> {
+    Ok(Json(
+        Query::new(
+            "UPDATE type::thing($tb, $id) SET models = $config.models, tools = $config.tools, system_prompt = $config.system_prompt, options = $config.options;",
+        )
+        .record(CHAT_TABLE, &id)
+        .bind("config", config)
+        .take()
+        .await?,
+    ))
+}
+
+/// Moves the chat to the trash.
 pub async fn delete_chat(id: Path<String>) -> Result<Json<Option<Chat>>, ServerError> {
+    Ok(Json(trash_record(CHAT_TABLE, &id).await?))
+}
+
+/// Deletes the chat along with its preview, its whole message tree and the files only it used.
+pub async fn purge_chat(id: Path<String>) -> Result<Json<Option<Chat>>, ServerError> {
     let _: Option<Preview> = CONN.delete((PREVIEW_TABLE, &*id)).await?;
-    Ok(Json(CONN.delete((CHAT_TABLE, id.trim())).await?))
+    let chat: Option<Chat> = CONN.delete((CHAT_TABLE, id.trim())).await?;
+
+    Query::new("UPDATE type::table($tb) SET chats -= $id WHERE chats CONTAINS $id;")
+        .table(FOLDER_TABLE)
+        .bind_id("id", &id)
+        .execute()
+        .await?;
+
+    if let Some(root) = chat.as_ref().and_then(|x| x.root.clone()) {
+        let _ = delete_message_tree(&root).await?;
+    }
+
+    Ok(Json(chat))
 }
+
+/// Chats in the trash, or in a folder that is, are left out.
 pub async fn list_all_chats() -> Result<Json<Vec<Chat>>, ServerError> {
-    Ok(Json(CONN.select(CHAT_TABLE).await?))
+    let (_, hidden) = get_hidden_by_trash().await?;
+
+    Ok(Json(
+        Query::new(
+            "SELECT * FROM type::table($tb) WHERE deleted_at = NONE AND record::id(id) NOT IN $hidden;",
+        )
+        .table(CHAT_TABLE)
+        .bind("hidden", hidden)
+        .take()
+        .await?,
+    ))
 }
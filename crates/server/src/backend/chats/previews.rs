@@ -1,7 +1,9 @@
 use crate::backend::{
     CONN,
-    chats::{get_chat, messages::get_default_message_list_from_parent},
+    chats::{CHAT_TABLE, get_chat, messages::get_default_message_list_from_parent},
     errors::ServerError,
+    folders::get_hidden_by_trash,
+    query::Query,
     settings::get_settings,
 };
 use axum::{Json, extract::Path};
@@ -49,25 +51,8 @@ pub async fn update_preview(id: Path<String>) -> Result<Json<Option<Preview>>, S
     };
 
     if messages.is_empty() {
-        let preview = PreviewData {
-            user_id: None,
-            text: String::from("New Chat"),
-            time: time.clone(),
-        };
         return Ok(Json(
-            if CONN
-                .select::<Option<Preview>>((PREVIEW_TABLE, id.trim()))
-                .await?
-                .is_some()
-            {
-                CONN.update((PREVIEW_TABLE, id.trim()))
-                    .content(preview)
-                    .await?
-            } else {
-                CONN.create((PREVIEW_TABLE, id.trim()))
-                    .content(preview)
-                    .await?
-            },
+            set_preview(&id, String::from("New Chat"), time).await?,
         ));
     }
 
@@ -95,6 +80,8 @@ Generate a **concise, 3 to 5 word title** for the previous messages.
             ),
             files: Vec::new(),
             role: Role::System,
+            id: None,
+            func_calls: Vec::new(),
         },
     );
 
@@ -106,18 +93,27 @@ Generate a **concise, 3 to 5 word title** for the previous messages.
         model: provider.model,
         tools: Vec::new(),
         messages,
+        options: None,
     }))
     .await?
     .0
     .content;
 
+    Ok(Json(set_preview(&id, preview, time).await?))
+}
+
+pub async fn set_preview(
+    id: &str,
+    text: String,
+    time: Datetime,
+) -> Result<Option<Preview>, ServerError> {
     let preview = PreviewData {
         user_id: None,
-        text: preview,
+        text,
         time,
     };
 
-    Ok(Json(
+    Ok(
         if CONN
             .select::<Option<Preview>>((PREVIEW_TABLE, id.trim()))
             .await?
@@ -131,7 +127,7 @@ Generate a **concise, 3 to 5 word title** for the previous messages.
                 .content(preview)
                 .await?
         },
-    ))
+    )
 }
 
 pub async fn get_preview(id: Path<String>) -> Result<Json<Option<Preview>>, ServerError> {
@@ -144,19 +140,39 @@ pub async fn get_preview(id: Path<String>) -> Result<Json<Option<Preview>>, Serv
 }
 
 pub async fn search_previews(search: Path<String>) -> Result<Json<Vec<Preview>>, ServerError> {
+    let (_, hidden) = get_hidden_by_trash().await?;
+
     Ok(Json(
-        CONN.query(&format!(
-            "            
-SELECT *, search::score(1) AS score FROM {0} WHERE text @1@ '{1}' ORDER BY score DESC;
+        Query::new(
+            "
+LET $trashed = array::union(SELECT VALUE record::id(id) FROM type::table($chat_tb) WHERE deleted_at != NONE, $hidden);
+SELECT *, search::score(1) AS score FROM type::table($tb) WHERE text @1@ $search AND record::id(id) NOT IN $trashed ORDER BY score DESC;
 ",
-            PREVIEW_TABLE,
-            search.trim()
-        ))
-        .await?
-        .take(0)?,
+        )
+        .table(PREVIEW_TABLE)
+        .bind_id("chat_tb", CHAT_TABLE)
+        .bind("hidden", hidden)
+        .bind_str("search", &search)
+        .take_at(1)
+        .await?,
     ))
 }
 
+/// Previews of trashed chats, and of chats in a trashed folder, are left out.
 pub async fn list_all_previews() -> Result<Json<Vec<Preview>>, ServerError> {
-    Ok(Json(CONN.select(PREVIEW_TABLE).await?))
+    let (_, hidden) = get_hidden_by_trash().await?;
+
+    Ok(Json(
+        Query::new(
+            "
+LET $trashed = array::union(SELECT VALUE record::id(id) FROM type::table($chat_tb) WHERE deleted_at != NONE, $hidden);
+SELECT * FROM type::table($tb) WHERE record::id(id) NOT IN $trashed;
+",
+        )
+        .table(PREVIEW_TABLE)
+        .bind_id("chat_tb", CHAT_TABLE)
+        .bind("hidden", hidden)
+        .take_at(1)
+        .await?,
+    ))
 }
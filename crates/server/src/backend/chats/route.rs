@@ -1,6 +1,7 @@
-use crate::backend::chats::{self, messages, previews};
+use crate::backend::chats::{self, export, import, messages, previews};
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     routing::{get, post, put},
 };
 
@@ -25,7 +26,12 @@ pub fn routes() -> Router {
                 .put(messages::update_message)
                 .delete(messages::delete_message),
         )
+        .route(
+            "/message/{id}/rating/",
+            put(messages::rate_message).delete(messages::remove_message_rating),
+        )
         .route("/message/all/", get(messages::list_all_messages))
+        .route("/message/search/{search}", get(messages::search_messages))
         .route("/preview/all/", get(previews::list_all_previews))
         .route("/preview/search/{search}", get(previews::search_previews))
         .route(
@@ -34,8 +40,18 @@ pub fn routes() -> Router {
         )
         .route("/chat/", post(chats::create_chat))
         .route("/chat/branch/", post(chats::branch_new_chat))
+        .route(
+            "/chat/import/",
+            post(import::import_chats).layer(DefaultBodyLimit::max(import::IMPORT_BODY_LIMIT)),
+        )
         .route("/chat/{id}/root/{root}", put(chats::set_chat_root))
+        .route(
+            "/chat/{id}/config/",
+            get(chats::get_chat_config).put(chats::set_chat_config),
+        )
         .route("/chat/all/", get(chats::list_all_chats))
+        .route("/chat/{id}/export/{format}", get(export::export_chat))
+        .route("/chat/dataset/{format}", get(export::export_dataset))
         .route(
             "/chat/{id}",
             get(chats::get_chat)
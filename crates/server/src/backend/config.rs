@@ -0,0 +1,192 @@
+use crate::backend::{Arguments, errors::ServerError, utils::get_path_settings};
+use axum::http::HeaderValue;
+use clap::ValueEnum;
+use ochat_types::{
+    providers::{ProviderData, ProviderType},
+    settings::SettingsData,
+};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, sync::OnceLock};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+static CONFIG: OnceLock<ServerConfig> = OnceLock::new();
+const CONFIG_PATH: &str = "server.toml";
+const CONFIG_ENV: &str = "OCHAT_SERVER_CONFIG";
+
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseEngine {
+    #[default]
+    #[value(name = "rocksdb")]
+    #[serde(rename = "rocksdb")]
+    RocksDb,
+    Memory,
+    Remote,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub namespace: String,
+    pub database: String,
+    pub engine: DatabaseEngine,
+    pub path: String,
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            namespace: String::from("test"),
+            database: String::from("test"),
+            engine: DatabaseEngine::default(),
+            path: get_path_settings("database".to_string()),
+            url: None,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn endpoint(&self) -> Result<String, ServerError> {
+        Ok(match self.engine {
+            DatabaseEngine::RocksDb => format!("rocksdb://{}", self.path),
+            DatabaseEngine::Memory => String::from("mem://"),
+            DatabaseEngine::Remote => match &self.url {
+                Some(url) if url.contains("://") => url.trim().to_string(),
+                Some(url) if !url.trim().is_empty() => format!("ws://{}", url.trim()),
+                _ => {
+                    return Err(ServerError::Config(String::from(
+                        "A database url is required when using the remote engine.",
+                    )));
+                }
+            },
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TlsConfig {
+    pub cert: String,
+    pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct CorsConfig {
+    pub origins: Vec<String>,
+}
+
+impl CorsConfig {
+    pub fn layer(&self) -> CorsLayer {
+        let origin = if self.origins.iter().any(|x| x.trim() == "*") {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(
+                self.origins
+                    .iter()
+                    .filter_map(|x| HeaderValue::from_str(x.trim()).ok()),
+            )
+        };
+
+        CorsLayer::new()
+            .allow_origin(origin)
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProviderConfig {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "String::new")]
+    pub api_key: String,
+    #[serde(default = "Default::default")]
+    pub provider_type: ProviderType,
+}
+
+impl Into<ProviderData> for ProviderConfig {
+    fn into(self) -> ProviderData {
+        ProviderData {
+            name: self.name,
+            url: self.url,
+            api_key: self.api_key,
+            provider_type: self.provider_type,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub bind: Option<String>,
+    pub tls: Option<TlsConfig>,
+    pub cors: CorsConfig,
+    pub body_limit: Option<usize>,
+    /// Hours between garbage collection sweeps, unset to only run them by hand.
+    pub gc_interval: Option<u64>,
+    /// Days trashed chats, folders and prompts are kept before a sweep purges them, unset to keep them.
+    pub trash_retention: Option<u64>,
+    pub providers: Vec<ProviderConfig>,
+    pub settings: SettingsData,
+    pub database: DatabaseConfig,
+}
+
+impl ServerConfig {
+    pub fn from_file(path: &str) -> Result<Self, ServerError> {
+        let data = fs::read_to_string(path)?;
+        toml::from_str(&data).map_err(|e| ServerError::Config(format!("{} : {}", path, e)))
+    }
+
+    pub fn load(args: &Arguments) -> Result<Self, ServerError> {
+        let mut config = match args.config.clone().or(env::var(CONFIG_ENV).ok()) {
+            Some(path) => Self::from_file(&path)?,
+            None => {
+                let path = get_path_settings(CONFIG_PATH.to_string());
+                if fs::exists(&path).unwrap_or(false) {
+                    Self::from_file(&path)?
+                } else {
+                    Self::default()
+                }
+            }
+        };
+
+        if let Some(url) = &args.url {
+            config.bind = Some(url.clone());
+        }
+
+        if let Some(namespace) = &args.namespace {
+            config.database.namespace = namespace.clone();
+        }
+
+        if let Some(database) = &args.database {
+            config.database.database = database.clone();
+        }
+
+        if let Some(engine) = &args.engine {
+            config.database.engine = engine.clone();
+        }
+
+        if let Some(path) = &args.data_dir {
+            config.database.path = path.clone();
+        }
+
+        if let Some(url) = &args.db_url {
+            config.database.url = Some(url.clone());
+        }
+
+        Ok(config)
+    }
+}
+
+pub fn set_config(config: ServerConfig) {
+    let _ = CONFIG.set(config);
+}
+
+pub fn get_config() -> &'static ServerConfig {
+    CONFIG.get_or_init(ServerConfig::default)
+}
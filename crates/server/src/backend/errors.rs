@@ -3,6 +3,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use ochat_types::errors::{ErrorBody, ErrorCode};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -30,10 +31,72 @@ pub enum ServerError {
     Whisper(#[from] whisper_rs::WhisperError),
     #[error("IO Error : {0}")]
     IO(#[from] std::io::Error),
+    #[error("Not Found : {0}")]
+    NotFound(String),
+    #[error("Unauthorized : {0}")]
+    Unauthorized(String),
+    #[error("Forbidden : {0}")]
+    Forbidden(String),
+    #[error("Validation Error : {0}")]
+    Validation(String),
+    #[error("Upstream Error : {0}")]
+    Upstream(String),
+    #[error("Conflict : {0}")]
+    Conflict(String),
+    #[error("Config Error : {0}")]
+    Config(String),
     #[error("Error : {0}")]
     Unknown(String),
 }
 
+impl ServerError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Surreal(surrealdb::Error::Db(e)) => {
+                use surrealdb::error::Db;
+                match e {
+                    Db::InvalidAuth | Db::ExpiredSession | Db::ExpiredToken | Db::InvalidSignup => {
+                        ErrorCode::Unauthorized
+                    }
+                    Db::RecordExists { .. } | Db::IndexExists { .. } => ErrorCode::Conflict,
+                    Db::FieldCheck { .. } | Db::FieldValue { .. } => ErrorCode::Validation,
+                    _ => ErrorCode::Internal,
+                }
+            }
+            Self::Surreal(_)
+            | Self::Serde(_)
+            | Self::IO(_)
+            | Self::Config(_)
+            | Self::Unknown(_) => ErrorCode::Internal,
+            #[cfg(feature = "sound")]
+            Self::Whisper(_) => ErrorCode::Internal,
+            Self::RigMessageError(_) => ErrorCode::Validation,
+            Self::RigCompletionError(_)
+            | Self::RigPromptError(_)
+            | Self::RigEmbedError(_)
+            | Self::RigEmbeddingError(_)
+            | Self::Reqwest(_)
+            | Self::WebSearch(_)
+            | Self::Upstream(_) => ErrorCode::Upstream,
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::Unauthorized(_) => ErrorCode::Unauthorized,
+            Self::Forbidden(_) => ErrorCode::Forbidden,
+            Self::Validation(_) => ErrorCode::Validation,
+            Self::Conflict(_) => ErrorCode::Conflict,
+        }
+    }
+
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::Reqwest(e) => e
+                .status()
+                .map(|x| serde_json::json!({ "upstream_status": x.as_u16() })),
+            Self::Surreal(e) => Some(serde_json::json!({ "source": e.to_string() })),
+            _ => None,
+        }
+    }
+}
+
 impl From<Box<dyn std::error::Error>> for ServerError {
     fn from(value: Box<dyn std::error::Error>) -> Self {
         Self::Unknown(value.to_string())
@@ -42,6 +105,17 @@ impl From<Box<dyn std::error::Error>> for ServerError {
 
 impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(self.to_string())).into_response()
+        let code = self.code();
+        let body = ErrorBody {
+            code,
+            message: self.to_string(),
+            details: self.details(),
+        };
+
+        (
+            StatusCode::from_u16(code.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            Json(body),
+        )
+            .into_response()
     }
 }
@@ -1,11 +1,14 @@
-use crate::backend::{CONN, errors::ServerError, utils::get_file_uploads_path};
+use crate::backend::{
+    CONN, errors::ServerError, messages::MESSAGE_TABLE, query::Query, utils::get_file_uploads_path,
+};
 use axum::{Json, extract::Path};
 use base64::{Engine, prelude::BASE64_STANDARD};
 use ochat_types::files::{B64File, B64FileData, DBFile, FileType};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashSet,
     fs,
-    io::Write,
+    io::{ErrorKind, Write},
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -126,6 +129,50 @@ pub async fn delete_file(id: Path<String>) -> Result<Json<Option<DBFile>>, Serve
     Ok(Json(file))
 }
 
+/// Removes an upload from disk, returning its size. Already missing uploads count as zero.
+pub fn remove_upload(path: &str) -> Result<u64, ServerError> {
+    let size = fs::metadata(path).map(|x| x.len()).unwrap_or(0);
+
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(size),
+    }
+}
+
+/// Deletes the given files once no message refers to them, returning how many went and the bytes freed.
+pub async fn delete_unused_files(ids: Vec<String>) -> Result<(usize, u64), ServerError> {
+    if ids.is_empty() {
+        return Ok((0, 0));
+    }
+
+    let used: HashSet<String> =
+        Query::new("SELECT VALUE files FROM type::table($tb) WHERE files CONTAINSANY $ids;")
+            .table(MESSAGE_TABLE)
+            .bind("ids", ids.clone())
+            .take::<Vec<Vec<String>>>()
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+
+    let (mut count, mut bytes) = (0, 0);
+
+    for id in ids
+        .into_iter()
+        .filter(|x| !used.contains(x))
+        .collect::<HashSet<_>>()
+    {
+        let file: Option<DBFile> = CONN.delete((FILE_TABLE, id.trim())).await?;
+
+        if let Some(file) = file {
+            bytes += remove_upload(&file.path)?;
+            count += 1;
+        }
+    }
+
+    Ok((count, bytes))
+}
+
 pub async fn list_all_files() -> Result<Json<Vec<DBFile>>, ServerError> {
     Ok(Json(CONN.select(FILE_TABLE).await?))
 }
@@ -1,10 +1,12 @@
 pub mod route;
 
-use crate::backend::{CONN, errors::ServerError};
+use crate::backend::{CONN, errors::ServerError, query::Query, trash::trash_record};
 use axum::{Json, extract::Path};
 use ochat_types::folders::{Folder, FolderData, FolderNameData};
+use serde::Deserialize;
+use std::collections::HashSet;
 
-const FOLDER_TABLE: &str = "folders";
+pub(crate) const FOLDER_TABLE: &str = "folders";
 
 pub async fn define_folders() -> Result<(), ServerError> {
     let _ = CONN
@@ -52,37 +54,29 @@ pub async fn set_folder_parent(
     Path((id, parent)): Path<(String, String)>,
 ) -> Result<Json<Option<Folder>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET parent = '{2}';",
-            FOLDER_TABLE,
-            id.trim(),
-            parent.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new("UPDATE type::thing($tb, $id) SET parent = $parent;")
+            .record(FOLDER_TABLE, &id)
+            .bind_id("parent", &parent)
+            .take()
+            .await?,
     ))
 }
 
 pub async fn add_folder_chat(
     Path((id, chat)): Path<(String, String)>,
 ) -> Result<Json<Option<Folder>>, ServerError> {
-    let _ = CONN
-        .query(&format!(
-            "UPDATE {0} SET chats -= '{1}' WHERE name != 'Favourites';",
-            FOLDER_TABLE,
-            chat.trim()
-        ))
+    Query::new("UPDATE type::table($tb) SET chats -= $chat WHERE name != 'Favourites';")
+        .table(FOLDER_TABLE)
+        .bind_id("chat", &chat)
+        .execute()
         .await?;
 
     Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET chats += '{2}';",
-            FOLDER_TABLE,
-            id.trim(),
-            chat.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new("UPDATE type::thing($tb, $id) SET chats += $chat;")
+            .record(FOLDER_TABLE, &id)
+            .bind_id("chat", &chat)
+            .take()
+            .await?,
     ))
 }
 
@@ -91,109 +85,79 @@ pub async fn update_folder_name(
     Json(data): Json<FolderNameData>,
 ) -> Result<Json<Option<Folder>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET name = '{2}';",
-            FOLDER_TABLE,
-            id.trim(),
-            data.name.trim(),
-        ))
-        .await?
-        .take(0)?,
+        Query::new("UPDATE type::thing($tb, $id) SET name = $name;")
+            .record(FOLDER_TABLE, &id)
+            .bind_str("name", &data.name)
+            .take()
+            .await?,
     ))
 }
 pub async fn remove_folder_chat(
     Path((id, chat)): Path<(String, String)>,
 ) -> Result<Json<Option<Folder>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET chats -= '{2}';",
-            FOLDER_TABLE,
-            id.trim(),
-            chat.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new("UPDATE type::thing($tb, $id) SET chats -= $chat;")
+            .record(FOLDER_TABLE, &id)
+            .bind_id("chat", &chat)
+            .take()
+            .await?,
     ))
 }
 
 pub async fn unparent_folder(Path(id): Path<String>) -> Result<Json<Option<Folder>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET parent = NONE;",
-            FOLDER_TABLE,
-            id.trim(),
-        ))
-        .await?
-        .take(0)?,
+        Query::new("UPDATE type::thing($tb, $id) SET parent = NONE;")
+            .record(FOLDER_TABLE, &id)
+            .take()
+            .await?,
     ))
 }
 
-pub async fn fav_chat(Path(chat): Path<String>) -> Result<Json<Option<Folder>>, ServerError> {
-    let folder = get_folder_from_name("Favourites").await?.unwrap();
-
-    let action = if folder.chats.contains(&chat.trim().to_string()) {
-        "-"
+async fn toggle_folder_chat(folder: Folder, chat: &str) -> Result<Option<Folder>, ServerError> {
+    let sql = if folder.chats.contains(&chat.trim().to_string()) {
+        "UPDATE type::thing($tb, $id) SET chats -= $chat;"
     } else {
-        "+"
+        "UPDATE type::thing($tb, $id) SET chats += $chat;"
     };
 
-    Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET chats {2}= '{3}';",
-            FOLDER_TABLE,
-            folder.id.key(),
-            action,
-            chat.trim()
-        ))
-        .await?
-        .take(0)?,
-    ))
+    Query::new(sql)
+        .record(FOLDER_TABLE, &folder.id.key().to_string())
+        .bind_id("chat", chat)
+        .take()
+        .await
+}
+
+pub async fn fav_chat(Path(chat): Path<String>) -> Result<Json<Option<Folder>>, ServerError> {
+    let Some(folder) = get_folder_from_name("Favourites").await? else {
+        return Err(ServerError::NotFound(String::from(
+            "Favourites folder does not exist.",
+        )));
+    };
+    Ok(Json(toggle_folder_chat(folder, &chat).await?))
 }
 pub async fn archive_chat(Path(chat): Path<String>) -> Result<Json<Option<Folder>>, ServerError> {
-    let _ = CONN
-        .query(&format!(
-            "UPDATE {0} SET chats -= '{1}';",
-            FOLDER_TABLE,
-            chat.trim()
-        ))
+    Query::new("UPDATE type::table($tb) SET chats -= $chat;")
+        .table(FOLDER_TABLE)
+        .bind_id("chat", &chat)
+        .execute()
         .await?;
 
-    let folder = get_folder_from_name("Archived").await?.unwrap();
-
-    let action = if folder.chats.contains(&chat.trim().to_string()) {
-        "-"
-    } else {
-        "+"
+    let Some(folder) = get_folder_from_name("Archived").await? else {
+        return Err(ServerError::NotFound(String::from(
+            "Archived folder does not exist.",
+        )));
     };
-
-    Ok(Json(
-        CONN.query(&format!(
-            "UPDATE {0}:{1} SET chats {2}= '{3}';",
-            FOLDER_TABLE,
-            folder.id.key(),
-            action,
-            chat.trim()
-        ))
-        .await?
-        .take(0)?,
-    ))
+    Ok(Json(toggle_folder_chat(folder, &chat).await?))
 }
 pub async fn get_folder_from_name(name: &str) -> Result<Option<Folder>, ServerError> {
-    let mut folder: Vec<Folder> = CONN
-        .query(&format!(
-            "            
-SELECT * FROM {0} WHERE name = '{1}';
-",
-            FOLDER_TABLE, name
-        ))
-        .await?
-        .take(0)?;
+    let mut folder: Vec<Folder> =
+        Query::new("SELECT * FROM type::table($tb) WHERE name = $name AND deleted_at = NONE;")
+            .table(FOLDER_TABLE)
+            .bind_str("name", name)
+            .take()
+            .await?;
 
-    if folder.is_empty() {
-        Ok(None)
-    } else {
-        Ok(folder.pop())
-    }
+    Ok(folder.pop())
 }
 pub async fn create_folder(
     Json(folder): Json<FolderData>,
@@ -216,24 +180,94 @@ pub async fn update_folder(
     ))
 }
 
+#[derive(Deserialize)]
+struct FolderNode {
+    id: String,
+    parent: Option<String>,
+    #[serde(default)]
+    chats: Vec<String>,
+    trashed: bool,
+}
+
+/// The folders and chats hidden by the trash, the trashed folders and everything under them.
+pub(crate) async fn get_hidden_by_trash() -> Result<(Vec<String>, Vec<String>), ServerError> {
+    let folders: Vec<FolderNode> = Query::new(
+        "SELECT record::id(id) AS id, parent, chats, deleted_at != NONE AS trashed FROM type::table($tb);",
+    )
+    .table(FOLDER_TABLE)
+    .take()
+    .await?;
+
+    let mut hidden: HashSet<&str> = folders
+        .iter()
+        .filter(|x| x.trashed)
+        .map(|x| x.id.as_str())
+        .collect();
+
+    // Sub folders can be nested any depth, keep going until nothing new is hidden.
+    loop {
+        let found: Vec<&str> = folders
+            .iter()
+            .filter(|x| !hidden.contains(x.id.as_str()))
+            .filter(|x| x.parent.as_deref().is_some_and(|x| hidden.contains(x)))
+            .map(|x| x.id.as_str())
+            .collect();
+
+        if found.is_empty() {
+            break;
+        }
+
+        hidden.extend(found);
+    }
+
+    let chats = folders
+        .iter()
+        .filter(|x| hidden.contains(x.id.as_str()))
+        .flat_map(|x| x.chats.iter().cloned())
+        .collect();
+
+    Ok((hidden.into_iter().map(String::from).collect(), chats))
+}
+
+/// Moves the folder to the trash, its chats and sub folders are hidden until it is restored.
 pub async fn delete_folder(id: Path<String>) -> Result<Json<Option<Folder>>, ServerError> {
+    Ok(Json(trash_record(FOLDER_TABLE, &id).await?))
+}
+
+/// Deletes the folder for good, moving its sub folders up to the top level and leaving its chats
+/// outside of any folder.
+pub async fn purge_folder(id: Path<String>) -> Result<Json<Option<Folder>>, ServerError> {
+    Query::new("UPDATE type::table($tb) SET parent = NONE WHERE parent = $id;")
+        .record(FOLDER_TABLE, &id)
+        .execute()
+        .await?;
+
     Ok(Json(CONN.delete((FOLDER_TABLE, id.trim())).await?))
 }
 
 pub async fn list_all_folders() -> Result<Json<Vec<Folder>>, ServerError> {
-    Ok(Json(CONN.select(FOLDER_TABLE).await?))
+    let (hidden, _) = get_hidden_by_trash().await?;
+
+    Ok(Json(
+        Query::new("SELECT * FROM type::table($tb) WHERE record::id(id) NOT IN $hidden;")
+            .table(FOLDER_TABLE)
+            .bind("hidden", hidden)
+            .take()
+            .await?,
+    ))
 }
 
 pub async fn search_folders(search: Path<String>) -> Result<Json<Vec<Folder>>, ServerError> {
+    let (hidden, _) = get_hidden_by_trash().await?;
+
     Ok(Json(
-        CONN.query(&format!(
-            "            
-SELECT *, search::score(1) AS score FROM {0} WHERE name @1@ '{1}' ORDER BY score DESC;
-",
-            FOLDER_TABLE,
-            search.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new(
+            "SELECT *, search::score(1) AS score FROM type::table($tb) WHERE name @1@ $search AND record::id(id) NOT IN $hidden ORDER BY score DESC;",
+        )
+        .table(FOLDER_TABLE)
+        .bind_str("search", &search)
+        .bind("hidden", hidden)
+        .take()
+        .await?,
     ))
 }
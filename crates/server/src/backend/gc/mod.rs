@@ -0,0 +1,179 @@
+use crate::backend::{
+    CONN,
+    chats::{CHAT_TABLE, previews::PREVIEW_TABLE},
+    config::get_config,
+    errors::ServerError,
+    files::{FILE_TABLE, remove_upload},
+    messages::MESSAGE_TABLE,
+    query::Query,
+    trash::purge_expired_trash,
+    utils::get_path_local,
+    with_system_session,
+};
+use axum::Json;
+use ochat_types::{files::DBFile, gc::GcReport};
+use serde::Deserialize;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub mod route;
+
+/// Trees and uploads are written before the chat or message pointing at them, so anything younger is left alone.
+const GC_GRACE_HOURS: u64 = 1;
+
+#[derive(Deserialize, Debug)]
+struct MessageFiles {
+    id: String,
+    files: Vec<String>,
+}
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|x| x.modified())
+        .ok()
+        .and_then(|x| x.elapsed().ok())
+        .is_some_and(|x| x >= Duration::from_secs(GC_GRACE_HOURS * 60 * 60))
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name().map(|x| x.to_string_lossy().to_string())
+}
+
+/// Purges expired trash, then finds messages outside of every chat, files no message uses, previews of deleted chats and stray uploads.
+/// Runs in the system session so every user's records are considered.
+pub async fn collect_garbage(dry_run: bool) -> Result<GcReport, ServerError> {
+    with_system_session(collect(dry_run)).await
+}
+
+async fn collect(dry_run: bool) -> Result<GcReport, ServerError> {
+    let trash = match get_config().trash_retention {
+        Some(days) => purge_expired_trash(days, dry_run).await?,
+        None => 0,
+    };
+
+    let mut response = Query::new(
+        "
+LET $roots = SELECT VALUE root FROM type::table($chat_tb) WHERE root != NONE;
+LET $chats = SELECT VALUE record::id(id) FROM type::table($chat_tb);
+SELECT record::id(id) AS id, files FROM type::table($tb) WHERE (root = NONE OR root NOT IN $roots) AND <datetime>time < time::now() - duration::from::hours($grace);
+SELECT record::id(id) AS id, files FROM type::table($tb) WHERE files != [];
+SELECT VALUE record::id(id) FROM type::table($preview_tb) WHERE record::id(id) NOT IN $chats;
+",
+    )
+    .table(MESSAGE_TABLE)
+    .bind_id("chat_tb", CHAT_TABLE)
+    .bind_id("preview_tb", PREVIEW_TABLE)
+    .bind("grace", GC_GRACE_HOURS)
+    .run()
+    .await?;
+
+    let messages: Vec<MessageFiles> = response.take(2)?;
+    let orphans: HashSet<&str> = messages.iter().map(|x| x.id.as_str()).collect();
+    let used: HashSet<String> = response
+        .take::<Vec<MessageFiles>>(3)?
+        .into_iter()
+        .filter(|x| !orphans.contains(x.id.as_str()))
+        .flat_map(|x| x.files)
+        .collect();
+    let previews: Vec<String> = response.take(4)?;
+
+    let files: Vec<DBFile> = CONN.select(FILE_TABLE).await?;
+    let known: HashSet<String> = files
+        .iter()
+        .filter_map(|x| file_name(Path::new(&x.path)))
+        .collect();
+    let files: Vec<DBFile> = files
+        .into_iter()
+        .filter(|x| !used.contains(&x.id.key().to_string()) && is_stale(Path::new(&x.path)))
+        .collect();
+
+    let uploads: Vec<PathBuf> = fs::read_dir(get_path_local("uploads".to_string()))
+        .into_iter()
+        .flatten()
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| x.is_file() && is_stale(x) && file_name(x).is_some_and(|x| !known.contains(&x)))
+        .collect();
+
+    let mut report = GcReport {
+        dry_run,
+        messages: messages.len(),
+        files: files.len(),
+        previews: previews.len(),
+        uploads: uploads.len(),
+        trash,
+        bytes: files
+            .iter()
+            .map(|x| Path::new(&x.path))
+            .chain(uploads.iter().map(|x| x.as_path()))
+            .filter_map(|x| fs::metadata(x).ok())
+            .map(|x| x.len())
+            .sum(),
+    };
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    Query::new(
+        "
+DELETE type::table($tb) WHERE record::id(id) IN $messages;
+DELETE type::table($preview_tb) WHERE record::id(id) IN $previews;
+",
+    )
+    .table(MESSAGE_TABLE)
+    .bind_id("preview_tb", PREVIEW_TABLE)
+    .bind(
+        "messages",
+        messages.into_iter().map(|x| x.id).collect::<Vec<_>>(),
+    )
+    .bind("previews", previews)
+    .execute()
+    .await?;
+
+    report.bytes = 0;
+
+    for file in files {
+        let _: Option<DBFile> = CONN.delete((FILE_TABLE, file.id.key().to_string())).await?;
+        report.bytes += remove_upload(&file.path)?;
+    }
+
+    for upload in uploads {
+        report.bytes += remove_upload(&upload.to_string_lossy())?;
+    }
+
+    Ok(report)
+}
+
+pub async fn run_periodic_gc(every: Duration) {
+    let mut interval = tokio::time::interval(every);
+
+    loop {
+        let _ = interval.tick().await;
+
+        match collect_garbage(false).await {
+            Ok(report) => println!(
+                "Garbage collection removed {} trashed items, {} messages, {} files, {} previews and {} uploads, freeing {} bytes.",
+                report.trash,
+                report.messages,
+                report.files,
+                report.previews,
+                report.uploads,
+                report.bytes
+            ),
+            Err(e) => eprintln!("Garbage collection failed : {}", e),
+        }
+    }
+}
+
+pub async fn get_garbage() -> Result<Json<GcReport>, ServerError> {
+    Ok(Json(collect_garbage(true).await?))
+}
+
+pub async fn remove_garbage() -> Result<Json<GcReport>, ServerError> {
+    Ok(Json(collect_garbage(false).await?))
+}
@@ -0,0 +1,8 @@
+use crate::backend::{gc, system_guard};
+use axum::{Router, middleware, routing::get};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/gc/", get(gc::get_garbage).post(gc::remove_garbage))
+        .route_layer(middleware::from_fn(system_guard))
+}
@@ -1,11 +1,26 @@
-use axum::{Router, routing::get};
+use axum::{
+    Router,
+    routing::{get, post},
+};
 
 use crate::backend::generation;
 
 pub fn routes() -> Router {
     let router = Router::new()
         .route("/generation/text/run/", get(generation::text::run))
-        .route("/generation/text/stream/", get(generation::text::stream));
+        .route("/generation/text/stream/", get(generation::text::stream))
+        .route(
+            "/generation/text/job/",
+            post(generation::text::jobs::start_job),
+        )
+        .route(
+            "/generation/text/job/all/",
+            get(generation::text::jobs::list_jobs),
+        )
+        .route(
+            "/generation/text/job/{id}",
+            get(generation::text::jobs::attach_job).delete(generation::text::jobs::cancel_job),
+        );
 
     #[cfg(feature = "sound")]
     let router = router
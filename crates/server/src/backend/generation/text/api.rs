@@ -1,46 +1,56 @@
 use crate::backend::{
-    CONN,
     errors::ServerError,
-    files::get_file,
-    options::relationships::get_default_gen_options_from_model,
-    providers::{PROVIDER_TABLE, generic_rig, provider_into_config},
-    settings::get_settings,
+    generation::text::{
+        Resolved,
+        options::{Backend, MappedOptions, Param, map_options},
+    },
+    metrics::{GenerationTimer, StreamGuard},
+    providers::{generic_rig, provider_into_config},
     tools::builtin::{WebScraper, WebSearch},
 };
-use axum::Json;
 use futures::{Stream, StreamExt};
 use ochat_types::{
     chats::messages::Role,
     files::FileType,
-    generation::text::{ChatQueryData, ChatResponse, ChatStreamResult, split_text_into_thinking},
-    options::GenOptionKey,
+    generation::text::{
+        ChatQueryData, ChatResponse, ChatStreamResult, Citation, FunctionCall, GenerationMetrics,
+        split_text_into_thinking,
+    },
     providers::Provider,
 };
 use rig::{
     OneOrMany,
     client::{CompletionClient, EmbeddingsClient},
-    completion::{Completion, Prompt},
+    completion::{Completion, GetTokenUsage, Prompt},
     embeddings::{EmbeddingsBuilder, ToolSchema},
     message::ImageMediaType,
     streaming::StreamingCompletion,
     tool::ToolSet,
     vector_store::in_memory_store::{InMemoryVectorIndex, InMemoryVectorStore},
 };
+use serde_json::Value;
 use std::{collections::HashMap, thread, time::Duration};
+use tokio::sync::mpsc::UnboundedSender;
 
 type Agent = rig::agent::Agent<generic_rig::CompletionModel>;
 
+/// Completions a single answer may take, so a model that keeps calling tools still has to reply.
+const MAX_TOOL_ROUNDS: usize = 4;
+
+/// Builds the agent and history for the query, along with the generation options it applied.
 async fn get_chat_completion_request(
     query: &ChatQueryData,
-) -> Result<(Agent, Vec<rig::message::Message>), ServerError> {
-    let Some(provider) = CONN
-        .select::<Option<Provider>>((PROVIDER_TABLE, query.provider.trim()))
-        .await?
-    else {
-        panic!()
+    resolved: &Resolved,
+) -> Result<(Agent, Vec<rig::message::Message>, MappedOptions), ServerError> {
+    let Some(provider) = &resolved.provider else {
+        return Err(ServerError::NotFound(format!(
+            "Provider '{}' does not exist.",
+            query.provider.trim()
+        )));
     };
 
-    let provider = provider_into_config(&provider);
+    let backend = Backend::from(&provider.provider_type);
+    let provider = provider_into_config(provider);
     let mut agent = provider.agent(query.model.trim());
     let mut messages = Vec::new();
     for chat in query.messages.iter() {
@@ -49,39 +59,33 @@ async fn get_chat_completion_request(
                 content: OneOrMany::many({
                     let mut parts = vec![rig::message::UserContent::text(chat.text.to_string())];
                     for file in chat.files.iter() {
-                        match get_file(axum::extract::Path(file.clone()))
-                            .await
-                            .map(|x| x.0)
-                        {
-                            Ok(Some(image)) if image.file_type == FileType::Image => {
+                        match resolved.files.get(file).cloned() {
+                            Some(image) if image.file_type == FileType::Image => {
                                 parts.push(rig::message::UserContent::image_base64(
                                     image.b64data,
-                                    match image
-                                        .filename
-                                        .rsplit_once(".")
-                                        .map(|x| x.1.trim())
-                                        .unwrap()
-                                    {
-                                        "jpg" | "jpeg" => Some(ImageMediaType::JPEG),
-                                        "gif" => Some(ImageMediaType::GIF),
-                                        "png" => Some(ImageMediaType::PNG),
-                                        "webp" => Some(ImageMediaType::WEBP),
-                                        "heic" => Some(ImageMediaType::HEIC),
-                                        "heif" => Some(ImageMediaType::HEIF),
-                                        "svg" => Some(ImageMediaType::SVG),
-                                        _ => None,
-                                    },
+                                    image.filename.rsplit_once(".").and_then(|x| {
+                                        match x.1.trim() {
+                                            "jpg" | "jpeg" => Some(ImageMediaType::JPEG),
+                                            "gif" => Some(ImageMediaType::GIF),
+                                            "png" => Some(ImageMediaType::PNG),
+                                            "webp" => Some(ImageMediaType::WEBP),
+                                            "heic" => Some(ImageMediaType::HEIC),
+                                            "heif" => Some(ImageMediaType::HEIF),
+                                            "svg" => Some(ImageMediaType::SVG),
+                                            _ => None,
+                                        }
+                                    }),
                                     None,
                                 ))
                             }
-                            Ok(Some(file)) => parts.push(rig::message::UserContent::Document(
+                            Some(file) => parts.push(rig::message::UserContent::Document(
                                 rig::message::Document {
                                     additional_params: None,
                                     data: rig::message::DocumentSourceKind::Base64(file.b64data),
                                     media_type: Some(rig::message::DocumentMediaType::MARKDOWN),
                                 },
                             )),
-                            _ => {}
+                            None => {}
                         }
                     }
                     parts
@@ -98,87 +102,54 @@ async fn get_chat_completion_request(
                 agent = agent.append_preamble(&chat.text.to_string());
                 continue;
             }
-            Role::Function => todo!(),
-        })
-    }
+            // Replayed as the assistant's calls followed by their results.
+            Role::Function if !chat.func_calls.is_empty() => {
+                let mut calls = Vec::new();
+                let mut results = Vec::new();
 
-    let mut params = HashMap::new();
-    if let Ok(Json(Some(options))) = get_default_gen_options_from_model(axum::extract::Path((
-        query.provider.clone(),
-        query.model.clone(),
-    )))
-    .await
-    {
-        for option in options.data {
-            if !option.activated {
-                continue;
-            }
-            match option.key {
-                GenOptionKey::Temperature => {
-                    agent = agent.temperature(option.value.as_f32() as f64)
-                }
-                GenOptionKey::Mirostat => {
-                    params.insert("mirostat", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::MirostatETA => {
-                    params.insert("mirostat_eta", serde_json::to_value(option.value.as_f32())?);
-                }
-                GenOptionKey::MirostatTau => {
-                    params.insert("mirostat_tau", serde_json::to_value(option.value.as_f32())?);
-                }
-                GenOptionKey::CtxWindow => {
-                    params.insert("num_ctx", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::NumGQA => {
-                    params.insert("num_gqa", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::GPULayers => {
-                    params.insert("num_gpu", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::NumThreads => {
-                    params.insert("num_thread", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::RepeatN => {
-                    params.insert(
-                        "repeat_last_n",
-                        serde_json::to_value(option.value.as_i32())?,
-                    );
+                for (i, call) in chat.func_calls.iter().enumerate() {
+                    let id = call.id.clone().unwrap_or_else(|| format!("call_{}", i));
+                    calls.push(rig::message::AssistantContent::tool_call(
+                        id.clone(),
+                        call.name.clone(),
+                        serde_json::to_value(&call.args)?,
+                    ));
+                    results.push(rig::message::UserContent::tool_result(
+                        id,
+                        OneOrMany::one(rig::message::ToolResultContent::text(call.result_text())),
+                    ));
                 }
-                GenOptionKey::RepeatPenalty => {
-                    params.insert(
-                        "repeat_penalty",
-                        serde_json::to_value(option.value.as_f32())?,
-                    );
-                }
-                GenOptionKey::Seed => {
-                    params.insert("seed", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::TailFreeZ => {
-                    params.insert("tfs_z", serde_json::to_value(option.value.as_f32())?);
-                }
-                GenOptionKey::NumberPredict => {
-                    params.insert("num_predict", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::TopK => {
-                    params.insert("top_k", serde_json::to_value(option.value.as_i32())?);
-                }
-                GenOptionKey::TopP => {
-                    params.insert("top_p", serde_json::to_value(option.value.as_f32())?);
+
+                messages.push(rig::message::Message::Assistant {
+                    id: None,
+                    content: OneOrMany::many(calls).unwrap(),
+                });
+                rig::message::Message::User {
+                    content: OneOrMany::many(results).unwrap(),
                 }
-                GenOptionKey::StopSequence => {}
             }
+            Role::Function => rig::message::Message::user(format!("Tool output:\n{}", chat.text)),
+        })
+    }
+
+    let options = map_options(backend, resolved.options.clone());
+    for param in options.params.iter() {
+        match param {
+            Param::Temperature(x) => agent = agent.temperature(*x),
+            Param::MaxTokens(x) => agent = agent.max_tokens(*x as u64),
+            _ => {}
         }
     }
 
-    if !params.is_empty() {
-        agent = agent.additional_params(serde_json::to_value(params)?);
+    if let Some(params) = options.additional_params() {
+        agent = agent.additional_params(params);
     }
 
     Ok((
         if query.force_disable_tools || query.tools.is_empty() {
             agent.build()
         } else {
-            match get_tools().await {
+            match get_tools(resolved.embeddings.as_ref()).await {
                 Ok(Some(tools))
                     if check_tool_compatibality(&provider, query.model.trim()).await =>
                 {
@@ -188,6 +159,7 @@ async fn get_chat_completion_request(
             }
         },
         messages,
+        options,
     ))
 }
 
@@ -200,7 +172,16 @@ pub async fn check_tool_compatibality(client: &generic_rig::Client, model: &str)
     }
 }
 
-pub async fn get_tools() -> Result<
+fn get_toolset() -> ToolSet {
+    ToolSet::builder()
+        .dynamic_tool(WebScraper)
+        .dynamic_tool(WebSearch)
+        .build()
+}
+
+pub async fn get_tools(
+    embeddings: Option<&(Provider, String)>,
+) -> Result<
     Option<(
         usize,
         InMemoryVectorIndex<generic_rig::EmbeddingModel<reqwest::Client>, ToolSchema>,
@@ -208,24 +189,13 @@ pub async fn get_tools() -> Result<
     )>,
     ServerError,
 > {
-    let toolset = ToolSet::builder()
-        .dynamic_tool(WebScraper)
-        .dynamic_tool(WebSearch)
-        .build();
+    let toolset = get_toolset();
 
-    let Some(provider) = get_settings().await?.0.embeddings_provider else {
+    let Some((provider, model)) = embeddings else {
         return Ok(None);
     };
-    let model = provider.model;
 
-    let Some(provider) = CONN
-        .select::<Option<Provider>>((PROVIDER_TABLE, provider.provider.trim()))
-        .await?
-    else {
-        panic!()
-    };
-
-    let client = provider_into_config(&provider);
+    let client = provider_into_config(provider);
 
     let embedding_model = client.embedding_model(model);
     let embeddings = EmbeddingsBuilder::new(embedding_model.clone())
@@ -240,44 +210,155 @@ pub async fn get_tools() -> Result<
     Ok(Some((2, index, toolset)))
 }
 
-pub async fn run(data: ChatQueryData) -> Result<Json<ChatResponse>, ServerError> {
-    let request = get_chat_completion_request(&data).await?;
-
-    let response = {
-        let mut messages = request.1;
-        request
-            .0
-            .completion(
-                if messages.len() % 2 == 0 {
-                    rig::message::Message::user("Now generate from your previous instructions...")
-                } else {
-                    messages.pop().unwrap()
-                },
-                messages,
-            )
-            .await?
-            .send()
-            .await?
+/// The prompt for the next completion, which is the history's last message when the user sent it.
+fn take_prompt(messages: &mut Vec<rig::message::Message>) -> rig::message::Message {
+    match messages.last() {
+        Some(rig::message::Message::User { .. }) => messages.pop().unwrap(),
+        _ => rig::message::Message::user("Now generate from your previous instructions..."),
+    }
+}
+
+/// Runs the tool calls a completion ended with and adds the exchange to the history,
+/// leaving the results as the prompt of the next completion.
+async fn call_tools(
+    messages: &mut Vec<rig::message::Message>,
+    prompt: rig::message::Message,
+    calls: Vec<rig::message::ToolCall>,
+    tx: Option<&UnboundedSender<ChatStreamResult>>,
+) -> Vec<FunctionCall> {
+    let send = |x: ChatStreamResult| {
+        if let Some(tx) = tx {
+            let _ = tx.send(x);
+        }
     };
+    let toolset = get_toolset();
+    let mut content = Vec::new();
+    let mut results = Vec::new();
+    let mut func_calls = Vec::new();
+
+    for call in calls {
+        let mut func_call = FunctionCall {
+            id: Some(call.id.clone()),
+            name: call.function.name.clone(),
+            args: match &call.function.arguments {
+                Value::Object(x) => x.clone().into_iter().collect(),
+                _ => HashMap::new(),
+            },
+            result: None,
+        };
+        send(ChatStreamResult::ToolCall(func_call.clone()));
+
+        let output = match toolset
+            .call(&call.function.name, call.function.arguments.to_string())
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => e.to_string(),
+        };
+
+        results.push(rig::message::UserContent::tool_result(
+            call.id.clone(),
+            OneOrMany::one(rig::message::ToolResultContent::text(output.clone())),
+        ));
+        let result = serde_json::from_str(&output).unwrap_or(Value::String(output));
+        let citations = get_citations(&result);
+        func_call.result = Some(result);
+
+        send(ChatStreamResult::ToolResult(func_call.clone()));
+        if !citations.is_empty() {
+            send(ChatStreamResult::Citations(citations));
+        }
+
+        func_calls.push(func_call);
+        content.push(rig::message::AssistantContent::ToolCall(call));
+    }
+
+    messages.push(prompt);
+    messages.push(rig::message::Message::Assistant {
+        id: None,
+        content: OneOrMany::many(content).unwrap(),
+    });
+    messages.push(rig::message::Message::User {
+        content: OneOrMany::many(results).unwrap(),
+    });
+
+    func_calls
+}
+
+/// Search results carry their `url` and `title`, scraped pages are keyed by their url.
+fn get_citations(result: &Value) -> Vec<Citation> {
+    match result {
+        Value::Array(x) => x
+            .iter()
+            .filter_map(|x| {
+                Some(Citation {
+                    url: x["url"].as_str()?.to_string(),
+                    title: x["title"].as_str().map(|x| x.to_string()),
+                })
+            })
+            .collect(),
+        Value::Object(x) => x
+            .keys()
+            .filter(|x| x.starts_with("http"))
+            .map(|x| Citation {
+                url: x.clone(),
+                title: None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub async fn run(data: ChatQueryData, resolved: &Resolved) -> Result<ChatResponse, ServerError> {
+    let (agent, mut messages, options) = get_chat_completion_request(&data, resolved).await?;
 
     let mut content = String::new();
     let mut thinking = String::new();
+    let mut func_calls = Vec::new();
+    let mut prompt_tokens = 0;
+    let mut completion_tokens = 0;
+    let mut finish_reason = None;
+
+    for round in 1..=MAX_TOOL_ROUNDS {
+        let prompt = take_prompt(&mut messages);
+        let response = agent
+            .completion(prompt.clone(), messages.clone())
+            .await?
+            .send()
+            .await?;
 
-    for choice in response.choice.iter() {
-        match choice {
-            rig::message::AssistantContent::Text(x) => content.push_str(&x.text),
-            rig::message::AssistantContent::Reasoning(x) => {
-                for x in x.reasoning.iter() {
-                    thinking.push_str(&x)
+        prompt_tokens += response.usage.input_tokens;
+        completion_tokens += response.usage.output_tokens;
+        finish_reason = response
+            .raw_response
+            .choices
+            .first()
+            .map(|x| x.finish_reason.clone());
+
+        let mut calls = Vec::new();
+        for choice in response.choice.iter() {
+            match choice {
+                rig::message::AssistantContent::Text(x) => content.push_str(&x.text),
+                rig::message::AssistantContent::Reasoning(x) => {
+                    for x in x.reasoning.iter() {
+                        thinking.push_str(&x)
+                    }
                 }
+                rig::message::AssistantContent::ToolCall(x) => calls.push(x.clone()),
+                _ => {}
             }
-            _ => {}
         }
+
+        if calls.is_empty() || round == MAX_TOOL_ROUNDS {
+            break;
+        }
+
+        func_calls.extend(call_tools(&mut messages, prompt, calls, None).await);
     }
 
     let (content, thinking2) = split_text_into_thinking(content);
 
-    Ok(Json(ChatResponse {
+    Ok(ChatResponse {
         role: Role::AI,
         content,
         thinking: if thinking.is_empty() {
@@ -288,94 +369,147 @@ pub async fn run(data: ChatQueryData) -> Result<Json<ChatResponse>, ServerError>
             }
             Some(thinking)
         },
-        func_calls: Vec::new(),
-    }))
+        func_calls,
+        metrics: Some(GenerationMetrics {
+            prompt_tokens: Some(prompt_tokens).filter(|x| *x > 0),
+            completion_tokens: Some(completion_tokens).filter(|x| *x > 0),
+            finish_reason,
+            options: options.applied,
+            unsupported_options: options.unsupported,
+            ..Default::default()
+        }),
+    })
 }
 
-pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult> {
+pub async fn stream(
+    data: ChatQueryData,
+    resolved: Resolved,
+) -> impl Stream<Item = ChatStreamResult> {
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
     tokio::spawn(async move {
-        let request = match get_chat_completion_request(&data).await {
-            Ok(x) => x,
-            Err(e) => {
-                let _ = tx.send(ChatStreamResult::Err(e.to_string()));
-                let _ = tx.send(ChatStreamResult::Finished);
-                return;
-            }
-        };
+        let _guard = StreamGuard::start();
+        let mut timer = GenerationTimer::new(&data.provider, &data.model);
 
-        let mut response = {
-            let mut messages = request.1;
-            match request
-                .0
-                .stream_completion(
-                    if messages.len() % 2 == 0 {
-                        rig::message::Message::user(
-                            "Now generate from your previous instructions...",
-                        )
-                    } else {
-                        messages.pop().unwrap()
-                    },
-                    messages,
-                )
-                .await
-            {
-                Ok(x) => match x.stream().await {
-                    Ok(x) => x,
-                    Err(e) => {
-                        let _ = tx.send(ChatStreamResult::Err(e.to_string()));
-                        let _ = tx.send(ChatStreamResult::Finished);
-                        return;
-                    }
-                },
+        let (agent, mut messages, options) =
+            match get_chat_completion_request(&data, &resolved).await {
+                Ok(x) => x,
                 Err(e) => {
+                    timer.fail();
                     let _ = tx.send(ChatStreamResult::Err(e.to_string()));
                     let _ = tx.send(ChatStreamResult::Finished);
                     return;
                 }
-            }
-        };
+            };
 
         let mut content = String::new();
         let mut thinking = String::new();
-        while let Some(response) = response.next().await {
-            match response {
-                Ok(response) => {
-                    let mut temp = String::new();
-                    let mut temp_thinking = String::new();
-                    match response {
-                        rig::streaming::StreamedAssistantContent::Text(x) => temp.push_str(&x.text),
-                        rig::streaming::StreamedAssistantContent::Reasoning(x) => {
-                            for x in x.reasoning.iter() {
-                                temp_thinking.push_str(x)
-                            }
-                        }
-                        rig::streaming::StreamedAssistantContent::ReasoningDelta {
-                            id: _,
-                            reasoning,
-                        } => temp_thinking.push_str(&reasoning),
-                        _ => {}
-                    }
-                    content.push_str(&temp);
-                    thinking.push_str(&temp_thinking);
-
-                    let _ = tx.send(ChatStreamResult::Generating(ChatResponse {
-                        role: Role::AI,
-                        content: temp,
-                        thinking: if temp_thinking.is_empty() {
-                            None
-                        } else {
-                            Some(temp_thinking)
-                        },
-                        func_calls: Vec::new(),
-                    }));
+        let mut func_calls = Vec::new();
+        let mut prompt_tokens = 0;
+        let mut completion_tokens = 0;
+        let mut finish_reason = None;
+
+        for round in 1..=MAX_TOOL_ROUNDS {
+            let prompt = take_prompt(&mut messages);
+            let response = match agent
+                .stream_completion(prompt.clone(), messages.clone())
+                .await
+            {
+                Ok(x) => x.stream().await,
+                Err(e) => Err(e),
+            };
+
+            let mut response = match response {
+                Ok(x) => x,
+                Err(e) if round == 1 => {
+                    timer.fail();
+                    let _ = tx.send(ChatStreamResult::Err(e.to_string()));
+                    let _ = tx.send(ChatStreamResult::Finished);
+                    return;
                 }
                 Err(e) => {
+                    finish_reason = Some(String::from("error"));
                     let _ = tx.send(ChatStreamResult::Err(e.to_string()));
+                    break;
+                }
+            };
+
+            let mut calls = Vec::new();
+            // Stop pulling from the provider as soon as nobody is listening any more, so a
+            // cancelled job or dropped client also ends the upstream request.
+            while let Some(response) = tokio::select! {
+                x = response.next() => x,
+                _ = tx.closed() => None,
+            } {
+                match response {
+                    Ok(rig::streaming::StreamedAssistantContent::Final(x)) => {
+                        if let Some(usage) = x.token_usage() {
+                            prompt_tokens += usage.input_tokens;
+                            completion_tokens += usage.output_tokens;
+                        }
+                    }
+                    Ok(rig::streaming::StreamedAssistantContent::ToolCall(x)) => calls.push(x),
+                    Ok(response) => {
+                        let mut temp = String::new();
+                        let mut temp_thinking = String::new();
+                        match response {
+                            rig::streaming::StreamedAssistantContent::Text(x) => {
+                                temp.push_str(&x.text)
+                            }
+                            rig::streaming::StreamedAssistantContent::Reasoning(x) => {
+                                for x in x.reasoning.iter() {
+                                    temp_thinking.push_str(x)
+                                }
+                            }
+                            rig::streaming::StreamedAssistantContent::ReasoningDelta {
+                                id: _,
+                                reasoning,
+                            } => temp_thinking.push_str(&reasoning),
+                            _ => {}
+                        }
+                        content.push_str(&temp);
+                        thinking.push_str(&temp_thinking);
+                        timer.chunk();
+
+                        let _ = tx.send(ChatStreamResult::Generating(ChatResponse {
+                            role: Role::AI,
+                            content: temp,
+                            thinking: if temp_thinking.is_empty() {
+                                None
+                            } else {
+                                Some(temp_thinking)
+                            },
+                            func_calls: Vec::new(),
+                            metrics: None,
+                        }));
+                    }
+                    Err(e) => {
+                        finish_reason = Some(String::from("error"));
+                        let _ = tx.send(ChatStreamResult::Err(e.to_string()));
+                    }
                 }
             }
+
+            if calls.is_empty() || tx.is_closed() || round == MAX_TOOL_ROUNDS {
+                break;
+            }
+
+            func_calls.extend(call_tools(&mut messages, prompt, calls, Some(&tx)).await);
+            let _ = tx.send(ChatStreamResult::Status(String::from(
+                "Reading the tool results",
+            )));
         }
+
+        let mut metrics = GenerationMetrics {
+            prompt_tokens: Some(prompt_tokens).filter(|x| *x > 0),
+            completion_tokens: Some(completion_tokens).filter(|x| *x > 0),
+            finish_reason,
+            options: options.applied,
+            unsupported_options: options.unsupported,
+            ..Default::default()
+        };
+        timer.finish(&mut metrics);
+
         let (content, thinking2) = split_text_into_thinking(content);
         let _ = tx.send(ChatStreamResult::Generated(ChatResponse {
             role: Role::AI,
@@ -388,7 +522,8 @@ pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult>
                 }
                 Some(thinking)
             },
-            func_calls: Vec::new(),
+            func_calls,
+            metrics: Some(metrics),
         }));
 
         thread::sleep(Duration::from_millis(20));
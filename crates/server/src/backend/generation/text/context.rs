@@ -0,0 +1,463 @@
+use crate::backend::{
+    chats::messages::{MESSAGE_TABLE, get_own_messages},
+    errors::ServerError,
+    generation::text::{Resolved, api, mistralrs},
+    providers::provider_into_reqwest,
+    query::Query,
+};
+use ochat_types::{
+    chats::messages::Role,
+    generation::text::{ChatQueryData, ChatQueryMessage, ContextTrim},
+    options::GenOptionKey,
+    providers::ProviderType,
+    settings::ContextStrategy,
+};
+use serde_json::{Value, json};
+use std::{fs, path::Path};
+use tokenizers::Tokenizer;
+
+/// Ollama only allocates its default window unless `num_ctx` is set.
+const OLLAMA_DEFAULT_CONTEXT: u64 = 4096;
+/// Share of the window kept free for the answer when `NumberPredict` is not set.
+const RESPONSE_SHARE: u64 = 4;
+/// Share of the budget kept free for the summary when summarizing.
+const SUMMARY_SHARE: u64 = 8;
+/// Rough cost of the role markers a chat template wraps around every message.
+const MESSAGE_OVERHEAD: u64 = 4;
+const CHARS_PER_TOKEN: u64 = 4;
+
+const SUMMARY_PROMPT: &str = "
+### Task:
+Summarize the conversation below so it can replace the original messages.
+### Guidelines:
+- Keep names, numbers, decisions, code and any open questions.
+- If a previous summary is given, merge it with the new messages into a single summary.
+- Write in the conversation's primary language.
+- Return only the summary.
+";
+
+enum TokenCounter {
+    Tokenizer(Box<Tokenizer>),
+    Estimate,
+}
+
+impl TokenCounter {
+    /// Local models ship their tokenizer, everything else gets a character based estimate.
+    fn new(resolved: &Resolved) -> Self {
+        let Some((path, _)) = &resolved.model_dir else {
+            return Self::Estimate;
+        };
+
+        ["tokenizer.json", "tokenizer.json.sec", "tokenizer.json.ter"]
+            .iter()
+            .find_map(|x| Tokenizer::from_file(path.join(x)).ok())
+            .map(|x| Self::Tokenizer(Box::new(x)))
+            .unwrap_or(Self::Estimate)
+    }
+
+    fn count(&self, message: &ChatQueryMessage) -> u64 {
+        let estimate = || (message.text.chars().count() as u64).div_ceil(CHARS_PER_TOKEN);
+
+        MESSAGE_OVERHEAD
+            + match self {
+                Self::Tokenizer(tokenizer) => tokenizer
+                    .encode(message.text.as_str(), false)
+                    .map(|x| x.len() as u64)
+                    .unwrap_or_else(|_| estimate()),
+                Self::Estimate => estimate(),
+            }
+    }
+}
+
+/// Drops or summarizes the oldest messages of the query when its history will not fit
+/// in the model's context window, keeping system messages and the latest message.
+pub async fn fit_to_context(
+    query: &mut ChatQueryData,
+    resolved: &Resolved,
+) -> Result<Option<ContextTrim>, ServerError> {
+    let settings = &resolved.settings;
+    if settings.context_strategy == ContextStrategy::Off {
+        return Ok(None);
+    }
+
+    let Some(window) = get_context_window(query, resolved).await else {
+        return Ok(None);
+    };
+
+    let reserve = resolved
+        .options
+        .as_ref()
+        .and_then(|x| x.get_option_from_key(&GenOptionKey::NumberPredict))
+        .filter(|x| x.activated && x.value.as_i32() > 0)
+        .map(|x| x.value.as_i32() as u64)
+        .unwrap_or(window / RESPONSE_SHARE);
+    let budget = window.saturating_sub(reserve);
+
+    let counter = TokenCounter::new(resolved);
+    let tokens: Vec<u64> = query.messages.iter().map(|x| counter.count(x)).collect();
+    if tokens.iter().sum::<u64>() <= budget {
+        return Ok(None);
+    }
+
+    let history_budget = if settings.context_strategy == ContextStrategy::Summarize {
+        budget - budget / SUMMARY_SHARE
+    } else {
+        budget
+    };
+
+    let Some((first_kept, mut used)) = get_first_kept(&query.messages, &tokens, history_budget)
+    else {
+        return Ok(None);
+    };
+
+    let (older, newer) = query.messages.split_at(first_kept);
+    let (system, removed): (Vec<ChatQueryMessage>, Vec<ChatQueryMessage>) =
+        older.iter().cloned().partition(|x| x.role == Role::System);
+
+    if removed.is_empty() {
+        return Ok(None);
+    }
+
+    let mut messages = system;
+    if settings.context_strategy == ContextStrategy::Summarize {
+        let summary = summarize(query, resolved, &removed, &counter, budget).await?;
+        let summary = ChatQueryMessage {
+            text: format!("Summary of the earlier conversation:\n{}", summary),
+            files: Vec::new(),
+            role: Role::System,
+            id: None,
+            func_calls: Vec::new(),
+        };
+        used += counter.count(&summary);
+        messages.push(summary);
+    }
+
+    let trim = ContextTrim {
+        strategy: settings.context_strategy,
+        trimmed: removed.len(),
+        first_kept: newer.first().and_then(|x| x.id.clone()),
+        context_window: window,
+        prompt_tokens: used,
+    };
+
+    messages.extend_from_slice(newer);
+    query.messages = messages;
+
+    Ok(Some(trim))
+}
+
+/// The oldest message kept within the budget and the tokens of everything kept, system
+/// messages included. The latest message is always kept, however long it is.
+fn get_first_kept(
+    messages: &[ChatQueryMessage],
+    tokens: &[u64],
+    budget: u64,
+) -> Option<(usize, u64)> {
+    let last = messages.iter().rposition(|x| x.role != Role::System)?;
+
+    let mut used: u64 = messages
+        .iter()
+        .zip(tokens.iter())
+        .filter(|x| x.0.role == Role::System)
+        .map(|x| x.1)
+        .sum();
+    let mut first_kept = last;
+
+    for (i, message) in messages.iter().enumerate().rev() {
+        if message.role == Role::System {
+            continue;
+        }
+
+        if i != last && used + tokens[i] > budget {
+            break;
+        }
+
+        used += tokens[i];
+        first_kept = i;
+    }
+
+    // Start on a user turn so the history still alternates.
+    while first_kept < last && messages[first_kept].role != Role::User {
+        if messages[first_kept].role != Role::System {
+            used -= tokens[first_kept];
+        }
+        first_kept += 1;
+    }
+
+    Some((first_kept, used))
+}
+
+async fn get_context_window(query: &ChatQueryData, resolved: &Resolved) -> Option<u64> {
+    if let Some(option) = resolved
+        .options
+        .as_ref()
+        .and_then(|x| x.get_option_from_key(&GenOptionKey::CtxWindow))
+        .filter(|x| x.activated && x.value.as_i32() > 0)
+    {
+        return Some(option.value.as_i32() as u64);
+    }
+
+    let window = match &resolved.model_dir {
+        Some((path, _)) => get_hf_context_window(path),
+        None => get_ollama_context_window(query, resolved).await,
+    };
+
+    window.or(resolved.settings.context_window.map(|x| x as u64))
+}
+
+fn get_hf_context_window(path: &Path) -> Option<u64> {
+    ["config.json", "config.json.sec", "config.json.ter"]
+        .iter()
+        .find_map(|x| read_json(&path.join(x)))
+        .and_then(|x| x["max_position_embeddings"].as_u64())
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+async fn get_ollama_context_window(query: &ChatQueryData, resolved: &Resolved) -> Option<u64> {
+    let provider = resolved.provider.as_ref()?;
+
+    if provider.provider_type != ProviderType::Ollama {
+        return None;
+    }
+
+    let info: Value = provider_into_reqwest(provider)
+        .build()
+        .ok()?
+        .post(format!(
+            "{}/api/show",
+            provider.url.trim_end_matches('/').trim_end_matches("/v1")
+        ))
+        .json(&json!({ "model": query.model.trim() }))
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+
+    info["model_info"]
+        .as_object()?
+        .iter()
+        .find(|x| x.0.ends_with(".context_length"))
+        .and_then(|x| x.1.as_u64())
+        .map(|x| x.min(OLLAMA_DEFAULT_CONTEXT))
+}
+
+/// Forgets the ids of messages outside the caller's chats, so their summaries are neither
+/// read nor cached. Jobs generate after their request is done, so this runs while it is not.
+pub(crate) async fn keep_own_ids(query: &mut ChatQueryData) {
+    let ids = query.messages.iter().filter_map(|x| x.id.clone()).collect();
+    let own = get_own_messages(ids).await.unwrap_or_default();
+
+    for message in query.messages.iter_mut() {
+        if message.id.as_ref().is_some_and(|x| !own.contains(x)) {
+            message.id = None;
+        }
+    }
+}
+
+/// Folds `messages` into a rolling summary, resuming from the newest summary cached on
+/// one of them and caching each new summary on the last message it covers.
+async fn summarize(
+    query: &ChatQueryData,
+    resolved: &Resolved,
+    messages: &[ChatQueryMessage],
+    counter: &TokenCounter,
+    budget: u64,
+) -> Result<String, ServerError> {
+    let mut summary = None;
+    let mut start = 0;
+
+    for (i, message) in messages.iter().enumerate().rev() {
+        let Some(id) = &message.id else {
+            continue;
+        };
+
+        let cached: Option<String> = resolved
+            .with_session(
+                Query::new("RETURN (SELECT VALUE summary FROM type::thing($tb, $id))[0];")
+                    .record(MESSAGE_TABLE, id)
+                    .take(),
+            )
+            .await?;
+
+        if cached.is_some() {
+            summary = cached;
+            start = i + 1;
+            break;
+        }
+    }
+
+    let prompt = counter.count(&ChatQueryMessage {
+        text: SUMMARY_PROMPT.to_string(),
+        files: Vec::new(),
+        role: Role::System,
+        id: None,
+        func_calls: Vec::new(),
+    });
+
+    let mut batch: Vec<&ChatQueryMessage> = Vec::new();
+    let mut used = 0;
+
+    for (i, message) in messages.iter().enumerate().skip(start) {
+        let tokens = counter.count(message);
+        let summary_tokens = summary
+            .as_ref()
+            .map(|x| (x.chars().count() as u64).div_ceil(CHARS_PER_TOKEN))
+            .unwrap_or_default();
+
+        if !batch.is_empty() && prompt + summary_tokens + used + tokens > budget {
+            summary = Some(summarize_batch(query, resolved, summary.as_deref(), &batch).await?);
+            cache_summary(resolved, &messages[i - 1], summary.as_deref()).await?;
+            batch.clear();
+            used = 0;
+        }
+
+        batch.push(message);
+        used += tokens;
+    }
+
+    if !batch.is_empty() {
+        summary = Some(summarize_batch(query, resolved, summary.as_deref(), &batch).await?);
+        cache_summary(resolved, messages.last().unwrap(), summary.as_deref()).await?;
+    }
+
+    Ok(summary.unwrap_or_default())
+}
+
+async fn summarize_batch(
+    query: &ChatQueryData,
+    resolved: &Resolved,
+    summary: Option<&str>,
+    batch: &[&ChatQueryMessage],
+) -> Result<String, ServerError> {
+    let mut text = String::new();
+    if let Some(summary) = summary {
+        text.push_str(&format!("Previous summary:\n{}\n\n", summary));
+    }
+
+    for message in batch {
+        text.push_str(&format!(
+            "{}: {}\n\n",
+            match message.role {
+                Role::User => "User",
+                Role::AI => "Assistant",
+                Role::Function => "Tool",
+                Role::System => "System",
+            },
+            message.text.trim()
+        ));
+    }
+
+    let data = ChatQueryData {
+        provider: query.provider.clone(),
+        model: query.model.clone(),
+        force_disable_tools: true,
+        tools: Vec::new(),
+        messages: vec![
+            ChatQueryMessage {
+                text: SUMMARY_PROMPT.to_string(),
+                files: Vec::new(),
+                role: Role::System,
+                id: None,
+                func_calls: Vec::new(),
+            },
+            ChatQueryMessage {
+                text,
+                files: Vec::new(),
+                role: Role::User,
+                id: None,
+                func_calls: Vec::new(),
+            },
+        ],
+        options: query.options.clone(),
+    };
+
+    let response = if data.provider.starts_with("HF") {
+        mistralrs::run(data, resolved).await?
+    } else {
+        api::run(data, resolved).await?
+    };
+
+    Ok(response.content)
+}
+
+async fn cache_summary(
+    resolved: &Resolved,
+    message: &ChatQueryMessage,
+    summary: Option<&str>,
+) -> Result<(), ServerError> {
+    let (Some(id), Some(summary)) = (&message.id, summary) else {
+        return Ok(());
+    };
+
+    resolved
+        .with_session(
+            Query::new("UPDATE type::thing($tb, $id) SET summary = $summary;")
+                .record(MESSAGE_TABLE, id)
+                .bind_str("summary", summary)
+                .execute(),
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(roles: &[Role]) -> Vec<ChatQueryMessage> {
+        roles
+            .iter()
+            .map(|role| ChatQueryMessage {
+                text: String::new(),
+                files: Vec::new(),
+                role: role.clone(),
+                id: None,
+                func_calls: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_a_user_turn_first() {
+        let messages = messages(&[
+            Role::System,
+            Role::User,
+            Role::AI,
+            Role::User,
+            Role::AI,
+            Role::User,
+        ]);
+        let tokens = vec![10; messages.len()];
+
+        // The assistant turn at 4 still fits, but the history cannot start on it.
+        assert_eq!(get_first_kept(&messages, &tokens, 35), Some((5, 20)));
+        assert_eq!(get_first_kept(&messages, &tokens, 45), Some((3, 40)));
+        assert_eq!(get_first_kept(&messages, &tokens, 60), Some((1, 60)));
+    }
+
+    #[test]
+    fn always_keeps_the_latest_message() {
+        assert_eq!(get_first_kept(&[], &[], 20), None);
+        assert_eq!(get_first_kept(&messages(&[Role::System]), &[10], 20), None);
+
+        let messages = messages(&[Role::User, Role::AI, Role::User]);
+        assert_eq!(
+            get_first_kept(&messages, &[10, 10, 100], 20),
+            Some((2, 100))
+        );
+    }
+
+    #[test]
+    fn skips_system_messages_between_turns() {
+        let messages = messages(&[Role::User, Role::AI, Role::System, Role::AI, Role::User]);
+        let tokens = vec![10, 10, 5, 10, 10];
+
+        // Dropping the leading assistant turns leaves the system message counted once.
+        assert_eq!(get_first_kept(&messages, &tokens, 30), Some((4, 15)));
+        assert_eq!(get_first_kept(&messages, &tokens, 50), Some((0, 45)));
+    }
+}
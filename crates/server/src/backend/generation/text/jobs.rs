@@ -0,0 +1,336 @@
+use crate::backend::{
+    chats::messages::{MESSAGE_TABLE, insert_function_message, is_own_message},
+    errors::ServerError,
+    generation::text::{Resolved, context::keep_own_ids, generate, resolve},
+    query::Query,
+    user::get_user_id,
+    with_user_session,
+};
+use axum::{
+    Json,
+    extract::Path,
+    http::{HeaderMap, header::AUTHORIZATION},
+    response::IntoResponse,
+};
+use axum_streams::StreamBodyAs;
+use futures::{Stream, StreamExt};
+use ochat_types::{
+    chats::messages::Role,
+    generation::text::{ChatQueryData, ChatResponse, ChatStreamResult, TextJob, TextJobData},
+    surreal::Datetime,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::{
+    Notify,
+    broadcast::{self, error::RecvError},
+};
+
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+const JOB_CAPACITY: usize = 1024;
+/// How long a finished job is kept for clients that attach after it ended.
+const FINISHED_GRACE: Duration = Duration::from_secs(60);
+
+/// Generations keyed by the id of the message they write into, each only visible to the user who started it.
+/// Finished ones stay for `FINISHED_GRACE` so their outcome can still be replayed.
+static JOBS: LazyLock<Mutex<HashMap<String, Job>>> = LazyLock::new(Default::default);
+
+struct Job {
+    info: TextJob,
+    content: String,
+    thinking: String,
+    sender: broadcast::Sender<ChatStreamResult>,
+    cancel: Arc<Notify>,
+    /// The answer or error the job ended with.
+    result: Option<ChatStreamResult>,
+    finished: bool,
+}
+
+impl Job {
+    fn partial(&self) -> Option<ChatResponse> {
+        if self.content.is_empty() && self.thinking.is_empty() {
+            return None;
+        }
+
+        Some(ChatResponse {
+            role: Role::AI,
+            content: self.content.clone(),
+            thinking: if self.thinking.is_empty() {
+                None
+            } else {
+                Some(self.thinking.clone())
+            },
+            func_calls: Vec::new(),
+            metrics: None,
+        })
+    }
+}
+
+pub async fn start_job(
+    headers: HeaderMap,
+    Json(mut data): Json<TextJobData>,
+) -> Result<Json<TextJob>, ServerError> {
+    let message = data.message.trim().to_string();
+    let user_id = get_user_id().await?;
+    if !is_own_message(&message).await? {
+        return Err(ServerError::NotFound(format!(
+            "Message '{}' does not exist.",
+            message
+        )));
+    }
+
+    // The job runs once this request is done, so it takes what it reads along and signs in
+    // again with the caller's token for what it writes.
+    let mut session = HeaderMap::new();
+    if let Some(x) = headers.get(AUTHORIZATION) {
+        session.insert(AUTHORIZATION, x.clone());
+    }
+
+    keep_own_ids(&mut data.query).await;
+    let resolved = Resolved {
+        session: Some(session.clone()),
+        ..resolve(&data.query).await?
+    };
+
+    let info = TextJob {
+        user_id: user_id.clone(),
+        message: message.clone(),
+        provider: data.query.provider.clone(),
+        model: data.query.model.clone(),
+        started: Datetime::default(),
+    };
+    let cancel = Arc::new(Notify::new());
+
+    {
+        let mut jobs = JOBS.lock().unwrap();
+        if jobs.get(&message).is_some_and(|x| !x.finished) {
+            return Err(ServerError::Conflict(format!(
+                "A generation is already running for message '{}'.",
+                message
+            )));
+        }
+
+        jobs.insert(
+            message.clone(),
+            Job {
+                info: info.clone(),
+                content: String::new(),
+                thinking: String::new(),
+                sender: broadcast::channel(JOB_CAPACITY).0,
+                cancel: cancel.clone(),
+                result: None,
+                finished: false,
+            },
+        );
+    }
+
+    tokio::spawn(run_job(message, data.query, resolved, session, cancel));
+
+    Ok(Json(info))
+}
+
+async fn run_job(
+    id: String,
+    query: ChatQueryData,
+    resolved: Resolved,
+    session: HeaderMap,
+    cancel: Arc<Notify>,
+) {
+    // Fitting the history can mean summarizing it first, which is worth being able to cancel too.
+    let mut stream = tokio::select! {
+        x = generate(query, resolved) => x,
+        _ = cancel.notified() => futures::stream::empty().boxed(),
+    };
+    let mut last_flush = Instant::now();
+    let mut saved = false;
+
+    loop {
+        let result = tokio::select! {
+            x = stream.next() => x,
+            _ = cancel.notified() => None,
+        };
+
+        let Some(result) = result else {
+            break;
+        };
+
+        match result {
+            ChatStreamResult::Finished => break,
+            ChatStreamResult::Generated(response) => {
+                saved = with_user_session(&session, save_response(&id, &response))
+                    .await
+                    .is_ok();
+                // Link the calls into the tree before listeners hear the answer is done.
+                if !response.func_calls.is_empty() {
+                    let _ = with_user_session(
+                        &session,
+                        insert_function_message(&id, response.func_calls.clone()),
+                    )
+                    .await;
+                }
+                send(&id, ChatStreamResult::Generated(response));
+            }
+            ChatStreamResult::Generating(response) => {
+                let partial = {
+                    let mut jobs = JOBS.lock().unwrap();
+                    let Some(job) = jobs.get_mut(&id) else {
+                        break;
+                    };
+                    job.content.push_str(&response.content);
+                    if let Some(thinking) = &response.thinking {
+                        job.thinking.push_str(thinking);
+                    }
+                    let _ = job.sender.send(ChatStreamResult::Generating(response));
+
+                    if last_flush.elapsed() >= FLUSH_INTERVAL {
+                        job.partial()
+                    } else {
+                        None
+                    }
+                };
+
+                if let Some(partial) = partial {
+                    let _ = with_user_session(&session, save_response(&id, &partial)).await;
+                    last_flush = Instant::now();
+                }
+            }
+            result => send(&id, result),
+        }
+    }
+
+    // Dropping the stream closes its channel, which stops the upstream request.
+    drop(stream);
+
+    let (partial, sender) = {
+        let mut jobs = JOBS.lock().unwrap();
+        let Some(job) = jobs.get_mut(&id) else {
+            return;
+        };
+        job.finished = true;
+        (job.partial(), job.sender.clone())
+    };
+
+    if !saved && let Some(partial) = partial {
+        let _ = with_user_session(&session, save_response(&id, &partial)).await;
+    }
+
+    let _ = sender.send(ChatStreamResult::Finished);
+
+    tokio::time::sleep(FINISHED_GRACE).await;
+    let mut jobs = JOBS.lock().unwrap();
+    // The message may have been generated again in the meantime.
+    if jobs.get(&id).is_some_and(|x| x.finished) {
+        jobs.remove(&id);
+    }
+}
+
+fn send(id: &str, result: ChatStreamResult) {
+    if let Some(job) = JOBS.lock().unwrap().get_mut(id) {
+        if matches!(
+            result,
+            ChatStreamResult::Generated(_) | ChatStreamResult::Err(_)
+        ) {
+            job.result = Some(result.clone());
+        }
+        let _ = job.sender.send(result);
+    }
+}
+
+async fn save_response(id: &str, response: &ChatResponse) -> Result<(), ServerError> {
+    let sql = if response.metrics.is_some() {
+        "UPDATE type::thing($tb, $id) SET content = $content, thinking = $thinking, metrics = $metrics;"
+    } else {
+        "UPDATE type::thing($tb, $id) SET content = $content, thinking = $thinking;"
+    };
+
+    Query::new(sql)
+        .record(MESSAGE_TABLE, id)
+        .bind_str("content", &response.content)
+        .bind("thinking", response.thinking.clone())
+        .bind("metrics", response.metrics.clone())
+        .execute()
+        .await
+}
+
+pub async fn list_jobs() -> Result<Json<Vec<TextJob>>, ServerError> {
+    let user_id = get_user_id().await?;
+    let mut jobs: Vec<TextJob> = JOBS
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|x| !x.finished && x.info.user_id == user_id)
+        .map(|x| x.info.clone())
+        .collect();
+    jobs.sort_by(|a, b| a.started.cmp(&b.started));
+
+    Ok(Json(jobs))
+}
+
+pub async fn cancel_job(id: Path<String>) -> Result<Json<TextJob>, ServerError> {
+    let user_id = get_user_id().await?;
+    let jobs = JOBS.lock().unwrap();
+    let Some(job) = jobs
+        .get(id.trim())
+        .filter(|x| !x.finished && x.info.user_id == user_id)
+    else {
+        return Err(job_not_found(&id));
+    };
+    job.cancel.notify_one();
+
+    Ok(Json(job.info.clone()))
+}
+
+/// Replays what the job has generated so far and then follows it until it finishes,
+/// a job that already finished only replays how it ended.
+fn attach(
+    id: &str,
+    user_id: &str,
+) -> Result<impl Stream<Item = ChatStreamResult> + use<>, ServerError> {
+    let jobs = JOBS.lock().unwrap();
+    let Some(job) = jobs.get(id).filter(|x| x.info.user_id == user_id) else {
+        return Err(job_not_found(id));
+    };
+
+    let snapshot = match &job.result {
+        Some(x) => Some(x.clone()),
+        None => job.partial().map(ChatStreamResult::Generating),
+    };
+
+    if job.finished {
+        return Ok(
+            futures::stream::iter(snapshot.into_iter().chain([ChatStreamResult::Finished]))
+                .left_stream(),
+        );
+    }
+
+    let updates = futures::stream::unfold(Some(job.sender.subscribe()), |receiver| async move {
+        let mut receiver = receiver?;
+        loop {
+            match receiver.recv().await {
+                Ok(ChatStreamResult::Finished) => return Some((ChatStreamResult::Finished, None)),
+                Ok(x) => return Some((x, Some(receiver))),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(futures::stream::iter(snapshot)
+        .chain(updates)
+        .right_stream())
+}
+
+pub async fn attach_job(id: Path<String>) -> Result<impl IntoResponse, ServerError> {
+    let user_id = get_user_id().await?;
+    Ok(StreamBodyAs::json_nl(attach(id.trim(), &user_id)?))
+}
+
+fn job_not_found(id: &str) -> ServerError {
+    ServerError::NotFound(format!(
+        "No generation is running for message '{}'.",
+        id.trim()
+    ))
+}
@@ -1,12 +1,17 @@
 use crate::backend::{
-    errors::ServerError, generation::text::split_text_into_thinking,
+    errors::ServerError,
+    generation::text::{
+        Resolved,
+        options::{Backend, MappedOptions, Param, map_options},
+        split_text_into_thinking,
+    },
+    metrics::{GenerationTimer, LoadedModel, StreamGuard},
     providers::hf::pull::get_models_dir,
 };
-use axum::Json;
 use futures::Stream;
 use mistralrs::{
-    DefaultSchedulerMethod, GgufModelBuilder, Model, ModelDType, ResponseOk, SchedulerConfig,
-    VisionMessages, best_device,
+    DefaultSchedulerMethod, GgufModelBuilder, Model, ModelDType, RequestBuilder, ResponseOk,
+    SchedulerConfig, StopTokens, VisionMessages, best_device,
     core::{
         AdapterPaths, AutoLoaderBuilder, EmbeddingSpecificConfig, LocalModelPaths,
         MistralRsBuilder, ModelPaths, NormalSpecificConfig, VisionSpecificConfig,
@@ -15,9 +20,16 @@ use mistralrs::{
 
 use ochat_types::{
     chats::messages::Role,
-    generation::text::{ChatQueryData, ChatQueryMessage, ChatResponse, ChatStreamResult},
+    generation::text::{
+        ChatQueryData, ChatQueryMessage, ChatResponse, ChatStreamResult, GenerationMetrics,
+    },
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
 };
-use std::{fs, path::PathBuf, thread, time::Duration};
 
 pub async fn get_model_dir_and_name(data: &ChatQueryData) -> (PathBuf, String) {
     let (user, model, name) = {
@@ -39,9 +51,25 @@ pub async fn get_model_dir_and_name(data: &ChatQueryData) -> (PathBuf, String) {
     (model_dir, name)
 }
 
-pub async fn get_model(data: &ChatQueryData) -> Result<Model, ServerError> {
-    let (path, name) = get_model_dir_and_name(data).await;
+pub async fn get_model(
+    data: &ChatQueryData,
+    resolved: &Resolved,
+) -> Result<LoadedModel<Model>, ServerError> {
+    let Some((path, name)) = &resolved.model_dir else {
+        return Err(ServerError::NotFound(format!(
+            "Model '{}' is not a local model.",
+            data.model.trim()
+        )));
+    };
+
+    Ok(LoadedModel::new(
+        load_model(data, path, name).await?,
+        data.provider.trim(),
+        data.model.trim(),
+    ))
+}
 
+async fn load_model(data: &ChatQueryData, path: &Path, name: &str) -> Result<Model, ServerError> {
     let get_file_if_exists = |path: PathBuf| -> Option<PathBuf> {
         if fs::exists(&path).unwrap_or_default() {
             Some(path)
@@ -98,7 +126,7 @@ pub async fn get_model(data: &ChatQueryData) -> Result<Model, ServerError> {
         }
     }
 
-    let explicit = get_file_if_exists(path.join(&name));
+    let explicit = get_file_if_exists(path.join(name));
     if let Some(explicit) = explicit {
         if !weight_files.contains(&explicit) {
             weight_files.push(explicit);
@@ -202,6 +230,17 @@ pub fn get_messages_from_chat_query(
     msgs = msgs.enable_thinking(true);
 
     for message in messages {
+        let text = if message.func_calls.is_empty() {
+            message.text
+        } else {
+            message
+                .func_calls
+                .iter()
+                .map(|x| format!("{}: {}", x.name, x.result_text()))
+                .collect::<Vec<String>>()
+                .join("\n\n")
+        };
+
         msgs = msgs.add_message(
             match message.role {
                 Role::User => mistralrs::TextMessageRole::User,
@@ -209,20 +248,44 @@ pub fn get_messages_from_chat_query(
                 Role::AI => mistralrs::TextMessageRole::Assistant,
                 Role::System => mistralrs::TextMessageRole::System,
             },
-            message.text,
+            text,
         );
     }
 
     Ok(msgs)
 }
 
-pub async fn run(data: ChatQueryData) -> Result<Json<ChatResponse>, ServerError> {
-    let model = get_model(&data).await?;
+/// Builds the request for the query with its sampling options applied.
+async fn get_request(
+    data: &ChatQueryData,
+    resolved: &Resolved,
+) -> Result<(RequestBuilder, MappedOptions), String> {
+    let options = map_options(Backend::MistralRs, resolved.options.clone());
+    let mut request = RequestBuilder::from(get_messages_from_chat_query(data.messages.clone())?)
+        .enable_thinking(true);
+
+    for param in options.params.iter() {
+        request = match param {
+            Param::Temperature(x) => request.set_sampler_temperature(*x),
+            Param::TopK(x) => request.set_sampler_topk(*x),
+            Param::TopP(x) => request.set_sampler_topp(*x),
+            Param::MaxTokens(x) => request.set_sampler_max_len(*x),
+            Param::Stop(x) => request.set_sampler_stop_toks(StopTokens::Seqs(x.clone())),
+            Param::Seed(_) | Param::Server => request,
+        };
+    }
+
+    Ok((request, options))
+}
+
+pub async fn run(data: ChatQueryData, resolved: &Resolved) -> Result<ChatResponse, ServerError> {
+    let model = get_model(&data, resolved).await?;
+    let (request, options) = get_request(&data, resolved)
+        .await
+        .map_err(ServerError::Unknown)?;
 
     let response = model
-        .send_chat_request(
-            get_messages_from_chat_query(data.messages).map_err(ServerError::Unknown)?,
-        )
+        .send_chat_request(request)
         .await
         .map_err(|e| ServerError::Unknown(e.to_string()))?;
 
@@ -236,7 +299,7 @@ pub async fn run(data: ChatQueryData) -> Result<Json<ChatResponse>, ServerError>
 
     let (content, thinking2) = split_text_into_thinking(content);
 
-    Ok(Json(ChatResponse {
+    Ok(ChatResponse {
         role: Role::AI,
         content,
         thinking: if thinking.is_empty() {
@@ -248,34 +311,51 @@ pub async fn run(data: ChatQueryData) -> Result<Json<ChatResponse>, ServerError>
             Some(thinking)
         },
         func_calls: Vec::new(),
-    }))
+        metrics: Some(GenerationMetrics {
+            prompt_tokens: Some(response.usage.prompt_tokens as u64),
+            completion_tokens: Some(response.usage.completion_tokens as u64),
+            finish_reason: response.choices.first().map(|x| x.finish_reason.clone()),
+            options: options.applied,
+            unsupported_options: options.unsupported,
+            ..Default::default()
+        }),
+    })
 }
 
-pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult> {
+pub async fn stream(
+    data: ChatQueryData,
+    resolved: Resolved,
+) -> impl Stream<Item = ChatStreamResult> {
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
     tokio::spawn(async move {
-        let model = match get_model(&data).await {
+        let _guard = StreamGuard::start();
+        let mut timer = GenerationTimer::new(&data.provider, &data.model);
+
+        let model = match get_model(&data, &resolved).await {
             Ok(m) => m,
             Err(e) => {
+                timer.fail();
                 let _ = tx.send(ChatStreamResult::Err(e.to_string()));
                 let _ = tx.send(ChatStreamResult::Finished);
                 return;
             }
         };
 
-        let msgs = match get_messages_from_chat_query(data.messages) {
-            Ok(m) => m,
+        let (request, options) = match get_request(&data, &resolved).await {
+            Ok(x) => x,
             Err(e) => {
+                timer.fail();
                 let _ = tx.send(ChatStreamResult::Err(e));
                 let _ = tx.send(ChatStreamResult::Finished);
                 return;
             }
         };
 
-        let mut response = match model.stream_chat_request(msgs).await {
+        let mut response = match model.stream_chat_request(request).await {
             Ok(x) => x,
             Err(e) => {
+                timer.fail();
                 let _ = tx.send(ChatStreamResult::Err(e.to_string()));
                 let _ = tx.send(ChatStreamResult::Finished);
                 return;
@@ -283,7 +363,15 @@ pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult>
         };
         let mut content = String::new();
         let mut thinking = String::new();
-        while let Some(response) = response.next().await {
+        let mut metrics = GenerationMetrics {
+            options: options.applied,
+            unsupported_options: options.unsupported,
+            ..Default::default()
+        };
+        while let Some(response) = tokio::select! {
+            x = response.next() => x,
+            _ = tx.closed() => None,
+        } {
             match response.as_result() {
                 Ok(response) => {
                     let mut temp = String::new();
@@ -296,7 +384,11 @@ pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult>
                                 temp_thinking.push_str(
                                     &choice.message.reasoning_content.clone().unwrap_or_default(),
                                 );
+                                metrics.finish_reason = Some(choice.finish_reason.clone());
                             }
+                            metrics.prompt_tokens = Some(response.usage.prompt_tokens as u64);
+                            metrics.completion_tokens =
+                                Some(response.usage.completion_tokens as u64);
                         }
                         ResponseOk::Chunk(response) => {
                             for choice in response.choices.iter() {
@@ -304,6 +396,13 @@ pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult>
                                 temp_thinking.push_str(
                                     &choice.delta.reasoning_content.clone().unwrap_or_default(),
                                 );
+                                if let Some(reason) = &choice.finish_reason {
+                                    metrics.finish_reason = Some(reason.clone());
+                                }
+                            }
+                            if let Some(usage) = &response.usage {
+                                metrics.prompt_tokens = Some(usage.prompt_tokens as u64);
+                                metrics.completion_tokens = Some(usage.completion_tokens as u64);
                             }
                         }
                         ResponseOk::CompletionChunk(response) => {
@@ -321,6 +420,7 @@ pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult>
 
                     content.push_str(&temp);
                     thinking.push_str(&temp_thinking);
+                    timer.chunk();
 
                     let _ = tx.send(ChatStreamResult::Generating(ChatResponse {
                         role: Role::AI,
@@ -331,13 +431,17 @@ pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult>
                             Some(temp_thinking)
                         },
                         func_calls: Vec::new(),
+                        metrics: None,
                     }));
                 }
                 Err(e) => {
+                    metrics.finish_reason = Some(String::from("error"));
                     let _ = tx.send(ChatStreamResult::Err(e.to_string()));
                 }
             }
         }
+        timer.finish(&mut metrics);
+
         let (content, thinking2) = split_text_into_thinking(content);
         let _ = tx.send(ChatStreamResult::Generated(ChatResponse {
             role: Role::AI,
@@ -351,6 +455,7 @@ pub async fn stream(data: ChatQueryData) -> impl Stream<Item = ChatStreamResult>
                 Some(thinking)
             },
             func_calls: Vec::new(),
+            metrics: Some(metrics),
         }));
 
         thread::sleep(Duration::from_millis(20));
@@ -1,29 +1,210 @@
-use crate::backend::errors::ServerError;
-use axum::{Json, response::IntoResponse};
+use crate::backend::{
+    CONN,
+    errors::ServerError,
+    files::get_file,
+    metrics::GenerationTimer,
+    options::{get_gen_options, relationships::get_default_gen_options_from_model},
+    providers::PROVIDER_TABLE,
+    settings::get_settings,
+    with_user_session,
+};
+use axum::{Json, extract::Path, http::HeaderMap, response::IntoResponse};
 use axum_streams::StreamBodyAs;
-use ochat_types::generation::text::{ChatQueryData, ChatResponse};
+use futures::{StreamExt, stream::BoxStream};
+use ochat_types::{
+    files::B64File,
+    generation::text::{ChatQueryData, ChatResponse, ChatStreamResult},
+    options::GenOptions,
+    providers::Provider,
+    settings::Settings,
+};
+use std::{collections::HashMap, path::PathBuf};
 
 pub mod api;
+pub mod context;
+pub mod jobs;
 pub mod mistralrs;
+pub mod options;
 
-#[axum::debug_handler]
-pub async fn run(Json(data): Json<ChatQueryData>) -> Result<Json<ChatResponse>, ServerError> {
-    if data.provider.starts_with("HF") {
-        mistralrs::run(data).await
+/// The generation options picked for the query, falling back to the model's defaults.
+pub(crate) async fn get_query_options(query: &ChatQueryData) -> Option<GenOptions> {
+    let options = match &query.options {
+        Some(id) => get_gen_options(Path(id.clone())).await,
+        None => {
+            get_default_gen_options_from_model(Path((query.provider.clone(), query.model.clone())))
+                .await
+        }
+    };
+
+    options.ok().and_then(|x| x.0)
+}
+
+/// Everything a generation reads from the database, looked up while the request still holds
+/// the session so the generation does not depend on it once the request is done.
+#[derive(Clone)]
+pub struct Resolved {
+    pub settings: Settings,
+    pub options: Option<GenOptions>,
+    /// The API provider, local models have their directory and name instead.
+    pub provider: Option<Provider>,
+    pub model_dir: Option<(PathBuf, String)>,
+    /// The embeddings provider and model picking the tools, when the query can use any.
+    pub embeddings: Option<(Provider, String)>,
+    /// The files of the messages keyed by their id.
+    pub files: HashMap<String, B64File>,
+    /// Set when generating outlives the request, its database calls sign in again with it.
+    pub session: Option<HeaderMap>,
+}
+
+impl Resolved {
+    pub async fn with_session<T>(
+        &self,
+        task: impl Future<Output = Result<T, ServerError>>,
+    ) -> Result<T, ServerError> {
+        match &self.session {
+            Some(headers) => with_user_session(headers, task).await,
+            None => task.await,
+        }
+    }
+}
+
+async fn get_provider(id: &str) -> Result<Option<Provider>, ServerError> {
+    Ok(CONN.select((PROVIDER_TABLE, id.trim())).await?)
+}
+
+pub(crate) async fn resolve(query: &ChatQueryData) -> Result<Resolved, ServerError> {
+    let settings = get_settings().await?.0;
+
+    let (provider, model_dir) = if query.provider.starts_with("HF") {
+        (None, Some(mistralrs::get_model_dir_and_name(query).await))
     } else {
-        api::run(data).await
+        let Some(provider) = get_provider(&query.provider).await? else {
+            return Err(ServerError::NotFound(format!(
+                "Provider '{}' does not exist.",
+                query.provider.trim()
+            )));
+        };
+        (Some(provider), None)
+    };
+
+    let embeddings = match &settings.embeddings_provider {
+        Some(x) if !query.force_disable_tools && !query.tools.is_empty() => {
+            get_provider(&x.provider)
+                .await?
+                .map(|provider| (provider, x.model.clone()))
+        }
+        _ => None,
+    };
+
+    let mut files = HashMap::new();
+    for id in query.messages.iter().flat_map(|x| x.files.iter()) {
+        if let Ok(Json(Some(file))) = get_file(Path(id.clone())).await {
+            files.insert(id.clone(), file);
+        }
     }
+
+    Ok(Resolved {
+        options: get_query_options(query).await,
+        settings,
+        provider,
+        model_dir,
+        embeddings,
+        files,
+        session: None,
+    })
 }
 
 #[axum::debug_handler]
-pub async fn stream(Json(data): Json<ChatQueryData>) -> impl IntoResponse {
-    if data.provider.starts_with("HF") {
-        StreamBodyAs::json_nl(mistralrs::stream(data).await)
+pub async fn run(Json(mut data): Json<ChatQueryData>) -> Result<Json<ChatResponse>, ServerError> {
+    let timer = GenerationTimer::new(&data.provider, &data.model);
+    context::keep_own_ids(&mut data).await;
+
+    let resolved = match resolve(&data).await {
+        Ok(x) => x,
+        Err(e) => {
+            timer.fail();
+            return Err(e);
+        }
+    };
+
+    let trim = match context::fit_to_context(&mut data, &resolved).await {
+        Ok(x) => x,
+        Err(e) => {
+            timer.fail();
+            return Err(e);
+        }
+    };
+
+    let result = if data.provider.starts_with("HF") {
+        mistralrs::run(data, &resolved).await
     } else {
-        StreamBodyAs::json_nl(api::stream(data).await)
+        api::run(data, &resolved).await
+    };
+
+    match result {
+        Ok(mut response) => {
+            let mut metrics = response.metrics.take().unwrap_or_default();
+            metrics.context = trim;
+            timer.finish(&mut metrics);
+            response.metrics = Some(metrics);
+            Ok(Json(response))
+        }
+        Err(e) => {
+            timer.fail();
+            Err(e)
+        }
     }
 }
 
+#[axum::debug_handler]
+pub async fn stream(Json(mut data): Json<ChatQueryData>) -> impl IntoResponse {
+    context::keep_own_ids(&mut data).await;
+
+    let stream = match resolve(&data).await {
+        Ok(resolved) => generate(data, resolved).await,
+        Err(e) => failed(e),
+    };
+
+    StreamBodyAs::json_nl(stream)
+}
+
+fn failed(e: ServerError) -> BoxStream<'static, ChatStreamResult> {
+    futures::stream::iter([
+        ChatStreamResult::Err(e.to_string()),
+        ChatStreamResult::Finished,
+    ])
+    .boxed()
+}
+
+/// Fits the history into the model's context and streams the answer from the matching backend.
+pub(crate) async fn generate(
+    mut data: ChatQueryData,
+    resolved: Resolved,
+) -> BoxStream<'static, ChatStreamResult> {
+    let trim = match context::fit_to_context(&mut data, &resolved).await {
+        Ok(x) => x,
+        Err(e) => return failed(e),
+    };
+
+    let stream = if data.provider.starts_with("HF") {
+        mistralrs::stream(data, resolved).await.boxed()
+    } else {
+        api::stream(data, resolved).await.boxed()
+    };
+
+    stream
+        .map(move |x| match x {
+            ChatStreamResult::Generated(mut response) => {
+                if let Some(metrics) = &mut response.metrics {
+                    metrics.context = trim.clone();
+                }
+                ChatStreamResult::Generated(response)
+            }
+            x => x,
+        })
+        .boxed()
+}
+
 pub fn split_text_into_thinking(text: String) -> (String, Option<String>) {
     let text = text
         .trim()
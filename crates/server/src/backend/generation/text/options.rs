@@ -0,0 +1,209 @@
+use crate::backend::providers::generic_rig::TOP_LEVEL_PARAMS;
+use ochat_types::{
+    options::{GenOption, GenOptionKey, GenOptionValue, GenOptions},
+    providers::ProviderType,
+};
+use serde_json::{Value, json};
+
+/// The backends generation options get translated for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Reached through its OpenAI compatible `chat/completions`, which ignores Ollama's own `options`.
+    Ollama,
+    /// Any OpenAI compatible API, Gemini included.
+    OpenAI,
+    MistralRs,
+}
+
+impl From<&ProviderType> for Backend {
+    fn from(value: &ProviderType) -> Self {
+        match value {
+            ProviderType::Ollama => Self::Ollama,
+            ProviderType::OpenAI | ProviderType::Gemini => Self::OpenAI,
+        }
+    }
+}
+
+/// A generation option in the form a backend takes it.
+#[derive(Clone, Debug)]
+pub enum Param {
+    Temperature(f64),
+    TopK(usize),
+    TopP(f64),
+    MaxTokens(usize),
+    Stop(Vec<String>),
+    Seed(i64),
+    /// Not sent anywhere, the server uses it itself to fit the history into the context.
+    Server,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MappedOptions {
+    pub params: Vec<Param>,
+    pub applied: Vec<GenOption>,
+    pub unsupported: Vec<GenOptionKey>,
+}
+
+impl MappedOptions {
+    /// The additional params of the API backends, the standard chat completion fields that go
+    /// at the top of the body.
+    pub fn additional_params(&self) -> Option<Value> {
+        let mut top_level = serde_json::Map::new();
+
+        for param in self.params.iter() {
+            let (key, value) = match param {
+                Param::TopP(x) => ("top_p", json!(x)),
+                Param::Stop(x) => ("stop", json!(x)),
+                Param::Seed(x) => ("seed", json!(x)),
+                // Temperature and max tokens are set through the agent.
+                _ => continue,
+            };
+            top_level.insert(key.to_string(), value);
+        }
+
+        if top_level.is_empty() {
+            None
+        } else {
+            Some(json!({ TOP_LEVEL_PARAMS: top_level }))
+        }
+    }
+}
+
+pub fn map_options(backend: Backend, options: Option<GenOptions>) -> MappedOptions {
+    let mut mapped = MappedOptions::default();
+
+    for option in options.into_iter().flat_map(|x| x.data) {
+        if !option.activated {
+            continue;
+        }
+
+        match map_option(backend, &option) {
+            Some(param) => {
+                mapped.params.push(param);
+                mapped.applied.push(option);
+            }
+            None => mapped.unsupported.push(option.key),
+        }
+    }
+
+    mapped
+}
+
+fn map_option(backend: Backend, option: &GenOption) -> Option<Param> {
+    let value = &option.value;
+
+    match option.key {
+        GenOptionKey::Temperature => Some(Param::Temperature(value.as_f32() as f64)),
+        GenOptionKey::TopP => Some(Param::TopP(value.as_f32() as f64)),
+        GenOptionKey::TopK => match backend {
+            Backend::MistralRs => Some(Param::TopK(value.as_i32().max(0) as usize)),
+            _ => None,
+        },
+        // Only Ollama's own API takes -1 and -2 here.
+        GenOptionKey::NumberPredict => {
+            (value.as_i32() > 0).then(|| Param::MaxTokens(value.as_i32() as usize))
+        }
+        GenOptionKey::StopSequence => {
+            // Older options stored a number here, which is no sequence to stop at.
+            let GenOptionValue::Text(x) = value else {
+                return None;
+            };
+            let stop: Vec<String> = x
+                .split(',')
+                .map(|x| x.trim().to_string())
+                .filter(|x| !x.is_empty())
+                .collect();
+
+            (!stop.is_empty()).then_some(Param::Stop(stop))
+        }
+        GenOptionKey::Seed => match backend {
+            // mistralrs seeds its sampler once when the model is loaded, a request cannot set it.
+            Backend::MistralRs => None,
+            _ => Some(Param::Seed(value.as_i32() as i64)),
+        },
+        GenOptionKey::CtxWindow => Some(Param::Server),
+        // Neither the OpenAI API nor mistralrs' `RequestBuilder` has this multiplicative penalty,
+        // their frequency and presence penalties are additive and would change what it means.
+        // The rest only exist in Ollama's `options`, which `chat/completions` does not read.
+        GenOptionKey::RepeatPenalty
+        | GenOptionKey::RepeatN
+        | GenOptionKey::Mirostat
+        | GenOptionKey::MirostatETA
+        | GenOptionKey::MirostatTau
+        | GenOptionKey::NumGQA
+        | GenOptionKey::GPULayers
+        | GenOptionKey::NumThreads
+        | GenOptionKey::TailFreeZ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied_keys(backend: Backend) -> Vec<GenOptionKey> {
+        GenOption::get_all()
+            .into_iter()
+            .map(|mut x| {
+                if x.key == GenOptionKey::StopSequence {
+                    x.value = GenOptionValue::Text("END".to_string());
+                }
+                x
+            })
+            .filter(|x| map_option(backend, x).is_some())
+            .map(|x| x.key)
+            .collect()
+    }
+
+    #[test]
+    fn maps_only_what_each_backend_reads() {
+        let completions = vec![
+            GenOptionKey::CtxWindow,
+            GenOptionKey::Temperature,
+            GenOptionKey::Seed,
+            GenOptionKey::StopSequence,
+            GenOptionKey::NumberPredict,
+            GenOptionKey::TopP,
+        ];
+
+        assert_eq!(applied_keys(Backend::OpenAI), completions);
+        assert_eq!(applied_keys(Backend::Ollama), completions);
+        assert_eq!(
+            applied_keys(Backend::MistralRs),
+            vec![
+                GenOptionKey::CtxWindow,
+                GenOptionKey::Temperature,
+                GenOptionKey::StopSequence,
+                GenOptionKey::NumberPredict,
+                GenOptionKey::TopK,
+                GenOptionKey::TopP,
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_ollama_only_predict_values_unsupported() {
+        let option = GenOption::new(GenOptionKey::NumberPredict, GenOptionValue::Int(-1));
+        assert!(map_option(Backend::Ollama, &option).is_none());
+    }
+
+    #[test]
+    fn sends_completion_params_at_the_top_level() {
+        let mapped = MappedOptions {
+            params: vec![
+                Param::Temperature(0.5),
+                Param::TopP(0.5),
+                Param::Seed(7),
+                Param::Stop(vec!["END".to_string()]),
+                Param::Server,
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            mapped.additional_params(),
+            Some(json!({ TOP_LEVEL_PARAMS: { "top_p": 0.5, "seed": 7, "stop": ["END"] } }))
+        );
+        assert_eq!(MappedOptions::default().additional_params(), None);
+    }
+}
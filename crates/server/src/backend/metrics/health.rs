@@ -0,0 +1,130 @@
+use crate::backend::{
+    CONN, TABLES,
+    metrics::METRICS,
+    providers::{list_all_providers, provider_into_reqwest},
+    query::Query,
+};
+use axum::{Json, http::StatusCode};
+use futures::future::join_all;
+use ochat_types::{
+    health::{HealthCheck, HealthState, HealthStatus},
+    providers::Provider,
+};
+use std::{sync::OnceLock, time::Duration};
+
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Whether every table was there once the server defined them. Record users cannot run
+/// `INFO FOR DB`, so it is checked once with the session the server starts with.
+static TABLES_CHECK: OnceLock<Result<(), String>> = OnceLock::new();
+
+fn status(checks: Vec<HealthCheck>) -> (StatusCode, Json<HealthStatus>) {
+    let state = if checks
+        .iter()
+        .any(|x| x.required && x.state != HealthState::Ok)
+    {
+        HealthState::Unavailable
+    } else if checks.iter().any(|x| x.state != HealthState::Ok) {
+        HealthState::Degraded
+    } else {
+        HealthState::Ok
+    };
+
+    (
+        if state == HealthState::Unavailable {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::OK
+        },
+        Json(HealthStatus {
+            state,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime: METRICS.uptime(),
+            checks,
+        }),
+    )
+}
+
+fn check(name: &str, required: bool, result: Result<(), String>) -> HealthCheck {
+    HealthCheck {
+        name: name.to_string(),
+        state: if result.is_ok() {
+            HealthState::Ok
+        } else if required {
+            HealthState::Unavailable
+        } else {
+            HealthState::Degraded
+        },
+        required,
+        message: result.err(),
+    }
+}
+
+async fn find_missing_tables() -> Result<(), String> {
+    let defined: Vec<String> = Query::new("RETURN object::keys((INFO FOR DB).tables);")
+        .take()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let missing: Vec<&str> = TABLES
+        .iter()
+        .filter(|x| !defined.iter().any(|y| y == *x))
+        .copied()
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Missing tables : {}", missing.join(", ")))
+    }
+}
+
+/// Checks the tables have been defined, for `ready` to report from then on.
+pub(crate) async fn check_tables() {
+    let _ = TABLES_CHECK.set(find_missing_tables().await);
+}
+
+async fn check_provider(provider: Provider) -> HealthCheck {
+    let result = match provider_into_reqwest(&provider)
+        .timeout(PROVIDER_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client
+            .get(format!(
+                "{}/models",
+                provider.url.trim().trim_end_matches('/')
+            ))
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    check(&format!("provider:{}", provider.name), false, result)
+}
+
+/// Liveness, answers as long as the server can handle requests.
+pub async fn health() -> (StatusCode, Json<HealthStatus>) {
+    status(Vec::new())
+}
+
+/// Readiness, checks the database and tables are usable and which providers can be reached.
+pub async fn ready() -> (StatusCode, Json<HealthStatus>) {
+    let database = CONN.health().await.map_err(|e| e.to_string());
+    let mut checks = vec![check("database", true, database.clone())];
+
+    if database.is_ok() {
+        let tables = TABLES_CHECK
+            .get()
+            .cloned()
+            .unwrap_or_else(|| Err(String::from("The tables have not been checked yet.")));
+        checks.push(check("tables", true, tables));
+
+        if let Ok(providers) = list_all_providers().await {
+            checks.append(&mut join_all(providers.0.into_iter().map(check_provider)).await);
+        }
+    }
+
+    status(checks)
+}
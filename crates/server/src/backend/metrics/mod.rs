@@ -0,0 +1,458 @@
+pub mod health;
+pub mod route;
+
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::{Request, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ochat_types::generation::text::GenerationMetrics;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    ops::Deref,
+    sync::{
+        LazyLock, Mutex,
+        atomic::{AtomicI64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// Upper bounds, in seconds, of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct RequestStats {
+    count: u64,
+    sum: f64,
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+#[derive(Default)]
+struct GenerationStats {
+    count: u64,
+    errors: u64,
+    tokens: u64,
+    seconds: f64,
+    tokens_per_second: f64,
+}
+
+#[derive(Default)]
+struct PullStats {
+    completed: u64,
+    total: u64,
+}
+
+pub struct Metrics {
+    started: Instant,
+    requests: Mutex<BTreeMap<(String, String, u16), RequestStats>>,
+    generations: Mutex<BTreeMap<(String, String), GenerationStats>>,
+    pulls: Mutex<BTreeMap<(&'static str, String), PullStats>>,
+    loaded_models: Mutex<BTreeMap<(String, String), i64>>,
+    active_streams: AtomicI64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            requests: Mutex::default(),
+            generations: Mutex::default(),
+            pulls: Mutex::default(),
+            loaded_models: Mutex::default(),
+            active_streams: AtomicI64::default(),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn uptime(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    pub fn record_request(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let mut requests = self.requests.lock().unwrap();
+        let stats = requests
+            .entry((method.to_string(), route.to_string(), status))
+            .or_default();
+
+        stats.count += 1;
+        stats.sum += seconds;
+
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if seconds <= *bound {
+                stats.buckets[i] += 1;
+            }
+        }
+    }
+
+    pub fn record_generation(&self, provider: &str, model: &str, tokens: u64, seconds: f64) {
+        let mut generations = self.generations.lock().unwrap();
+        let stats = generations
+            .entry((provider.to_string(), model.to_string()))
+            .or_default();
+
+        stats.count += 1;
+        stats.tokens += tokens;
+        stats.seconds += seconds;
+
+        if seconds > 0.0 {
+            stats.tokens_per_second = tokens as f64 / seconds;
+        }
+    }
+
+    pub fn record_generation_error(&self, provider: &str, model: &str) {
+        self.generations
+            .lock()
+            .unwrap()
+            .entry((provider.to_string(), model.to_string()))
+            .or_default()
+            .errors += 1;
+    }
+
+    pub fn set_pull_progress(&self, kind: &'static str, model: &str, completed: u64, total: u64) {
+        let _ = self
+            .pulls
+            .lock()
+            .unwrap()
+            .insert((kind, model.to_string()), PullStats { completed, total });
+    }
+
+    pub fn finish_pull(&self, kind: &'static str, model: &str) {
+        let _ = self
+            .pulls
+            .lock()
+            .unwrap()
+            .remove(&(kind, model.to_string()));
+    }
+
+    fn add_loaded_model(&self, provider: &str, model: &str, value: i64) {
+        let mut models = self.loaded_models.lock().unwrap();
+        let key = (provider.to_string(), model.to_string());
+        let count = models.entry(key.clone()).or_default();
+        *count += value;
+
+        if *count <= 0 {
+            let _ = models.remove(&key);
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "# HELP ochat_uptime_seconds Seconds since the server started.\n# TYPE ochat_uptime_seconds gauge\nochat_uptime_seconds {}",
+            self.uptime()
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP ochat_http_requests_total Handled HTTP requests.\n# TYPE ochat_http_requests_total counter"
+        );
+        let requests = self.requests.lock().unwrap();
+        for ((method, route, status), stats) in requests.iter() {
+            let _ = writeln!(
+                out,
+                "ochat_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                method,
+                escape(route),
+                status,
+                stats.count
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP ochat_http_request_duration_seconds HTTP request latency.\n# TYPE ochat_http_request_duration_seconds histogram"
+        );
+        for ((method, route, status), stats) in requests.iter() {
+            let labels = format!(
+                "method=\"{}\",route=\"{}\",status=\"{}\"",
+                method,
+                escape(route),
+                status
+            );
+
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets.iter()) {
+                let _ = writeln!(
+                    out,
+                    "ochat_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, count
+                );
+            }
+
+            let _ = writeln!(
+                out,
+                "ochat_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}\nochat_http_request_duration_seconds_sum{{{}}} {}\nochat_http_request_duration_seconds_count{{{}}} {}",
+                labels, stats.count, labels, stats.sum, labels, stats.count
+            );
+        }
+        drop(requests);
+
+        let generations = self.generations.lock().unwrap();
+        for (name, kind, help, value) in [
+            (
+                "ochat_generations_total",
+                "counter",
+                "Completed text generations.",
+                (|x: &GenerationStats| x.count as f64) as fn(&GenerationStats) -> f64,
+            ),
+            (
+                "ochat_generation_errors_total",
+                "counter",
+                "Failed text generations.",
+                |x| x.errors as f64,
+            ),
+            (
+                "ochat_generation_tokens_total",
+                "counter",
+                "Generated completion tokens.",
+                |x| x.tokens as f64,
+            ),
+            (
+                "ochat_generation_seconds_total",
+                "counter",
+                "Time spent generating text.",
+                |x| x.seconds,
+            ),
+            (
+                "ochat_generation_tokens_per_second",
+                "gauge",
+                "Tokens per second of the latest generation.",
+                |x| x.tokens_per_second,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP {0} {1}\n# TYPE {0} {2}", name, help, kind);
+            for ((provider, model), stats) in generations.iter() {
+                let _ = writeln!(
+                    out,
+                    "{}{{provider=\"{}\",model=\"{}\"}} {}",
+                    name,
+                    escape(provider),
+                    escape(model),
+                    value(stats)
+                );
+            }
+        }
+        drop(generations);
+
+        let _ = writeln!(
+            out,
+            "# HELP ochat_active_streams Text generation streams currently open.\n# TYPE ochat_active_streams gauge\nochat_active_streams {}",
+            self.active_streams.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP ochat_pull_completed Progress of running model pulls.\n# TYPE ochat_pull_completed gauge"
+        );
+        let pulls = self.pulls.lock().unwrap();
+        for ((kind, model), stats) in pulls.iter() {
+            let _ = writeln!(
+                out,
+                "ochat_pull_completed{{source=\"{}\",model=\"{}\"}} {}",
+                kind,
+                escape(model),
+                stats.completed
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP ochat_pull_total Size of running model pulls.\n# TYPE ochat_pull_total gauge"
+        );
+        for ((kind, model), stats) in pulls.iter() {
+            let _ = writeln!(
+                out,
+                "ochat_pull_total{{source=\"{}\",model=\"{}\"}} {}",
+                kind,
+                escape(model),
+                stats.total
+            );
+        }
+        drop(pulls);
+
+        let _ = writeln!(
+            out,
+            "# HELP ochat_loaded_models Local models currently loaded in memory.\n# TYPE ochat_loaded_models gauge"
+        );
+        for ((provider, model), count) in self.loaded_models.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "ochat_loaded_models{{provider=\"{}\",model=\"{}\"}} {}",
+                escape(provider),
+                escape(model),
+                count
+            );
+        }
+
+        out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Times a single text generation and records it once finished.
+pub struct GenerationTimer {
+    provider: String,
+    model: String,
+    start: Instant,
+    first_token: Option<Duration>,
+    tokens: u64,
+}
+
+impl GenerationTimer {
+    pub fn new(provider: &str, model: &str) -> Self {
+        Self {
+            provider: provider.trim().to_string(),
+            model: model.trim().to_string(),
+            start: Instant::now(),
+            first_token: None,
+            tokens: 0,
+        }
+    }
+
+    /// Counts a streamed chunk as a generated token.
+    pub fn chunk(&mut self) {
+        if self.first_token.is_none() {
+            self.first_token = Some(self.start.elapsed());
+        }
+        self.tokens += 1;
+    }
+
+    /// Records the generation and fills in the timings of `metrics`, counting the streamed chunks
+    /// as tokens when the provider did not report any.
+    pub fn finish(self, metrics: &mut GenerationMetrics) {
+        let elapsed = self.start.elapsed();
+        let tokens = metrics.completion_tokens.unwrap_or(self.tokens);
+
+        METRICS.record_generation(&self.provider, &self.model, tokens, elapsed.as_secs_f64());
+
+        metrics.completion_tokens = Some(tokens);
+        metrics.time_to_first_token = self.first_token.map(|x| x.as_millis() as u64);
+        metrics.duration = elapsed.as_millis() as u64;
+        metrics.tokens_per_second = if elapsed.as_secs_f64() > 0.0 {
+            Some(tokens as f64 / elapsed.as_secs_f64())
+        } else {
+            None
+        };
+    }
+
+    pub fn fail(self) {
+        METRICS.record_generation_error(&self.provider, &self.model);
+    }
+}
+
+/// Counts an open generation stream until dropped.
+pub struct StreamGuard;
+
+impl StreamGuard {
+    pub fn start() -> Self {
+        let _ = METRICS.active_streams.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        let _ = METRICS.active_streams.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Reports a model pull's progress and clears it once dropped.
+pub struct PullGuard {
+    kind: &'static str,
+    model: String,
+}
+
+impl PullGuard {
+    pub fn new(kind: &'static str, model: &str) -> Self {
+        METRICS.set_pull_progress(kind, model, 0, 0);
+        Self {
+            kind,
+            model: model.to_string(),
+        }
+    }
+
+    pub fn progress(&self, completed: u64, total: u64) {
+        METRICS.set_pull_progress(self.kind, &self.model, completed, total);
+    }
+}
+
+impl Drop for PullGuard {
+    fn drop(&mut self) {
+        METRICS.finish_pull(self.kind, &self.model);
+    }
+}
+
+/// Counts a local model as loaded for as long as it is held.
+pub struct LoadedModel<T> {
+    model: T,
+    provider: String,
+    name: String,
+}
+
+impl<T> LoadedModel<T> {
+    pub fn new(model: T, provider: &str, name: &str) -> Self {
+        METRICS.add_loaded_model(provider, name, 1);
+        Self {
+            model,
+            provider: provider.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+impl<T> Deref for LoadedModel<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.model
+    }
+}
+
+impl<T> Drop for LoadedModel<T> {
+    fn drop(&mut self) {
+        METRICS.add_loaded_model(&self.provider, &self.name, -1);
+    }
+}
+
+pub async fn track_requests(req: Request<Body>, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|x| x.as_str().to_string())
+        .unwrap_or(String::from("unmatched"));
+
+    let response = next.run(req).await;
+
+    METRICS.record_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        start.elapsed().as_secs_f64(),
+    );
+
+    response
+}
+
+pub async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        METRICS.render(),
+    )
+}
@@ -0,0 +1,12 @@
+use crate::backend::metrics;
+use axum::{Router, routing::get};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/health", get(metrics::health::health))
+        .route("/ready", get(metrics::health::ready))
+}
+
+pub fn root_routes() -> Router {
+    Router::new().route("/metrics", get(metrics::metrics))
+}
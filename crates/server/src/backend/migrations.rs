@@ -0,0 +1,223 @@
+use crate::backend::{
+    CONN, chats::messages::backfill_message_roots, errors::ServerError, query::Query,
+};
+use clap::ValueEnum;
+use futures::{FutureExt, future::BoxFuture};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const MIGRATIONS_TABLE: &str = "migrations";
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+    /// Data changes too involved for a query, run before `sql` is applied.
+    pub task: Option<fn() -> BoxFuture<'static, Result<(), ServerError>>>,
+}
+
+/// Ordered schema changes, each applied once inside its own transaction.
+/// Never edit a released step, add a new one with the next version instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "unbounded_gen_options_data",
+        sql: "DEFINE FIELD OVERWRITE data ON TABLE gen_options TYPE array<object>;",
+        task: None,
+    },
+    Migration {
+        version: 2,
+        name: "message_search_index",
+        sql: "
+DEFINE ANALYZER IF NOT EXISTS messages_analyzer TOKENIZERS class, blank FILTERS lowercase, ascii;
+DEFINE INDEX IF NOT EXISTS content_index ON TABLE messages COLUMNS content SEARCH ANALYZER messages_analyzer BM25;
+DEFINE INDEX IF NOT EXISTS thinking_index ON TABLE messages COLUMNS thinking SEARCH ANALYZER messages_analyzer BM25;
+",
+        task: None,
+    },
+    Migration {
+        version: 3,
+        name: "message_tree_root",
+        sql: "
+DEFINE FIELD IF NOT EXISTS root ON TABLE messages TYPE option<string> VALUE $value ?? $before;
+DEFINE INDEX IF NOT EXISTS root_index ON TABLE messages COLUMNS root;
+",
+        task: None,
+    },
+    Migration {
+        version: 4,
+        name: "trash",
+        sql: "
+DEFINE FIELD IF NOT EXISTS deleted_at ON TABLE chats TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS deleted_at ON TABLE folders TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS deleted_at ON TABLE prompts TYPE option<string>;
+",
+        task: None,
+    },
+    Migration {
+        version: 5,
+        name: "chat_config",
+        sql: "
+DEFINE FIELD IF NOT EXISTS models ON TABLE chats TYPE array<object> DEFAULT [];
+DEFINE FIELD IF NOT EXISTS models[*].provider ON TABLE chats TYPE string;
+DEFINE FIELD IF NOT EXISTS models[*].model ON TABLE chats TYPE string;
+DEFINE FIELD IF NOT EXISTS tools ON TABLE chats TYPE array<string> DEFAULT [];
+DEFINE FIELD IF NOT EXISTS system_prompt ON TABLE chats TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS options ON TABLE chats TYPE option<string>;
+",
+        task: None,
+    },
+    Migration {
+        version: 6,
+        name: "message_root_backfill",
+        sql: "",
+        task: Some(|| backfill_message_roots().boxed()),
+    },
+];
+
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MigrationAction {
+    Status,
+    DryRun,
+    Run,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct AppliedMigration {
+    version: u32,
+    name: String,
+    time: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: String,
+    pub applied: Option<String>,
+}
+
+pub async fn define_migrations() -> Result<(), ServerError> {
+    let _ = CONN
+        .query(&format!(
+            "
+DEFINE TABLE IF NOT EXISTS {0} SCHEMAFULL PERMISSIONS NONE;
+DEFINE FIELD IF NOT EXISTS version ON TABLE {0} TYPE int;
+DEFINE FIELD IF NOT EXISTS name ON TABLE {0} TYPE string;
+DEFINE FIELD IF NOT EXISTS time ON TABLE {0} TYPE string DEFAULT <string>time::now();
+",
+            MIGRATIONS_TABLE
+        ))
+        .await?;
+    Ok(())
+}
+
+pub async fn get_schema_version() -> Result<u32, ServerError> {
+    let version: Option<u32> =
+        Query::new("RETURN math::max((SELECT VALUE version FROM type::table($tb))) ?? 0;")
+            .table(MIGRATIONS_TABLE)
+            .take()
+            .await?;
+
+    Ok(version.unwrap_or(0))
+}
+
+pub async fn get_migration_status() -> Result<Vec<MigrationStatus>, ServerError> {
+    let applied: Vec<AppliedMigration> = CONN.select(MIGRATIONS_TABLE).await?;
+
+    Ok(MIGRATIONS
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            name: migration.name.to_string(),
+            applied: applied
+                .iter()
+                .find(|x| x.version == migration.version)
+                .map(|x| x.time.clone()),
+        })
+        .collect())
+}
+
+pub async fn run_migrations() -> Result<Vec<MigrationStatus>, ServerError> {
+    for status in get_migration_status()
+        .await?
+        .into_iter()
+        .filter(|x| x.applied.is_none())
+    {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|x| x.version == status.version)
+            .unwrap();
+        let failed = |e: ServerError| {
+            ServerError::Unknown(format!(
+                "Migration {} '{}' failed : {}",
+                migration.version, migration.name, e
+            ))
+        };
+
+        if let Some(task) = migration.task {
+            task().await.map_err(failed)?;
+        }
+
+        Query::new(format!(
+            "BEGIN TRANSACTION;\n{}\nCREATE type::thing($tb, $version) SET version = $version, name = $name;\nCOMMIT TRANSACTION;",
+            migration.sql
+        ))
+        .table(MIGRATIONS_TABLE)
+        .bind("version", migration.version)
+        .bind_str("name", migration.name)
+        .execute()
+        .await
+        .map_err(failed)?;
+    }
+
+    get_migration_status().await
+}
+
+fn print_migrations(statuses: &[MigrationStatus]) {
+    for status in statuses {
+        match &status.applied {
+            Some(time) => println!(
+                "[x] {:04} {} (applied {})",
+                status.version, status.name, time
+            ),
+            None => println!("[ ] {:04} {}", status.version, status.name),
+        }
+    }
+}
+
+pub async fn run_migration_action(action: &MigrationAction) -> Result<(), ServerError> {
+    match action {
+        MigrationAction::Status => {
+            println!("Schema version : {}", get_schema_version().await?);
+            print_migrations(&get_migration_status().await?);
+        }
+        MigrationAction::DryRun => {
+            let pending = get_migration_status()
+                .await?
+                .into_iter()
+                .filter(|x| x.applied.is_none())
+                .collect::<Vec<_>>();
+
+            if pending.is_empty() {
+                println!("The schema is up to date.");
+            }
+
+            for status in pending {
+                let migration = MIGRATIONS
+                    .iter()
+                    .find(|x| x.version == status.version)
+                    .unwrap();
+                println!("-- {:04} {}", migration.version, migration.name);
+                if migration.task.is_some() {
+                    println!("-- Updates existing records from the server.");
+                }
+                println!("{}\n", migration.sql);
+            }
+        }
+        MigrationAction::Run => {
+            print_migrations(&run_migrations().await?);
+            println!("Schema version : {}", get_schema_version().await?);
+        }
+    }
+
+    Ok(())
+}
@@ -1,71 +1,157 @@
+pub mod assistants;
 pub mod chats;
+pub mod config;
 pub mod errors;
 pub mod files;
 pub mod folders;
+pub mod gc;
 pub mod generation;
+pub mod metrics;
+pub mod migrations;
+pub mod openapi;
 pub mod options;
 pub mod prompts;
 pub mod providers;
+pub mod query;
 pub mod settings;
 pub mod tools;
+pub mod trash;
 pub mod user;
 pub mod utils;
 
 use crate::backend::{
-    chats::{define_chats, previews::define_previews},
+    assistants::{ASSISTANTS_TABLE, define_assistants},
+    chats::{
+        CHAT_TABLE, define_chats,
+        previews::{PREVIEW_TABLE, define_previews},
+    },
+    config::{DatabaseEngine, ServerConfig, get_config, set_config},
     errors::ServerError,
-    files::define_files,
-    folders::define_folders,
-    messages::define_messages,
-    options::{define_gen_options, relationships::define_gen_models},
-    prompts::define_prompts,
+    files::{EMBEDDINGS_TABLE, FILE_TABLE, define_files},
+    folders::{FOLDER_TABLE, define_folders},
+    messages::{MESSAGE_TABLE, define_messages},
+    metrics::{METRICS, health::check_tables, track_requests},
+    migrations::{
+        MIGRATIONS_TABLE, MigrationAction, define_migrations, run_migration_action, run_migrations,
+    },
+    options::{
+        GEN_OPTIONS_TABLE, define_gen_options,
+        relationships::{GEN_MODELS_TABLE, define_gen_models},
+    },
+    prompts::{PROMPTS_TABLE, define_prompts},
     providers::{
-        add_default_providers, define_providers,
-        ollama::models::{add_all_ollama_models, define_ollama_models},
+        PROVIDER_TABLE, add_default_providers, define_providers,
+        ollama::models::{OLLAMA_MODELS_TABLE, add_all_ollama_models, define_ollama_models},
+    },
+    settings::{SETTINGS_TABLE, apply_config_settings, define_settings},
+    user::{
+        AUTH_TABLE, USER_TABLE, authenticate, define_users, require_admin,
+        sessions::{SESSIONS_TABLE, define_sessions},
+        tokens::{API_TOKENS_TABLE, check_api_token_scope, define_api_tokens},
     },
-    settings::define_settings,
-    user::{authenticate, define_users},
-    utils::get_path_settings,
 };
-use axum::{Router, body::Body, middleware};
+use axum::{Router, body::Body, extract::DefaultBodyLimit, http::HeaderMap, middleware};
+use axum_server::tls_rustls::RustlsConfig;
 use chats::messages;
 use clap::Parser;
 use ochat_types::WORD_ART;
-use std::sync::LazyLock;
-use surrealdb::{
-    Surreal,
-    engine::local::{Db, RocksDb},
-};
+use std::{sync::LazyLock, time::Duration};
+use surrealdb::{Surreal, engine::any::Any, opt::auth::Root};
+use tokio::sync::RwLock;
+
+static CONN: LazyLock<Surreal<Any>> = LazyLock::new(Surreal::init);
 
-static CONN: LazyLock<Surreal<Db>> = LazyLock::new(Surreal::init);
-const DATABASE: &str = "test";
-const NAMESPACE: &str = "test";
+/// Every request holds this from signing in until it has its response, maintenance holds it
+/// alone while it needs a session that sees every user's records. The embedded stores only
+/// allow one connection, so the session of `CONN` is the only one there is.
+static SESSION: LazyLock<RwLock<()>> = LazyLock::new(Default::default);
+
+/// Every table `define_tables` is expected to create.
+pub(crate) const TABLES: &[&str] = &[
+    PROVIDER_TABLE,
+    SETTINGS_TABLE,
+    MESSAGE_TABLE,
+    CHAT_TABLE,
+    OLLAMA_MODELS_TABLE,
+    PREVIEW_TABLE,
+    FILE_TABLE,
+    EMBEDDINGS_TABLE,
+    PROMPTS_TABLE,
+    ASSISTANTS_TABLE,
+    GEN_OPTIONS_TABLE,
+    GEN_MODELS_TABLE,
+    USER_TABLE,
+    AUTH_TABLE,
+    API_TOKENS_TABLE,
+    SESSIONS_TABLE,
+    FOLDER_TABLE,
+    MIGRATIONS_TABLE,
+];
 
 #[derive(Parser, Debug, Clone)]
 #[command(version, about, long_about = None)]
 pub struct Arguments {
     #[arg(short, long)]
     url: Option<String>,
+    #[arg(short, long)]
+    config: Option<String>,
+    #[arg(long)]
+    namespace: Option<String>,
+    #[arg(long)]
+    database: Option<String>,
+    #[arg(long, value_enum)]
+    engine: Option<DatabaseEngine>,
+    #[arg(long)]
+    data_dir: Option<String>,
+    #[arg(long)]
+    db_url: Option<String>,
+    #[arg(long, value_enum)]
+    migrations: Option<MigrationAction>,
 }
 
 pub async fn start_server<F: FnOnce(String) -> Router>(router_fn: F) {
     let args = Arguments::parse();
+    set_config(ServerConfig::load(&args).unwrap());
+    LazyLock::force(&METRICS);
+
+    if let Some(action) = &args.migrations {
+        if let Err(e) = run_migrations_command(action).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     init_db().await.unwrap();
-    let api = Router::new().merge(user::route::auth_routes());
+    let config = get_config();
+
+    if let Some(hours) = config.gc_interval.filter(|x| *x > 0) {
+        let _ = tokio::spawn(gc::run_periodic_gc(Duration::from_secs(hours * 60 * 60)));
+    }
+
+    let api = Router::new()
+        .merge(user::route::auth_routes())
+        .merge(metrics::route::routes())
+        .route_layer(middleware::from_fn(hold_session));
 
     let api_protected = Router::new()
         .merge(user::route::routes())
+        .merge(user::route::admin_routes())
         .merge(chats::route::routes())
         .merge(files::route::routes())
         .merge(generation::route::routes())
         .merge(options::route::routes())
         .merge(prompts::route::routes())
+        .merge(assistants::route::routes())
         .merge(providers::route::routes())
         .merge(settings::route::routes())
         .merge(folders::route::routes())
-        .route_layer(middleware::from_fn(guard));
+        .merge(trash::route::routes())
+        .route_layer(middleware::from_fn(guard))
+        // Takes the system session itself, so it cannot hold on to a user's.
+        .merge(gc::route::routes());
 
-    let mut url = args.url.unwrap_or("localhost:1212".to_string());
+    let mut url = config.bind.clone().unwrap_or("localhost:1212".to_string());
 
     if url.is_empty() {
         url = "localhost:1212".to_string();
@@ -74,33 +160,153 @@ pub async fn start_server<F: FnOnce(String) -> Router>(router_fn: F) {
     url = url.replace("localhost", "127.0.0.1");
     url = url.trim_end_matches("/api").to_string();
 
-    let app = router_fn(url.clone()).nest("/api", Router::new().merge(api).merge(api_protected));
+    let mut api = Router::new().merge(api).merge(api_protected);
+
+    if let Some(limit) = config.body_limit {
+        api = api.layer(DefaultBodyLimit::max(limit));
+    }
+
+    let mut app = router_fn(url.clone())
+        .nest("/api", api)
+        .merge(metrics::route::root_routes())
+        .layer(middleware::from_fn(track_requests));
+
+    if !config.cors.origins.is_empty() {
+        app = app.layer(config.cors.layer());
+    }
 
     println!("{}", WORD_ART);
-    println!("Starting server at '{}'.", url);
 
-    let listener = tokio::net::TcpListener::bind(url).await.unwrap();
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
+    if let Some(tls) = &config.tls {
+        let tls = RustlsConfig::from_pem_file(&tls.cert, &tls.key)
+            .await
+            .unwrap();
+        let addr = tokio::net::lookup_host(&url).await.unwrap().next().unwrap();
+
+        println!("Starting server at 'https://{}'.", url);
+
+        axum_server::bind_rustls(addr, tls)
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    } else {
+        println!("Starting server at '{}'.", url);
+
+        let listener = tokio::net::TcpListener::bind(url).await.unwrap();
+        axum::serve(listener, app.into_make_service())
+            .await
+            .unwrap();
+    }
 }
 
 pub async fn guard(
     req: axum::http::Request<Body>,
     next: axum::middleware::Next,
 ) -> Result<axum::response::Response, ServerError> {
-    authenticate(req.headers()).await?;
+    let _session = SESSION.read().await;
+    if let Some(scope) = authenticate(req.headers()).await? {
+        check_api_token_scope(&scope, req.method(), req.uri())?;
+    }
     Ok(next.run(req).await)
 }
 
+pub async fn hold_session(
+    req: axum::http::Request<Body>,
+    next: axum::middleware::Next,
+) -> Result<axum::response::Response, ServerError> {
+    let _session = SESSION.read().await;
+    Ok(next.run(req).await)
+}
+
+/// Checks the caller is an admin and lets go of their session before the handler runs.
+pub async fn system_guard(
+    req: axum::http::Request<Body>,
+    next: axum::middleware::Next,
+) -> Result<axum::response::Response, ServerError> {
+    {
+        let _session = SESSION.read().await;
+        if let Some(scope) = authenticate(req.headers()).await? {
+            check_api_token_scope(&scope, req.method(), req.uri())?;
+        }
+        let _ = require_admin().await?;
+    }
+    Ok(next.run(req).await)
+}
+
+pub async fn admin_guard(
+    req: axum::http::Request<Body>,
+    next: axum::middleware::Next,
+) -> Result<axum::response::Response, ServerError> {
+    let _ = require_admin().await?;
+    Ok(next.run(req).await)
+}
+
+/// Runs `--migrations` against the database without starting the server.
+async fn run_migrations_command(action: &MigrationAction) -> Result<(), ServerError> {
+    connect_db().await.map_err(|e| {
+        ServerError::Unknown(format!(
+            "Could not open the database, a running server may be holding it : {}",
+            e
+        ))
+    })?;
+    set_db().await?;
+
+    if action == &MigrationAction::Run {
+        define_tables().await?;
+    }
+
+    run_migration_action(action).await
+}
+
 pub async fn connect_db() -> Result<(), ServerError> {
-    CONN.connect::<RocksDb>(&get_path_settings("database".to_string()))
-        .await?;
+    let config = &get_config().database;
+    CONN.connect(config.endpoint()?).await?;
+
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        CONN.signin(Root { username, password }).await?;
+    }
+
     Ok(())
 }
 
+/// Runs `task` signed in again as the user of `headers`, for work that carries on after its
+/// request has let go of the session.
+pub async fn with_user_session<T>(
+    headers: &HeaderMap,
+    task: impl Future<Output = Result<T, ServerError>>,
+) -> Result<T, ServerError> {
+    let _session = SESSION.read().await;
+    let _ = authenticate(headers).await?;
+    task.await
+}
+
+/// Runs `task` with a session that sees every user's records once no request is in flight.
+/// The session is dropped again afterwards, so the next request has to sign in first.
+pub async fn with_system_session<T>(
+    task: impl Future<Output = Result<T, ServerError>>,
+) -> Result<T, ServerError> {
+    let _session = SESSION.write().await;
+    let config = &get_config().database;
+    CONN.invalidate().await?;
+
+    let result = async {
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            CONN.signin(Root { username, password }).await?;
+        }
+        set_db().await?;
+        task.await
+    }
+    .await;
+
+    CONN.invalidate().await?;
+    result
+}
+
 pub async fn set_db() -> Result<(), ServerError> {
-    CONN.use_ns(NAMESPACE).use_db(DATABASE).await?;
+    let config = &get_config().database;
+    CONN.use_ns(&config.namespace)
+        .use_db(&config.database)
+        .await?;
     Ok(())
 }
 
@@ -108,7 +314,10 @@ pub async fn init_db() -> Result<(), ServerError> {
     connect_db().await?;
     set_db().await?;
     define_tables().await?;
-    define_starting_data().await
+    let _ = run_migrations().await?;
+    check_tables().await;
+    define_starting_data().await?;
+    apply_config_settings().await
 }
 
 pub async fn define_tables() -> Result<(), ServerError> {
@@ -123,11 +332,15 @@ pub async fn define_tables() -> Result<(), ServerError> {
         define_previews(),
         define_files(),
         define_prompts(),
+        define_assistants(),
         define_gen_options(),
         define_gen_models(),
         define_chats(),
         define_users(),
+        define_api_tokens(),
+        define_sessions(),
         define_folders(),
+        define_migrations(),
     ]?;
 
     Ok(())
@@ -0,0 +1,629 @@
+use crate::backend::errors::ServerError;
+use axum::Json;
+use ochat_types::{
+    ServerFeatures,
+    assistants::{Assistant, AssistantData},
+    chats::{
+        Chat, ChatConfig, ChatData,
+        export::{ChatExport, DpoRecord},
+        import::{ImportData, ImportResult},
+        messages::{Message, MessageData, MessageRating, MessageSearchResult},
+        previews::Preview,
+    },
+    errors::ErrorBody,
+    files::{B64File, B64FileData, DBFile},
+    folders::{Folder, FolderData, FolderNameData},
+    gc::GcReport,
+    generation::text::{ChatQueryData, ChatResponse, ChatStreamResult, TextJob, TextJobData},
+    health::HealthStatus,
+    options::{
+        GenOptions, GenOptionsData,
+        relationships::{GenModelRelationship, GenModelRelationshipData},
+    },
+    prompts::{Prompt, PromptData},
+    providers::{
+        Model, Provider, ProviderData,
+        hf::{DownloadedHFModels, HFModel, HFModelDetails, HFPullModelStreamResult},
+        ollama::{OllamaModelsInfo, OllamaPullModelStreamResult},
+    },
+    settings::{Settings, SettingsData, SettingsProvider},
+    trash::TrashItem,
+    user::{
+        ApiToken, ApiTokenData, ChangePasswordData, NewApiToken, Session, SigninData, SignupData,
+        Token, User,
+    },
+};
+use schemars::{JsonSchema, SchemaGenerator, generate::SchemaSettings};
+use serde_json::{Map, Value, json};
+use std::sync::LazyLock;
+
+const JSON: &str = "application/json";
+const NDJSON: &str = "application/x-ndjson";
+const PUBLIC_PATHS: [&str; 7] = [
+    "/version/",
+    "/features/",
+    "/signin/",
+    "/signup/",
+    "/openapi.json",
+    "/health",
+    "/ready",
+];
+
+static OPENAPI: LazyLock<Value> = LazyLock::new(|| {
+    let mut spec = Spec::default();
+    add_auth_operations(&mut spec);
+    add_user_operations(&mut spec);
+    add_chat_operations(&mut spec);
+    add_folder_operations(&mut spec);
+    add_file_operations(&mut spec);
+    add_prompt_operations(&mut spec);
+    add_assistant_operations(&mut spec);
+    add_trash_operations(&mut spec);
+    add_option_operations(&mut spec);
+    add_provider_operations(&mut spec);
+    add_settings_operations(&mut spec);
+    add_generation_operations(&mut spec);
+    spec.build()
+});
+
+struct Spec {
+    generator: SchemaGenerator,
+    paths: Map<String, Value>,
+    tag: &'static str,
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Self {
+            generator: SchemaSettings::draft2020_12()
+                .with(|x| {
+                    x.definitions_path = "/components/schemas".into();
+                    x.meta_schema = None;
+                })
+                .into_generator(),
+            paths: Map::new(),
+            tag: "",
+        }
+    }
+}
+
+impl Spec {
+    fn schema<T: JsonSchema>(&mut self) -> Value {
+        self.generator.subschema_for::<T>().to_value()
+    }
+
+    fn tag(&mut self, tag: &'static str) -> &mut Self {
+        self.tag = tag;
+        self
+    }
+
+    fn insert(
+        &mut self,
+        method: &str,
+        path: &str,
+        summary: &str,
+        body: Option<Value>,
+        response: Value,
+        content_type: &str,
+    ) -> &mut Self {
+        let error = self.schema::<ErrorBody>();
+        let mut operation = json!({
+            "tags": [self.tag],
+            "summary": summary,
+            "operationId": format!("{}{}", method, path.replace(['/', '{', '}', '.'], "_")),
+            "parameters": path
+                .split('/')
+                .filter_map(|x| x.strip_prefix('{').and_then(|x| x.strip_suffix('}')))
+                .map(|x| json!({
+                    "name": x,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" }
+                }))
+                .collect::<Vec<Value>>(),
+            "responses": {
+                "200": {
+                    "description": "Success",
+                    "content": { content_type: { "schema": response } }
+                },
+                "default": {
+                    "description": "Error",
+                    "content": { JSON: { "schema": error } }
+                }
+            }
+        });
+
+        if let Some(body) = body {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { JSON: { "schema": body } }
+            });
+        }
+
+        if PUBLIC_PATHS.contains(&path) {
+            operation["security"] = json!([]);
+        }
+
+        let item = self
+            .paths
+            .entry(path.to_string())
+            .or_insert_with(|| json!({}));
+        item[method] = operation;
+        self
+    }
+
+    fn route<R: JsonSchema>(&mut self, method: &str, path: &str, summary: &str) -> &mut Self {
+        let response = self.schema::<R>();
+        self.insert(method, path, summary, None, response, JSON)
+    }
+
+    fn route_with_body<B: JsonSchema, R: JsonSchema>(
+        &mut self,
+        method: &str,
+        path: &str,
+        summary: &str,
+    ) -> &mut Self {
+        let body = self.schema::<B>();
+        let response = self.schema::<R>();
+        self.insert(method, path, summary, Some(body), response, JSON)
+    }
+
+    fn stream<B: JsonSchema, R: JsonSchema>(
+        &mut self,
+        method: &str,
+        path: &str,
+        summary: &str,
+        with_body: bool,
+    ) -> &mut Self {
+        let body = with_body.then(|| self.schema::<B>());
+        let response = self.schema::<R>();
+        self.insert(method, path, summary, body, response, NDJSON)
+    }
+
+    fn build(mut self) -> Value {
+        json!({
+            "openapi": "3.1.0",
+            "info": {
+                "title": "ochat",
+                "description": "The REST API exposed by ochat-server.",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "servers": [{ "url": "/api" }],
+            "security": [{ "token": [] }],
+            "paths": self.paths,
+            "components": {
+                "securitySchemes": {
+                    "token": {
+                        "type": "apiKey",
+                        "in": "header",
+                        "name": "Authorization",
+                        "description": "A session jwt or an API token starting with 'ochat_'."
+                    }
+                },
+                "schemas": self.generator.take_definitions(true),
+            }
+        })
+    }
+}
+
+fn add_auth_operations(spec: &mut Spec) {
+    spec.tag("auth")
+        .route::<String>("get", "/version/", "Get the server version")
+        .route::<Vec<ServerFeatures>>("get", "/features/", "List the enabled server features")
+        .route_with_body::<SigninData, Token>("post", "/signin/", "Sign in")
+        .route_with_body::<SignupData, Token>("post", "/signup/", "Sign up")
+        .route::<Value>("get", "/openapi.json", "Get this document")
+        .tag("health")
+        .route::<HealthStatus>("get", "/health", "Check the server is alive")
+        .route::<HealthStatus>(
+            "get",
+            "/ready",
+            "Check the database, tables and providers are usable",
+        );
+}
+
+fn add_user_operations(spec: &mut Spec) {
+    spec.tag("user")
+        .route::<Option<User>>("get", "/user/", "Get the current user")
+        .route::<Option<User>>("get", "/user/{id}", "Get a user")
+        .route::<Option<Session>>("post", "/user/logout/", "Revoke the current session")
+        .route::<Token>("post", "/user/refresh/", "Refresh the current session")
+        .route_with_body::<ChangePasswordData, Option<User>>(
+            "put",
+            "/user/password/",
+            "Change the current user's password",
+        )
+        .route::<Vec<Session>>("get", "/user/session/all/", "List active sessions")
+        .route::<Option<Session>>("delete", "/user/session/{id}", "Revoke a session")
+        .route_with_body::<ApiTokenData, NewApiToken>("post", "/user/token/", "Create an API token")
+        .route::<Vec<ApiToken>>("get", "/user/token/all/", "List API tokens")
+        .route::<Option<ApiToken>>("delete", "/user/token/{id}", "Revoke an API token")
+        .tag("admin")
+        .route::<Vec<User>>("get", "/users/", "List all users")
+        .route_with_body::<SignupData, Option<User>>("post", "/users/", "Create a user")
+        .route::<Option<User>>("delete", "/users/{id}", "Delete a user")
+        .route::<Option<User>>("put", "/users/{id}/enable/", "Enable a user")
+        .route::<Option<User>>("put", "/users/{id}/disable/", "Disable a user")
+        .route::<Option<User>>("put", "/users/{id}/promote/", "Promote a user to admin")
+        .route::<Option<User>>("put", "/users/{id}/demote/", "Demote an admin")
+        .route::<GcReport>("get", "/gc/", "Report what garbage collection would remove")
+        .route::<GcReport>(
+            "post",
+            "/gc/",
+            "Remove orphaned messages, files and previews",
+        );
+}
+
+fn add_chat_operations(spec: &mut Spec) {
+    spec.tag("chats")
+        .route_with_body::<ChatData, Option<Chat>>("post", "/chat/", "Create a chat")
+        .route_with_body::<Vec<MessageData>, Option<Chat>>(
+            "post",
+            "/chat/branch/",
+            "Create a chat from a list of messages",
+        )
+        .route_with_body::<ImportData, ImportResult>(
+            "post",
+            "/chat/import/",
+            "Import chats from ChatGPT, Open WebUI or ochat exports",
+        )
+        .route::<Vec<Chat>>("get", "/chat/all/", "List all chats")
+        .route::<Option<Chat>>("get", "/chat/{id}", "Get a chat")
+        .route_with_body::<ChatData, Option<Chat>>("put", "/chat/{id}", "Update a chat")
+        .route::<Option<Chat>>("delete", "/chat/{id}", "Move a chat to the trash")
+        .route::<Option<Chat>>("put", "/chat/{id}/root/{root}", "Set a chat's root message")
+        .route::<Option<ChatConfig>>(
+            "get",
+            "/chat/{id}/config/",
+            "Get a chat's models, tools, system prompt and options",
+        )
+        .route_with_body::<ChatConfig, Option<Chat>>(
+            "put",
+            "/chat/{id}/config/",
+            "Set a chat's models, tools, system prompt and options",
+        )
+        .route::<ChatExport>(
+            "get",
+            "/chat/{id}/export/{format}",
+            "Export a chat as json, markdown or html",
+        )
+        .stream::<(), DpoRecord>(
+            "get",
+            "/chat/dataset/{format}",
+            "Export rated messages as a dpo or sft JSONL dataset",
+            false,
+        )
+        .tag("messages")
+        .route_with_body::<MessageData, Option<Message>>("post", "/message/", "Create a message")
+        .route_with_body::<MessageData, Option<Message>>(
+            "post",
+            "/message/parent/{parent}",
+            "Create a child message",
+        )
+        .route::<Vec<Message>>(
+            "get",
+            "/message/parent/{parent}",
+            "List the children of a message",
+        )
+        .route::<Vec<Message>>(
+            "get",
+            "/message/parent/{parent}/default/",
+            "List the default branch below a message",
+        )
+        .route::<Vec<Message>>(
+            "get",
+            "/message/parent/{id}/all/",
+            "List every message below a message",
+        )
+        .route::<Vec<Message>>("get", "/message/all/", "List all messages")
+        .route::<Vec<MessageSearchResult>>(
+            "get",
+            "/message/search/{search}",
+            "Search the content and thinking of messages",
+        )
+        .route::<Option<Message>>("get", "/message/{id}", "Get a message")
+        .route_with_body::<MessageData, Option<Message>>("put", "/message/{id}", "Update a message")
+        .route::<Option<Message>>("delete", "/message/{id}", "Delete a message")
+        .route_with_body::<MessageRating, Option<Message>>(
+            "put",
+            "/message/{id}/rating/",
+            "Rate an AI message",
+        )
+        .route::<Option<Message>>(
+            "delete",
+            "/message/{id}/rating/",
+            "Remove a message's rating",
+        )
+        .tag("previews")
+        .route::<Vec<Preview>>("get", "/preview/all/", "List all chat previews")
+        .route::<Vec<Preview>>("get", "/preview/search/{search}", "Search chat previews")
+        .route::<Option<Preview>>("get", "/preview/{id}", "Get a chat preview")
+        .route::<Option<Preview>>("put", "/preview/{id}", "Regenerate a chat preview");
+}
+
+fn add_folder_operations(spec: &mut Spec) {
+    spec.tag("folders")
+        .route_with_body::<FolderData, Option<Folder>>("post", "/folder/", "Create a folder")
+        .route::<Vec<Folder>>("get", "/folder/all/", "List all folders")
+        .route::<Vec<Folder>>("get", "/folder/search/{search}", "Search folders")
+        .route::<Option<Folder>>("get", "/folder/{id}", "Get a folder")
+        .route_with_body::<FolderData, Option<Folder>>("put", "/folder/{id}", "Update a folder")
+        .route::<Option<Folder>>("delete", "/folder/{id}", "Move a folder to the trash")
+        .route::<Option<Folder>>(
+            "put",
+            "/folder/{id}/parent/{parent}",
+            "Move a folder into another folder",
+        )
+        .route::<Option<Folder>>(
+            "put",
+            "/folder/{id}/parent/none",
+            "Move a folder to the top level",
+        )
+        .route::<Option<Folder>>("put", "/folder/{id}/chat/{chat}", "Add a chat to a folder")
+        .route::<Option<Folder>>(
+            "delete",
+            "/folder/{id}/chat/{chat}",
+            "Remove a chat from a folder",
+        )
+        .route_with_body::<FolderNameData, Option<Folder>>(
+            "put",
+            "/folder/{id}/name/",
+            "Rename a folder",
+        )
+        .route::<Option<Folder>>("put", "/folder/archive/chat/{chat}", "Archive a chat")
+        .route::<Option<Folder>>("put", "/folder/fav/chat/{chat}", "Favourite a chat");
+}
+
+fn add_file_operations(spec: &mut Spec) {
+    spec.tag("files")
+        .route_with_body::<B64FileData, Option<DBFile>>("post", "/file/", "Upload a file")
+        .route::<Vec<DBFile>>("get", "/file/all/", "List all files")
+        .route::<Option<B64File>>("get", "/file/{id}", "Download a file")
+        .route_with_body::<B64FileData, Option<DBFile>>("put", "/file/{id}", "Replace a file")
+        .route::<Option<DBFile>>("delete", "/file/{id}", "Delete a file");
+}
+
+fn add_prompt_operations(spec: &mut Spec) {
+    spec.tag("prompts")
+        .route_with_body::<PromptData, Option<Prompt>>("post", "/prompt/", "Create a prompt")
+        .route::<Vec<Prompt>>("get", "/prompt/all/", "List all prompts")
+        .route::<Vec<Prompt>>("get", "/prompt/search/{search}", "Search prompts")
+        .route::<Option<Prompt>>("get", "/prompt/{id}", "Get a prompt")
+        .route_with_body::<PromptData, Option<Prompt>>("put", "/prompt/{id}", "Update a prompt")
+        .route::<Option<Prompt>>("delete", "/prompt/{id}", "Move a prompt to the trash");
+}
+
+fn add_assistant_operations(spec: &mut Spec) {
+    spec.tag("assistants")
+        .route_with_body::<AssistantData, Option<Assistant>>(
+            "post",
+            "/assistant/",
+            "Create an assistant",
+        )
+        .route::<Vec<Assistant>>("get", "/assistant/all/", "List all assistants")
+        .route::<Option<Assistant>>("get", "/assistant/{id}", "Get an assistant")
+        .route_with_body::<AssistantData, Option<Assistant>>(
+            "put",
+            "/assistant/{id}",
+            "Update an assistant",
+        )
+        .route::<Option<Assistant>>("delete", "/assistant/{id}", "Delete an assistant");
+}
+
+fn add_trash_operations(spec: &mut Spec) {
+    spec.tag("trash")
+        .route::<Vec<TrashItem>>("get", "/trash/", "List trashed chats, folders and prompts")
+        .route::<Vec<TrashItem>>("delete", "/trash/", "Empty the trash")
+        .route::<Option<TrashItem>>("put", "/trash/{kind}/{id}", "Restore a trashed item")
+        .route::<Option<TrashItem>>("delete", "/trash/{kind}/{id}", "Purge a trashed item");
+}
+
+fn add_option_operations(spec: &mut Spec) {
+    spec.tag("options")
+        .route_with_body::<GenOptionsData, Option<GenOptions>>(
+            "post",
+            "/option/",
+            "Create generation options",
+        )
+        .route::<Vec<GenOptions>>("get", "/option/all/", "List all generation options")
+        .route::<Vec<GenOptions>>(
+            "get",
+            "/option/search/{search}",
+            "Search generation options",
+        )
+        .route::<Option<GenOptions>>("get", "/option/{id}", "Get generation options")
+        .route_with_body::<GenOptionsData, Option<GenOptions>>(
+            "put",
+            "/option/{id}",
+            "Update generation options",
+        )
+        .route::<Option<GenOptions>>("delete", "/option/{id}", "Delete generation options")
+        .route::<Vec<SettingsProvider>>(
+            "get",
+            "/option/{id}/model/all/",
+            "List the models using generation options",
+        )
+        .route::<Vec<GenModelRelationship>>(
+            "get",
+            "/option/{id}/all/",
+            "List the model relationships of generation options",
+        )
+        .route_with_body::<GenModelRelationshipData, Option<GenModelRelationship>>(
+            "post",
+            "/option/relationship/",
+            "Link generation options to a model",
+        )
+        .route::<Vec<GenOptions>>(
+            "get",
+            "/option/relationship/all/",
+            "List all model relationships",
+        )
+        .route::<Option<GenModelRelationship>>(
+            "get",
+            "/option/relationship/{id}",
+            "Get a model relationship",
+        )
+        .route_with_body::<GenModelRelationshipData, Option<GenModelRelationship>>(
+            "put",
+            "/option/relationship/{id}",
+            "Update a model relationship",
+        )
+        .route::<Option<GenModelRelationship>>(
+            "delete",
+            "/option/relationship/{id}",
+            "Delete a model relationship",
+        );
+}
+
+fn add_provider_operations(spec: &mut Spec) {
+    spec.tag("providers")
+        .route_with_body::<ProviderData, Option<Provider>>("post", "/provider/", "Add a provider")
+        .route::<Vec<Provider>>("get", "/provider/all/", "List all providers")
+        .route::<Option<Provider>>("get", "/provider/{id}", "Get a provider")
+        .route_with_body::<ProviderData, Option<Provider>>(
+            "put",
+            "/provider/{id}",
+            "Update a provider",
+        )
+        .route::<Option<Provider>>("delete", "/provider/{id}", "Delete a provider")
+        .route::<Vec<Model>>(
+            "get",
+            "/provider/{id}/model/all/",
+            "List a provider's models",
+        )
+        .route::<Model>(
+            "get",
+            "/provider/{id}/model/{model}",
+            "Get a provider's model",
+        )
+        .route::<String>(
+            "delete",
+            "/provider/{id}/model/{model}",
+            "Delete a provider's model",
+        )
+        .stream::<(), OllamaPullModelStreamResult>(
+            "post",
+            "/provider/{id}/model/{model}",
+            "Pull an ollama model",
+            false,
+        )
+        .route::<Option<GenOptions>>(
+            "get",
+            "/provider/{id}/model/{model}/options/",
+            "Get a model's default generation options",
+        )
+        .route::<Vec<OllamaModelsInfo>>(
+            "get",
+            "/provider/ollama/model/all/",
+            "List the ollama library",
+        )
+        .route::<Vec<OllamaModelsInfo>>(
+            "get",
+            "/provider/ollama/model/search/{search}",
+            "Search the ollama library",
+        )
+        .route::<DownloadedHFModels>(
+            "get",
+            "/provider/hf/model/downloaded/",
+            "List all downloaded huggingface models",
+        );
+
+    for kind in ["text", "stt", "tts"] {
+        spec.route::<Vec<HFModel>>(
+            "get",
+            &format!("/provider/hf/{}/model/all/", kind),
+            &format!("List {} models on huggingface", kind),
+        )
+        .route::<Vec<HFModel>>(
+            "get",
+            &format!("/provider/hf/{}/model/search/{{search}}", kind),
+            &format!("Search {} models on huggingface", kind),
+        )
+        .route::<Vec<SettingsProvider>>(
+            "get",
+            &format!("/provider/hf/{}/model/downloaded/", kind),
+            &format!("List downloaded {} models", kind),
+        )
+        .route::<HFModelDetails>(
+            "get",
+            &format!("/provider/hf/{}/model/{{user}}/{{id}}", kind),
+            &format!("Get a huggingface {} model", kind),
+        )
+        .stream::<(), HFPullModelStreamResult>(
+            "post",
+            &format!("/provider/hf/{}/model/{{user}}/{{id}}/{{name}}", kind),
+            &format!("Pull a huggingface {} model", kind),
+            false,
+        );
+    }
+}
+
+fn add_settings_operations(spec: &mut Spec) {
+    spec.tag("settings")
+        .route::<Settings>("get", "/settings/", "Get the settings")
+        .route_with_body::<SettingsData, Option<Settings>>(
+            "put",
+            "/settings/",
+            "Update the settings",
+        )
+        .route::<Option<Settings>>("post", "/settings/reset/", "Reset the settings");
+}
+
+fn add_generation_operations(spec: &mut Spec) {
+    spec.tag("generation")
+        .route_with_body::<ChatQueryData, ChatResponse>(
+            "get",
+            "/generation/text/run/",
+            "Generate a chat response",
+        )
+        .stream::<ChatQueryData, ChatStreamResult>(
+            "get",
+            "/generation/text/stream/",
+            "Stream a chat response",
+            true,
+        )
+        .route_with_body::<TextJobData, TextJob>(
+            "post",
+            "/generation/text/job/",
+            "Start a generation that writes into a message",
+        )
+        .route::<Vec<TextJob>>(
+            "get",
+            "/generation/text/job/all/",
+            "List running generations",
+        )
+        .stream::<(), ChatStreamResult>(
+            "get",
+            "/generation/text/job/{id}",
+            "Attach to a running generation",
+            false,
+        )
+        .route::<TextJob>(
+            "delete",
+            "/generation/text/job/{id}",
+            "Cancel a running generation",
+        );
+
+    #[cfg(feature = "sound")]
+    {
+        use ochat_types::generation::{
+            stt::{SttQueryData, SttResponse},
+            tts::{TtsQueryData, TtsResponse},
+        };
+
+        spec.route_with_body::<TtsQueryData, TtsResponse>(
+            "get",
+            "/generation/tts/run/",
+            "Synthesise speech",
+        )
+        .route_with_body::<SttQueryData, SttResponse>(
+            "get",
+            "/generation/stt/run/",
+            "Transcribe speech",
+        );
+    }
+}
+
+pub async fn openapi() -> Result<Json<Value>, ServerError> {
+    Ok(Json(OPENAPI.clone()))
+}
@@ -1,7 +1,7 @@
 pub mod relationships;
 pub mod route;
 
-use crate::backend::{CONN, errors::ServerError};
+use crate::backend::{CONN, errors::ServerError, query::Query};
 use axum::{Json, extract::Path};
 use ochat_types::options::{GenOptions, GenOptionsData};
 
@@ -15,7 +15,7 @@ DEFINE TABLE IF NOT EXISTS {0} SCHEMALESS
     PERMISSIONS FOR select, update, delete WHERE user_id = record::id($auth.id) FOR create FULL;
 DEFINE FIELD IF NOT EXISTS user_id ON TABLE {0} TYPE string DEFAULT ALWAYS record::id($auth.id);
 DEFINE FIELD IF NOT EXISTS name ON TABLE {0} TYPE string;
-DEFINE FIELD IF NOT EXISTS data ON TABLE {0} TYPE array<object, 16>;
+DEFINE FIELD IF NOT EXISTS data ON TABLE {0} TYPE array<object>;
 
 DEFINE ANALYZER options_analyzer TOKENIZERS class, blank FILTERS lowercase, ascii;
 DEFINE INDEX name_index ON TABLE {0} COLUMNS name SEARCH ANALYZER options_analyzer BM25;
@@ -51,15 +51,13 @@ pub async fn search_gen_options(
     search: Path<String>,
 ) -> Result<Json<Vec<GenOptions>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "            
-SELECT *, search::score(1) AS score FROM {0} WHERE name @1@ '{1}' ORDER BY score DESC;
-",
-            GEN_OPTIONS_TABLE,
-            search.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new(
+            "SELECT *, search::score(1) AS score FROM type::table($tb) WHERE name @1@ $search ORDER BY score DESC;",
+        )
+        .table(GEN_OPTIONS_TABLE)
+        .bind_str("search", &search)
+        .take()
+        .await?,
     ))
 }
 
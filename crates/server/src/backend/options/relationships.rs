@@ -1,4 +1,4 @@
-use crate::backend::{CONN, errors::ServerError};
+use crate::backend::{CONN, errors::ServerError, query::Query};
 use axum::{Json, extract::Path};
 use ochat_types::{
     options::{
@@ -39,11 +39,11 @@ pub async fn update_gen_models(
     id: Path<String>,
     Json(options): Json<GenModelRelationshipData>,
 ) -> Result<Json<Option<GenModelRelationship>>, ServerError> {
-    let _ = CONN
-        .query(&format!(
-            "DELETE {0} WHERE provider = '{1}' and model = '{2}';",
-            GEN_MODELS_TABLE, options.provider, options.model
-        ))
+    Query::new("DELETE type::table($tb) WHERE provider = $provider and model = $model;")
+        .table(GEN_MODELS_TABLE)
+        .bind_id("provider", &options.provider)
+        .bind_id("model", &options.model)
+        .execute()
         .await?;
     Ok(Json(
         CONN.update((GEN_MODELS_TABLE, id.trim()))
@@ -62,14 +62,12 @@ pub async fn get_default_gen_options_from_model(
     Path((id, model)): Path<(String, String)>,
 ) -> Result<Json<Option<GenOptions>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "SELECT * FROM {0} WHERE provider = '{1}' and model = '{2}';",
-            GEN_MODELS_TABLE,
-            id.trim(),
-            model.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new("SELECT * FROM type::table($tb) WHERE provider = $provider and model = $model;")
+            .table(GEN_MODELS_TABLE)
+            .bind_id("provider", &id)
+            .bind_id("model", &model)
+            .take()
+            .await?,
     ))
 }
 
@@ -77,13 +75,11 @@ pub async fn get_models_from_options(
     id: Path<String>,
 ) -> Result<Json<Vec<SettingsProvider>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "SELECT * FROM {0} WHERE option = '{1}';",
-            GEN_MODELS_TABLE,
-            id.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new("SELECT * FROM type::table($tb) WHERE option = $option;")
+            .table(GEN_MODELS_TABLE)
+            .bind_id("option", &id)
+            .take()
+            .await?,
     ))
 }
 
@@ -91,13 +87,11 @@ pub async fn get_gen_models_from_options(
     id: Path<String>,
 ) -> Result<Json<Vec<GenModelRelationship>>, ServerError> {
     Ok(Json(
-        CONN.query(&format!(
-            "SELECT * FROM {0} WHERE option = '{1}';",
-            GEN_MODELS_TABLE,
-            id.trim()
-        ))
-        .await?
-        .take(0)?,
+        Query::new("SELECT * FROM type::table($tb) WHERE option = $option;")
+            .table(GEN_MODELS_TABLE)
+            .bind_id("option", &id)
+            .take()
+            .await?,
     ))
 }
 
@@ -1,7 +1,7 @@
-use crate::backend::{CONN, errors::ServerError};
+use crate::backend::{CONN, errors::ServerError, query::Query, trash::trash_record};
 use axum::{Json, extract::Path};
 use ochat_types::prompts::{Prompt, PromptData};
-const PROMPTS_TABLE: &str = "prompts";
+pub(crate) const PROMPTS_TABLE: &str = "prompts";
 
 pub mod route;
 
@@ -53,21 +53,31 @@ pub async fn get_prompt(id: Path<String>) -> Result<Json<Option<Prompt>>, Server
 }
 
 pub async fn search_prompts(search: Path<String>) -> Result<Json<Vec<Prompt>>, ServerError> {
-    Ok(Json(CONN
-        .query(&format!(
-            "            
-SELECT *, search::score(1) + search::score(2) + search::score(3) AS score FROM {0} WHERE title @1@ '{1}' or command @2@ '{1}' or content @3@ '{1}' ORDER BY score DESC;
-",
-            PROMPTS_TABLE, search.trim()
-        ))
-        .await?
-        .take(0)?))
+    Ok(Json(
+        Query::new(
+            "SELECT *, search::score(1) + search::score(2) + search::score(3) AS score FROM type::table($tb) WHERE (title @1@ $search or command @2@ $search or content @3@ $search) AND deleted_at = NONE ORDER BY score DESC;",
+        )
+        .table(PROMPTS_TABLE)
+        .bind_str("search", &search)
+        .take()
+        .await?,
+    ))
 }
 
+/// Moves the prompt to the trash.
 pub async fn delete_prompt(id: Path<String>) -> Result<Json<Option<Prompt>>, ServerError> {
+    Ok(Json(trash_record(PROMPTS_TABLE, &id).await?))
+}
+
+pub async fn purge_prompt(id: Path<String>) -> Result<Json<Option<Prompt>>, ServerError> {
     Ok(Json(CONN.delete((PROMPTS_TABLE, id.trim())).await?))
 }
 
 pub async fn list_all_prompts() -> Result<Json<Vec<Prompt>>, ServerError> {
-    Ok(Json(CONN.select(PROMPTS_TABLE).await?))
+    Ok(Json(
+        Query::new("SELECT * FROM type::table($tb) WHERE deleted_at = NONE;")
+            .table(PROMPTS_TABLE)
+            .take()
+            .await?,
+    ))
 }
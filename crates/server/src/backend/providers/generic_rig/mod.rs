@@ -18,6 +18,9 @@ use std::convert::TryFrom;
 
 type GenericApiKey = BearerAuth;
 
+/// Key of the additional params that are sent at the top of the request body instead of in `options`.
+pub const TOP_LEVEL_PARAMS: &str = "top_level";
+
 pub type Client<H = reqwest::Client> = rig::client::Client<GenericExt, H>;
 pub type ClientBuilder<H = reqwest::Client> =
     rig::client::ClientBuilder<GenericBuilder, GenericApiKey, H>;
@@ -219,6 +222,8 @@ pub struct GenericCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u64>,
     options: serde_json::Value,
+    #[serde(flatten)]
+    top_level: serde_json::Map<String, serde_json::Value>,
 }
 pub fn merge_json(a: serde_json::Value, b: serde_json::Value) -> serde_json::Value {
     match (a, b) {
@@ -261,6 +266,7 @@ impl TryFrom<(&str, CompletionRequest)> for GenericCompletionRequest {
         );
 
         let mut think = false;
+        let mut top_level = serde_json::Map::new();
 
         // TODO: Fix this up to include the full range of ollama options
         let options = if let Some(mut extra) = req.additional_params {
@@ -269,6 +275,12 @@ impl TryFrom<(&str, CompletionRequest)> for GenericCompletionRequest {
                     CompletionError::RequestError("`think` must be a bool".into())
                 })?;
             }
+            if let Some(serde_json::Value::Object(params)) = extra
+                .as_object_mut()
+                .and_then(|x| x.remove(TOP_LEVEL_PARAMS))
+            {
+                top_level = params;
+            }
             merge_json(json!({ "temperature": req.temperature }), extra)
         } else {
             json!({ "temperature": req.temperature })
@@ -288,6 +300,7 @@ impl TryFrom<(&str, CompletionRequest)> for GenericCompletionRequest {
                 .map(ToolDefinition::from)
                 .collect::<Vec<_>>(),
             options,
+            top_level,
         })
     }
 }
@@ -1,4 +1,5 @@
 use crate::backend::{
+    metrics::PullGuard,
     providers::hf::{
         API_URL, HF_URL,
         conversion::{ModelFormat, convert_model},
@@ -173,6 +174,8 @@ pub async fn run_pull_stream(
         )));
     }
 
+    let pull = PullGuard::new("hf", &format!("{}/{}", model_id, name));
+
     tokio::spawn(async move {
         let mut totals: HashMap<usize, Option<u64>> = HashMap::new();
         let mut completeds: HashMap<usize, u64> = HashMap::new();
@@ -198,6 +201,7 @@ pub async fn run_pull_stream(
 
                     let avg_progress = (sum_percent / file_count as f64) * 100.0;
                     let speed = total_bytes as f64 / start_time.elapsed().as_secs_f64().max(0.1);
+                    pull.progress(avg_progress as u64, 100);
 
                     let _ = tx.send(HFPullModelStreamResult::Pulling(HFPullModelResponse {
                         total: Some(100),
@@ -7,7 +7,7 @@ pub mod route;
 use axum::{Json, extract::Path, http::HeaderMap};
 use ochat_types::providers::*;
 
-use crate::backend::{CONN, errors::ServerError};
+use crate::backend::{CONN, config::get_config, errors::ServerError};
 pub const PROVIDER_TABLE: &str = "providers";
 
 pub(crate) fn provider_into_config(provider: &Provider) -> generic_rig::Client {
@@ -62,10 +62,16 @@ DEFINE FIELD IF NOT EXISTS provider_type ON TABLE {0} TYPE string;
 }
 
 pub async fn add_default_providers() -> Result<(), ServerError> {
-    if match list_all_providers().await {
-        Ok(x) => x.is_empty(),
-        _ => true,
-    } {
+    let providers = list_all_providers().await.map(|x| x.0).unwrap_or_default();
+    let configured = &get_config().providers;
+
+    if !configured.is_empty() {
+        for provider in configured {
+            if !providers.iter().any(|x| x.name == provider.name) {
+                let _ = add_provider(Json(provider.clone().into())).await?;
+            }
+        }
+    } else if providers.is_empty() {
         if let Some(ollama) = get_local_ollama_data().await {
             let _ = add_provider(Json(ollama)).await?;
         }
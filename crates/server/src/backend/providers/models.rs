@@ -19,8 +19,14 @@ pub async fn list_all_provider_models(id: Path<String>) -> Result<Json<Vec<Model
             .await
         {
             Ok(x) => {
-                let value: Value = x.json().await?;
-                serde_json::from_value(value.get("data").unwrap().clone()).unwrap()
+                let value: Value = x.error_for_status()?.json().await?;
+                let Some(data) = value.get("data") else {
+                    return Err(ServerError::Upstream(String::from(
+                        "Provider returned no model list.",
+                    )));
+                };
+                serde_json::from_value(data.clone())
+                    .map_err(|e| ServerError::Upstream(e.to_string()))?
             }
             Err(e) => {
                 return Err(e.into());
@@ -46,13 +52,22 @@ pub async fn delete_provider_model(
             .send()
             .await
         {
-            Ok(x) => x.json().await?,
+            Ok(x) if x.status() == reqwest::StatusCode::NOT_FOUND => {
+                return Err(ServerError::NotFound(format!(
+                    "Model '{}' does not exist.",
+                    model.trim()
+                )));
+            }
+            Ok(x) => x.error_for_status()?.json().await?,
             Err(e) => {
                 return Err(e.into());
             }
         }
     } else {
-        panic!()
+        return Err(ServerError::NotFound(format!(
+            "Provider '{}' does not exist.",
+            id.trim()
+        )));
     };
 
     Ok(Json(response))
@@ -71,13 +86,22 @@ pub async fn get_provider_model(
             .send()
             .await
         {
-            Ok(x) => x.json().await?,
+            Ok(x) if x.status() == reqwest::StatusCode::NOT_FOUND => {
+                return Err(ServerError::NotFound(format!(
+                    "Model '{}' does not exist.",
+                    model.trim()
+                )));
+            }
+            Ok(x) => x.error_for_status()?.json().await?,
             Err(e) => {
                 return Err(e.into());
             }
         }
     } else {
-        panic!()
+        return Err(ServerError::NotFound(format!(
+            "Provider '{}' does not exist.",
+            id.trim()
+        )));
     };
 
     Ok(Json(response))
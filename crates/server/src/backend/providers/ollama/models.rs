@@ -2,7 +2,7 @@ use axum::{Json, extract::Path};
 use ochat_types::providers::ollama::OllamaModelsInfo;
 use std::collections::HashMap;
 
-use crate::backend::{CONN, errors::ServerError};
+use crate::backend::{CONN, errors::ServerError, query::Query};
 
 pub const OLLAMA_MODELS_TABLE: &str = "ollama_models";
 
@@ -39,8 +39,9 @@ DEFINE INDEX author_index ON TABLE {0} COLUMNS author SEARCH ANALYZER ollama_ana
 
 pub async fn add_all_ollama_models() -> Result<(), ServerError> {
     let models = get_all_ollama_models().await?;
-    let _ = CONN
-        .query(&format!("DELETE {};", OLLAMA_MODELS_TABLE))
+    Query::new("DELETE type::table($tb);")
+        .table(OLLAMA_MODELS_TABLE)
+        .execute()
         .await?;
     for model in models {
         let _: Option<OllamaModelsInfo> = CONN.create(OLLAMA_MODELS_TABLE).content(model).await?;
@@ -90,14 +91,15 @@ async fn get_all_ollama_models() -> Result<Vec<OllamaModelsInfo>, ServerError> {
 pub async fn search_ollama_models(
     search: Path<String>,
 ) -> Result<Json<Vec<OllamaModelsInfo>>, ServerError> {
-    Ok(Json(CONN
-        .query(&format!(
-            "
-SELECT *, search::score(1) + search::score(2) + search::score(3) AS score FROM {0} WHERE name @1@ '{1}' or description @2@ '{1}' or author @3@ '{1}' ORDER BY score DESC;
-",
-            OLLAMA_MODELS_TABLE, &*search
-        ))
-        .await?.take(0)?))
+    Ok(Json(
+        Query::new(
+            "SELECT *, search::score(1) + search::score(2) + search::score(3) AS score FROM type::table($tb) WHERE name @1@ $search or description @2@ $search or author @3@ $search ORDER BY score DESC;",
+        )
+        .table(OLLAMA_MODELS_TABLE)
+        .bind_str("search", &search)
+        .take()
+        .await?,
+    ))
 }
 
 pub async fn list_all_ollama_models() -> Result<Json<Vec<OllamaModelsInfo>>, ServerError> {
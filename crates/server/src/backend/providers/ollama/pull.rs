@@ -1,5 +1,7 @@
 use crate::backend::{
     CONN,
+    errors::ServerError,
+    metrics::PullGuard,
     providers::{PROVIDER_TABLE, Provider},
 };
 use axum::extract::Path;
@@ -13,13 +15,12 @@ use tokio_stream::StreamExt;
 async fn run_pull_stream(
     provider: String,
     model: String,
-) -> impl Stream<Item = OllamaPullModelStreamResult> {
+) -> Result<impl Stream<Item = OllamaPullModelStreamResult>, ServerError> {
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
     let mut response = if let Some(provider) = CONN
         .select::<Option<Provider>>((PROVIDER_TABLE, &*provider))
-        .await
-        .unwrap()
+        .await?
     {
         let client = reqwest::Client::new();
         let url = format!(
@@ -34,19 +35,27 @@ async fn run_pull_stream(
                 "stream" : true
             }))
             .send()
-            .await
-            .unwrap()
+            .await?
+            .error_for_status()?
             .bytes_stream()
     } else {
-        panic!()
+        return Err(ServerError::NotFound(format!(
+            "Provider '{}' does not exist.",
+            provider
+        )));
     };
 
+    let pull = PullGuard::new("ollama", &model);
+
     tokio::spawn(async move {
         while let Some(response) = response.next().await {
             match response {
                 Ok(response) => {
                     let _ = match serde_json::from_slice::<OllamaPullModelResponse>(&response) {
-                        Ok(x) => tx.send(OllamaPullModelStreamResult::Pulling(x)),
+                        Ok(x) => {
+                            pull.progress(x.completed.unwrap_or(0), x.total.unwrap_or(0));
+                            tx.send(OllamaPullModelStreamResult::Pulling(x))
+                        }
                         Err(e) => tx.send(OllamaPullModelStreamResult::Err(e.to_string())),
                     };
                 }
@@ -59,10 +68,14 @@ async fn run_pull_stream(
         let _ = tx.send(OllamaPullModelStreamResult::Finished);
     });
 
-    return Box::pin(tokio_stream::wrappers::UnboundedReceiverStream::new(rx));
+    Ok(Box::pin(
+        tokio_stream::wrappers::UnboundedReceiverStream::new(rx),
+    ))
 }
 
 #[axum::debug_handler]
-pub async fn run(Path((id, model)): Path<(String, String)>) -> impl IntoResponse {
-    StreamBodyAs::json_nl(run_pull_stream(id, model).await)
+pub async fn run(
+    Path((id, model)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ServerError> {
+    Ok(StreamBodyAs::json_nl(run_pull_stream(id, model).await?))
 }
@@ -0,0 +1,78 @@
+use crate::backend::{CONN, errors::ServerError};
+use serde::Serialize;
+use surrealdb::{engine::any::Any, opt::QueryResult};
+
+type Bind = Box<
+    dyn FnOnce(surrealdb::method::Query<'static, Any>) -> surrealdb::method::Query<'static, Any>
+        + Send,
+>;
+
+pub struct Query {
+    sql: String,
+    params: Vec<Bind>,
+}
+
+impl Query {
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            sql: sql.into(),
+            params: Vec::new(),
+        }
+    }
+
+    pub fn bind(mut self, key: &str, value: impl Serialize + Send + 'static) -> Self {
+        let key = key.to_string();
+        self.params
+            .push(Box::new(move |query| query.bind((key, value))));
+        self
+    }
+
+    pub fn bind_str(self, key: &str, value: &str) -> Self {
+        self.bind(key, value.to_string())
+    }
+
+    /// Binds a table name or record id, which may come in with stray whitespace from a path.
+    pub fn bind_id(self, key: &str, id: &str) -> Self {
+        self.bind(key, id.trim().to_string())
+    }
+
+    /// Binds `$tb` so the statement can refer to `type::table($tb)`.
+    pub fn table(self, table: &str) -> Self {
+        self.bind_id("tb", table)
+    }
+
+    /// Binds `$tb` and `$id` so the statement can refer to `type::thing($tb, $id)`.
+    pub fn record(self, table: &str, id: &str) -> Self {
+        self.table(table).bind_id("id", id)
+    }
+
+    pub async fn run(self) -> Result<surrealdb::Response, ServerError> {
+        let mut query = CONN.query(self.sql);
+
+        for bind in self.params {
+            query = bind(query);
+        }
+
+        Ok(query.await?.check()?)
+    }
+
+    pub async fn execute(self) -> Result<(), ServerError> {
+        let _ = self.run().await?;
+        Ok(())
+    }
+
+    pub async fn take<R>(self) -> Result<R, ServerError>
+    where
+        usize: QueryResult<R>,
+    {
+        self.take_at(0).await
+    }
+
+    /// Takes the result of a later statement, skipping the `LET`s before it.
+    pub async fn take_at<R>(self, index: usize) -> Result<R, ServerError>
+    where
+        usize: QueryResult<R>,
+    {
+        Ok(self.run().await?.take(index)?)
+    }
+}
@@ -1,7 +1,9 @@
 use crate::backend::{
     CONN,
+    config::get_config,
     errors::ServerError,
     providers::{list_all_providers, models::list_all_provider_models},
+    user::require_admin,
     utils::get_path_local,
 };
 use axum::{Json, extract::Path};
@@ -9,7 +11,7 @@ use ochat_types::{providers::ProviderType, settings::*};
 use std::{path::PathBuf, str::FromStr};
 
 pub mod route;
-const SETTINGS_TABLE: &str = "settings";
+pub(crate) const SETTINGS_TABLE: &str = "settings";
 
 pub async fn define_settings() -> Result<(), ServerError> {
     let _ = CONN
@@ -20,6 +22,9 @@ DEFINE FIELD IF NOT EXISTS previews_provider ON TABLE {0} TYPE option<object>;
 DEFINE FIELD IF NOT EXISTS models_path ON TABLE {0} TYPE string;
 DEFINE FIELD IF NOT EXISTS use_llama_cpp ON TABLE {0} TYPE bool;
 DEFINE FIELD IF NOT EXISTS hf_token ON TABLE {0} TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS open_signup ON TABLE {0} TYPE bool DEFAULT true;
+DEFINE FIELD IF NOT EXISTS context_strategy ON TABLE {0} TYPE string DEFAULT "drop_oldest";
+DEFINE FIELD IF NOT EXISTS context_window ON TABLE {0} TYPE option<int>;
 ",
             SETTINGS_TABLE,
         ))
@@ -71,15 +76,18 @@ pub async fn reset_settings() -> Result<Json<Option<Settings>>, ServerError> {
         }
     };
 
-    let settings = SettingsData {
+    let settings_list: Vec<Settings> = CONN.select(SETTINGS_TABLE).await?;
+
+    let settings = with_config_settings(SettingsData {
         previews_provider: default_provider,
         embeddings_provider,
         use_llama_cpp: Some(true),
         models_path: Some(PathBuf::from_str(&get_path_local("models/".to_string())).unwrap()),
         hf_token: None,
-    };
-
-    let settings_list: Vec<Settings> = CONN.select(SETTINGS_TABLE).await?;
+        open_signup: Some(settings_list.first().map(|x| x.open_signup).unwrap_or(true)),
+        context_strategy: settings_list.first().map(|x| x.context_strategy),
+        context_window: settings_list.first().and_then(|x| x.context_window),
+    });
 
     Ok(Json(if settings_list.is_empty() {
         CONN.create(SETTINGS_TABLE).content(settings).await?
@@ -90,6 +98,57 @@ pub async fn reset_settings() -> Result<Json<Option<Settings>>, ServerError> {
     }))
 }
 
+fn with_config_settings(mut settings: SettingsData) -> SettingsData {
+    let config = get_config().settings.clone();
+
+    if config.previews_provider.is_some() {
+        settings.previews_provider = config.previews_provider;
+    }
+
+    if config.embeddings_provider.is_some() {
+        settings.embeddings_provider = config.embeddings_provider;
+    }
+
+    if config.models_path.is_some() {
+        settings.models_path = config.models_path;
+    }
+
+    if config.use_llama_cpp.is_some() {
+        settings.use_llama_cpp = config.use_llama_cpp;
+    }
+
+    if config.hf_token.is_some() {
+        settings.hf_token = config.hf_token;
+    }
+
+    if config.open_signup.is_some() {
+        settings.open_signup = config.open_signup;
+    }
+
+    if config.context_strategy.is_some() {
+        settings.context_strategy = config.context_strategy;
+    }
+
+    if config.context_window.is_some() {
+        settings.context_window = config.context_window;
+    }
+
+    settings
+}
+
+pub async fn apply_config_settings() -> Result<(), ServerError> {
+    let settings: Vec<Settings> = CONN.select(SETTINGS_TABLE).await?;
+
+    if let Some(current) = settings.first() {
+        let _: Option<Settings> = CONN
+            .update((SETTINGS_TABLE, current.id.key().to_string()))
+            .content(with_config_settings(current.clone().into()))
+            .await?;
+    }
+
+    Ok(())
+}
+
 pub async fn get_settings() -> Result<Json<Settings>, ServerError> {
     let mut settings: Vec<Settings> = CONN.select(SETTINGS_TABLE).await?;
 
@@ -100,6 +159,9 @@ pub async fn get_settings() -> Result<Json<Settings>, ServerError> {
             use_llama_cpp: true,
             models_path: PathBuf::from_str(&get_path_local("models/".to_string())).unwrap(),
             hf_token: None,
+            open_signup: true,
+            context_strategy: ContextStrategy::default(),
+            context_window: None,
             id: (SETTINGS_TABLE, "unknown").into(),
         })
     } else {
@@ -124,6 +186,21 @@ pub async fn update_settings(
         current_settings.use_llama_cpp = x;
     }
 
+    if let Some(x) = settings.open_signup {
+        if x != current_settings.open_signup {
+            let _ = require_admin().await?;
+        }
+        current_settings.open_signup = x;
+    }
+
+    if let Some(x) = settings.context_strategy {
+        current_settings.context_strategy = x;
+    }
+
+    if let Some(x) = settings.context_window {
+        current_settings.context_window = if x == 0 { None } else { Some(x) };
+    }
+
     if let Some(x) = settings.hf_token {
         let token = x.trim().to_string();
         current_settings.hf_token = if token.is_empty() { None } else { Some(token) };
@@ -0,0 +1,153 @@
+use crate::backend::{
+    chats::{CHAT_TABLE, previews::PREVIEW_TABLE, purge_chat},
+    errors::ServerError,
+    folders::{FOLDER_TABLE, purge_folder},
+    prompts::{PROMPTS_TABLE, purge_prompt},
+    query::Query,
+};
+use axum::{Json, extract::Path};
+use ochat_types::trash::{TrashItem, TrashKind};
+use serde::de::DeserializeOwned;
+
+pub mod route;
+
+fn get_trash_table(kind: &TrashKind) -> &'static str {
+    match kind {
+        TrashKind::Chat => CHAT_TABLE,
+        TrashKind::Folder => FOLDER_TABLE,
+        TrashKind::Prompt => PROMPTS_TABLE,
+    }
+}
+
+fn get_trash_select(kind: &TrashKind, filter: &str) -> String {
+    let name = match kind {
+        TrashKind::Chat => {
+            "(SELECT VALUE text FROM type::thing($preview_tb, record::id($parent.id)))[0] ?? 'New Chat'"
+        }
+        TrashKind::Folder => "name",
+        TrashKind::Prompt => "title",
+    };
+
+    format!(
+        "SELECT record::id(id) AS id, '{}' AS kind, {} AS name, deleted_at FROM {} WHERE deleted_at != NONE{};",
+        kind,
+        name,
+        get_trash_table(kind),
+        filter
+    )
+}
+
+async fn select_trash(
+    kinds: &[TrashKind],
+    filter: &str,
+    query: impl FnOnce(Query) -> Query,
+) -> Result<Vec<TrashItem>, ServerError> {
+    let sql: String = kinds.iter().map(|x| get_trash_select(x, filter)).collect();
+    let mut response = query(Query::new(sql).bind_id("preview_tb", PREVIEW_TABLE))
+        .run()
+        .await?;
+    let mut items = Vec::new();
+
+    for index in 0..kinds.len() {
+        items.append(&mut response.take::<Vec<TrashItem>>(index)?);
+    }
+
+    items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(items)
+}
+
+async fn get_trash_item(kind: TrashKind, id: &str) -> Result<Option<TrashItem>, ServerError> {
+    Ok(select_trash(&[kind], " AND record::id(id) = $id", |x| {
+        x.bind_id("id", id)
+    })
+    .await?
+    .pop())
+}
+
+async fn purge_trash_item(item: &TrashItem) -> Result<(), ServerError> {
+    let id = Path(item.id.clone());
+
+    match item.kind {
+        TrashKind::Chat => {
+            let _ = purge_chat(id).await?;
+        }
+        TrashKind::Folder => {
+            let _ = purge_folder(id).await?;
+        }
+        TrashKind::Prompt => {
+            let _ = purge_prompt(id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Soft deletes a record, hiding it from listings until it is restored or purged.
+pub async fn trash_record<T: DeserializeOwned>(
+    table: &str,
+    id: &str,
+) -> Result<Option<T>, ServerError> {
+    Query::new("UPDATE type::thing($tb, $id) SET deleted_at = <string>time::now();")
+        .record(table, id)
+        .take()
+        .await
+}
+
+/// Purges everything that has been in the trash for longer than `days`, returning how much there was.
+pub async fn purge_expired_trash(days: u64, dry_run: bool) -> Result<usize, ServerError> {
+    let items = select_trash(
+        &TrashKind::ALL,
+        " AND <datetime>deleted_at < time::now() - duration::from::days($days)",
+        |x| x.bind("days", days),
+    )
+    .await?;
+
+    if !dry_run {
+        for item in &items {
+            purge_trash_item(item).await?;
+        }
+    }
+
+    Ok(items.len())
+}
+
+pub async fn list_trash() -> Result<Json<Vec<TrashItem>>, ServerError> {
+    Ok(Json(select_trash(&TrashKind::ALL, "", |x| x).await?))
+}
+
+pub async fn restore_trash(
+    Path((kind, id)): Path<(TrashKind, String)>,
+) -> Result<Json<Option<TrashItem>>, ServerError> {
+    let item = get_trash_item(kind, &id).await?;
+
+    if item.is_some() {
+        Query::new("UPDATE type::thing($tb, $id) SET deleted_at = NONE;")
+            .record(get_trash_table(&kind), &id)
+            .execute()
+            .await?;
+    }
+
+    Ok(Json(item))
+}
+
+pub async fn purge_trash(
+    Path((kind, id)): Path<(TrashKind, String)>,
+) -> Result<Json<Option<TrashItem>>, ServerError> {
+    let item = get_trash_item(kind, &id).await?;
+
+    if let Some(item) = &item {
+        purge_trash_item(item).await?;
+    }
+
+    Ok(Json(item))
+}
+
+pub async fn empty_trash() -> Result<Json<Vec<TrashItem>>, ServerError> {
+    let items = select_trash(&TrashKind::ALL, "", |x| x).await?;
+
+    for item in &items {
+        purge_trash_item(item).await?;
+    }
+
+    Ok(Json(items))
+}
@@ -0,0 +1,14 @@
+use crate::backend::trash;
+use axum::{
+    Router,
+    routing::{get, put},
+};
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/trash/", get(trash::list_trash).delete(trash::empty_trash))
+        .route(
+            "/trash/{kind}/{id}",
+            put(trash::restore_trash).delete(trash::purge_trash),
+        )
+}
@@ -1,20 +1,24 @@
 use crate::backend::{
-    CONN, DATABASE, NAMESPACE, define_starting_data, errors::ServerError,
+    CONN, config::get_config, define_starting_data, errors::ServerError,
     folders::create_default_user_folders, providers::ollama::models::OLLAMA_MODELS_TABLE,
-    utils::get_count,
+    query::Query, settings::get_settings, utils::get_count,
 };
 use axum::{Json, extract::Path, http::HeaderMap};
 use ochat_types::{
     surreal::RecordId,
-    user::{SigninData, SignupData, Token, User},
+    user::{ApiTokenScope, ChangePasswordData, Role, SigninData, SignupData, Token, User},
 };
 use serde::{Deserialize, Serialize};
+use sessions::{check_session, create_session, revoke_other_sessions};
 use surrealdb::opt::auth::Record;
+use tokens::{API_TOKEN_PREFIX, authenticate_api_token};
 
 pub mod route;
+pub mod sessions;
+pub mod tokens;
 
-const USER_TABLE: &str = "user";
-const AUTH_TABLE: &str = "auth";
+pub(crate) const USER_TABLE: &str = "user";
+pub(crate) const AUTH_TABLE: &str = "auth";
 
 pub async fn define_users() -> Result<(), ServerError> {
     let _ = CONN
@@ -56,53 +60,45 @@ pub struct TokenData {
     pub exp: String,
 }
 
-pub async fn signup(data: Json<SignupData>) -> Result<Json<Token>, ServerError> {
+pub async fn signup(header: HeaderMap, data: Json<SignupData>) -> Result<Json<Token>, ServerError> {
+    if !get_settings().await?.open_signup && get_count(USER_TABLE).await? > 0 {
+        return Err(ServerError::Forbidden(String::from(
+            "Signup is closed, ask an admin to create an account.",
+        )));
+    }
+
     let jwt = CONN
         .signup(Record {
-            namespace: NAMESPACE,
-            database: DATABASE,
+            namespace: &get_config().database.namespace,
+            database: &get_config().database.database,
             access: AUTH_TABLE,
             params: data.0.clone(),
         })
         .await?;
 
     CONN.authenticate(jwt.clone()).await?;
+    let jwt = jwt.into_insecure_token();
+    create_session(&jwt, &header).await?;
 
     reset_persistent_db_data_if_required().await?;
     let _ = get_current_user().await;
 
-    let count = get_count(&format!(
-        "
-                SELECT count() FROM {0} GROUP ALL;
-            ",
-        USER_TABLE,
-    ))
-    .await?;
+    let count = get_count(USER_TABLE).await?;
 
     if count <= 1 {
-        let _ = CONN
-            .query(&format!(
-                "
-                UPDATE {0} SET role = 'Admin';
-            ",
-                USER_TABLE,
-            ))
+        let _ = Query::new("UPDATE type::table($tb) SET role = 'Admin';")
+            .table(USER_TABLE)
+            .execute()
             .await;
     }
 
-    Ok(Json(Token::new(jwt.into_insecure_token())))
+    Ok(Json(Token::new(jwt)))
 }
 
 pub async fn reset_persistent_db_data_if_required() -> Result<(), ServerError> {
     let _ = create_default_user_folders().await?;
 
-    let count = get_count(&format!(
-        "
-                SELECT count() FROM {0} GROUP ALL;
-            ",
-        OLLAMA_MODELS_TABLE,
-    ))
-    .await?;
+    let count = get_count(OLLAMA_MODELS_TABLE).await?;
 
     if count == 0 {
         define_starting_data().await?;
@@ -111,62 +107,133 @@ pub async fn reset_persistent_db_data_if_required() -> Result<(), ServerError> {
     Ok(())
 }
 
-pub async fn signin(data: Json<SigninData>) -> Result<Json<Token>, ServerError> {
+pub async fn signin(header: HeaderMap, data: Json<SigninData>) -> Result<Json<Token>, ServerError> {
     let jwt = CONN
         .signin(Record {
-            namespace: NAMESPACE,
-            database: DATABASE,
+            namespace: &get_config().database.namespace,
+            database: &get_config().database.database,
             access: AUTH_TABLE,
             params: data.0.clone(),
         })
         .await?;
 
     CONN.authenticate(jwt.clone()).await?;
+    check_enabled().await?;
+    let jwt = jwt.into_insecure_token();
+    create_session(&jwt, &header).await?;
 
     reset_persistent_db_data_if_required().await?;
 
-    Ok(Json(Token::new(jwt.into_insecure_token())))
+    Ok(Json(Token::new(jwt)))
 }
 
-pub async fn authenticate(header: &HeaderMap) -> Result<(), ServerError> {
-    let jwt: String = match header.get("Authorization") {
+pub fn get_jwt(header: &HeaderMap) -> Result<String, ServerError> {
+    Ok(match header.get("Authorization") {
         Some(x) => x,
         _ => {
-            return Err(ServerError::Unknown(String::from(
-                "AUTH ERROR : Not Logged In.",
-            )));
+            return Err(ServerError::Unauthorized(String::from("Not Logged In.")));
         }
     }
     .to_str()
-    .map_err(|e| ServerError::Unknown(e.to_string()))?
-    .to_string();
+    .map_err(|e| ServerError::Unauthorized(e.to_string()))?
+    .trim_start_matches("Bearer ")
+    .to_string())
+}
 
-    CONN.authenticate(jwt).await?;
+pub async fn authenticate(header: &HeaderMap) -> Result<Option<ApiTokenScope>, ServerError> {
+    let jwt = get_jwt(header)?;
+
+    if jwt.starts_with(API_TOKEN_PREFIX) {
+        let scope = authenticate_api_token(&jwt).await?;
+        check_enabled().await?;
+        return Ok(Some(scope));
+    }
+
+    CONN.authenticate(jwt.clone())
+        .await
+        .map_err(|e| ServerError::Unauthorized(e.to_string()))?;
+
+    check_session(&jwt).await?;
+    check_enabled().await?;
+    Ok(None)
+}
+
+pub async fn change_password(
+    header: HeaderMap,
+    Json(data): Json<ChangePasswordData>,
+) -> Result<Json<Option<User>>, ServerError> {
+    if data.new_password.is_empty() {
+        return Err(ServerError::Validation(String::from(
+            "The new password cannot be empty.",
+        )));
+    }
+
+    let changed: Option<String> = Query::new(
+        "UPDATE ONLY type::thing($tb, record::id($auth.id)) SET password = crypto::argon2::generate($new_password) WHERE crypto::argon2::compare(password, $old_password) RETURN VALUE name;",
+    )
+    .table(AUTH_TABLE)
+    .bind("old_password", data.old_password)
+    .bind("new_password", data.new_password)
+    .take()
+    .await?;
+
+    if changed.is_none() {
+        return Err(ServerError::Unauthorized(String::from(
+            "The current password is incorrect.",
+        )));
+    }
+
+    revoke_other_sessions(&get_jwt(&header)?).await?;
+    get_current_user().await
+}
+
+async fn check_enabled() -> Result<(), ServerError> {
+    let enabled: Option<bool> =
+        Query::new("SELECT VALUE enabled FROM ONLY type::thing($tb, record::id($auth.id));")
+            .table(USER_TABLE)
+            .take()
+            .await?;
+
+    if enabled == Some(false) {
+        CONN.invalidate().await?;
+        return Err(ServerError::Forbidden(String::from(
+            "This account has been disabled.",
+        )));
+    }
 
     Ok(())
 }
 
+pub async fn require_admin() -> Result<User, ServerError> {
+    match get_current_user().await?.0 {
+        Some(user) if user.role == Role::Admin => Ok(user),
+        _ => Err(ServerError::Forbidden(String::from(
+            "Only admins can do this.",
+        ))),
+    }
+}
+
+/// The id of the signed in user, as it is stored in the `user_id` fields.
+pub async fn get_user_id() -> Result<String, ServerError> {
+    let id: Option<String> =
+        Query::new("RETURN IF $auth.id THEN record::id($auth.id) ELSE NONE END;")
+            .take()
+            .await?;
+
+    id.ok_or_else(|| ServerError::Unauthorized(String::from("You are not signed in.")))
+}
+
 pub async fn get_current_user() -> Result<Json<Option<User>>, ServerError> {
-    let mut user: Vec<User> = CONN
-        .query(&format!(
-            "
-                SELECT * FROM type::thing('{0}', record::id($auth.id));
-            ",
-            USER_TABLE,
-        ))
-        .await?
-        .take(0)?;
+    let mut user: Vec<User> = Query::new("SELECT * FROM type::thing($tb, record::id($auth.id));")
+        .table(USER_TABLE)
+        .take()
+        .await?;
 
     if user.is_empty() {
-        user = CONN
-            .query(&format!(
-                "
-                CREATE type::thing('{0}', record::id($auth.id));
-            ",
-                USER_TABLE,
-            ))
-            .await?
-            .take(0)?;
+        user = Query::new("CREATE type::thing($tb, record::id($auth.id));")
+            .table(USER_TABLE)
+            .take()
+            .await?;
 
         if user.is_empty() {
             return Ok(Json(None));
@@ -176,16 +243,11 @@ pub async fn get_current_user() -> Result<Json<Option<User>>, ServerError> {
 }
 
 pub async fn get_user_from_name(name: String) -> Result<Option<User>, ServerError> {
-    let mut user: Vec<User> = CONN
-        .query(&format!(
-            "
-                SELECT * FROM {0} WHERE name = '{1}';
-            ",
-            USER_TABLE,
-            name.trim(),
-        ))
-        .await?
-        .take(0)?;
+    let mut user: Vec<User> = Query::new("SELECT * FROM type::table($tb) WHERE name = $name;")
+        .table(USER_TABLE)
+        .bind_str("name", &name)
+        .take()
+        .await?;
 
     if user.is_empty() {
         return Ok(None);
@@ -201,3 +263,109 @@ pub async fn get_user_from_id(id: Path<String>) -> Result<Json<Option<User>>, Se
 pub async fn list_all_users() -> Result<Json<Vec<User>>, ServerError> {
     Ok(Json(CONN.select(USER_TABLE).await?))
 }
+
+pub async fn create_user(Json(data): Json<SignupData>) -> Result<Json<Option<User>>, ServerError> {
+    if get_user_from_name(data.name.clone()).await?.is_some() {
+        return Err(ServerError::Conflict(format!(
+            "User '{}' already exists.",
+            data.name.trim()
+        )));
+    }
+
+    Ok(Json(
+        Query::new(
+            "CREATE ONLY type::thing($tb, record::id((CREATE ONLY type::table($auth_tb) SET name = $name, email = $email, password = crypto::argon2::generate($password)).id)) SET name = $name, email = $email;",
+        )
+        .table(USER_TABLE)
+        .bind_id("auth_tb", AUTH_TABLE)
+        .bind_str("name", &data.name)
+        .bind_str("email", &data.email)
+        .bind("password", data.password)
+        .take()
+        .await?,
+    ))
+}
+
+async fn get_other_user(id: &str) -> Result<User, ServerError> {
+    let admin = require_admin().await?;
+
+    if admin.id.key().to_string() == id.trim() {
+        return Err(ServerError::Conflict(String::from(
+            "Admins cannot change their own account here.",
+        )));
+    }
+
+    match CONN.select((USER_TABLE, id.trim())).await? {
+        Some(user) => Ok(user),
+        None => Err(ServerError::NotFound(format!(
+            "User '{}' does not exist.",
+            id.trim()
+        ))),
+    }
+}
+
+async fn set_user_field(
+    id: &str,
+    sql: &str,
+    value: impl Serialize + Send + 'static,
+) -> Result<Json<Option<User>>, ServerError> {
+    let _ = get_other_user(id).await?;
+
+    Ok(Json(
+        Query::new(sql)
+            .record(USER_TABLE, id)
+            .bind("value", value)
+            .take()
+            .await?,
+    ))
+}
+
+pub async fn enable_user(Path(id): Path<String>) -> Result<Json<Option<User>>, ServerError> {
+    set_user_field(
+        &id,
+        "UPDATE type::thing($tb, $id) SET enabled = $value;",
+        true,
+    )
+    .await
+}
+
+pub async fn disable_user(Path(id): Path<String>) -> Result<Json<Option<User>>, ServerError> {
+    set_user_field(
+        &id,
+        "UPDATE type::thing($tb, $id) SET enabled = $value;",
+        false,
+    )
+    .await
+}
+
+pub async fn promote_user(Path(id): Path<String>) -> Result<Json<Option<User>>, ServerError> {
+    set_user_field(
+        &id,
+        "UPDATE type::thing($tb, $id) SET role = $value;",
+        Role::Admin,
+    )
+    .await
+}
+
+pub async fn demote_user(Path(id): Path<String>) -> Result<Json<Option<User>>, ServerError> {
+    set_user_field(
+        &id,
+        "UPDATE type::thing($tb, $id) SET role = $value;",
+        Role::User,
+    )
+    .await
+}
+
+pub async fn delete_user(Path(id): Path<String>) -> Result<Json<Option<User>>, ServerError> {
+    let user = get_other_user(&id).await?;
+
+    Query::new("DELETE type::thing($auth_tb, $id);")
+        .bind_id("auth_tb", AUTH_TABLE)
+        .bind_id("id", &id)
+        .execute()
+        .await?;
+
+    let _: Option<User> = CONN.delete((USER_TABLE, id.trim())).await?;
+
+    Ok(Json(Some(user)))
+}
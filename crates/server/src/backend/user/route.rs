@@ -1,7 +1,7 @@
-use crate::backend::{errors::ServerError, user};
+use crate::backend::{admin_guard, errors::ServerError, openapi::openapi, user};
 use axum::{
-    Json, Router,
-    routing::{get, post},
+    Json, Router, middleware,
+    routing::{delete, get, post, put},
 };
 use ochat_types::ServerFeatures;
 
@@ -28,11 +28,30 @@ pub fn auth_routes() -> Router {
         .route("/features/", get(features))
         .route("/signin/", post(user::signin))
         .route("/signup/", post(user::signup))
-        .route("/users/", get(user::list_all_users))
+        .route("/openapi.json", get(openapi))
 }
 
 pub fn routes() -> Router {
     Router::new()
+        .route("/user/logout/", post(user::sessions::logout))
+        .route("/user/refresh/", post(user::sessions::refresh))
+        .route("/user/password/", put(user::change_password))
+        .route("/user/session/all/", get(user::sessions::list_all_sessions))
+        .route("/user/session/{id}", delete(user::sessions::revoke_session))
+        .route("/user/token/", post(user::tokens::create_api_token))
+        .route("/user/token/all/", get(user::tokens::list_all_api_tokens))
+        .route("/user/token/{id}", delete(user::tokens::revoke_api_token))
         .route("/user/{id}", get(user::get_user_from_id))
         .route("/user/", get(user::get_current_user))
 }
+
+pub fn admin_routes() -> Router {
+    Router::new()
+        .route("/users/", get(user::list_all_users).post(user::create_user))
+        .route("/users/{id}", delete(user::delete_user))
+        .route("/users/{id}/enable/", put(user::enable_user))
+        .route("/users/{id}/disable/", put(user::disable_user))
+        .route("/users/{id}/promote/", put(user::promote_user))
+        .route("/users/{id}/demote/", put(user::demote_user))
+        .route_layer(middleware::from_fn(admin_guard))
+}
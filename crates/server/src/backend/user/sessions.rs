@@ -0,0 +1,146 @@
+use crate::backend::{
+    CONN,
+    config::get_config,
+    errors::ServerError,
+    query::Query,
+    user::{AUTH_TABLE, get_jwt},
+};
+use axum::{
+    Json,
+    extract::Path,
+    http::{HeaderMap, header::USER_AGENT},
+};
+use ochat_types::user::{Session, Token};
+use serde_json::json;
+use surrealdb::opt::auth::Record;
+
+pub(crate) const SESSIONS_TABLE: &str = "sessions";
+const SESSION_ACCESS: &str = "session";
+
+pub async fn define_sessions() -> Result<(), ServerError> {
+    let _ = CONN
+        .query(&format!(
+            "
+DEFINE TABLE IF NOT EXISTS {0} SCHEMAFULL
+    PERMISSIONS FOR select, update, delete WHERE user_id = record::id($auth.id) FOR create FULL;
+DEFINE FIELD IF NOT EXISTS user_id ON TABLE {0} TYPE string DEFAULT ALWAYS record::id($auth.id);
+DEFINE FIELD IF NOT EXISTS device ON TABLE {0} TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS hash ON TABLE {0} TYPE string;
+DEFINE FIELD IF NOT EXISTS time ON TABLE {0} TYPE string DEFAULT <string>time::now();
+DEFINE FIELD IF NOT EXISTS last_used ON TABLE {0} TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS expires ON TABLE {0} TYPE string DEFAULT <string>(time::now() + 1w);
+DEFINE INDEX IF NOT EXISTS hash_index ON TABLE {0} COLUMNS hash UNIQUE;
+
+DEFINE ACCESS IF NOT EXISTS {1} ON DATABASE TYPE RECORD
+    SIGNIN ( SELECT * FROM type::thing('{2}', (SELECT VALUE user_id FROM ONLY {0} WHERE hash = crypto::sha256($jwt) AND <datetime>expires > time::now() LIMIT 1)) )
+    DURATION FOR TOKEN 1w, FOR SESSION 1w
+;
+",
+            SESSIONS_TABLE, SESSION_ACCESS, AUTH_TABLE
+        ))
+        .await?;
+    Ok(())
+}
+
+pub async fn create_session(jwt: &str, header: &HeaderMap) -> Result<(), ServerError> {
+    let device = header
+        .get(USER_AGENT)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.to_string());
+
+    Query::new(
+        "DELETE type::table($tb) WHERE <datetime>expires < time::now(); CREATE type::table($tb) SET device = $device, hash = crypto::sha256($jwt);",
+    )
+    .table(SESSIONS_TABLE)
+    .bind("device", device)
+    .bind_id("jwt", jwt)
+    .execute()
+    .await
+}
+
+pub async fn check_session(jwt: &str) -> Result<(), ServerError> {
+    let sessions: Vec<Session> = Query::new(
+        "UPDATE type::table($tb) SET last_used = <string>time::now() WHERE hash = crypto::sha256($jwt) AND <datetime>expires > time::now() RETURN AFTER;",
+    )
+    .table(SESSIONS_TABLE)
+    .bind_id("jwt", jwt)
+    .take()
+    .await?;
+
+    if sessions.is_empty() {
+        CONN.invalidate().await?;
+        return Err(ServerError::Unauthorized(String::from(
+            "This session has been revoked or has expired.",
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn revoke_other_sessions(jwt: &str) -> Result<(), ServerError> {
+    Query::new("DELETE type::table($tb) WHERE hash != crypto::sha256($jwt);")
+        .table(SESSIONS_TABLE)
+        .bind_id("jwt", jwt)
+        .execute()
+        .await
+}
+
+pub async fn logout(header: HeaderMap) -> Result<Json<Option<Session>>, ServerError> {
+    let jwt = get_jwt(&header)?;
+
+    let mut sessions: Vec<Session> =
+        Query::new("DELETE type::table($tb) WHERE hash = crypto::sha256($jwt) RETURN BEFORE;")
+            .table(SESSIONS_TABLE)
+            .bind_id("jwt", &jwt)
+            .take()
+            .await?;
+
+    CONN.invalidate().await?;
+    Ok(Json(sessions.pop()))
+}
+
+pub async fn refresh(header: HeaderMap) -> Result<Json<Token>, ServerError> {
+    let jwt = get_jwt(&header)?;
+
+    let new_jwt = CONN
+        .signin(Record {
+            namespace: &get_config().database.namespace,
+            database: &get_config().database.database,
+            access: SESSION_ACCESS,
+            params: json!({ "jwt": jwt }),
+        })
+        .await
+        .map_err(|e| ServerError::Unauthorized(e.to_string()))?;
+
+    CONN.authenticate(new_jwt.clone()).await?;
+    let new_jwt = new_jwt.into_insecure_token();
+
+    Query::new(
+        "UPDATE type::table($tb) SET hash = crypto::sha256($new_jwt), expires = <string>(time::now() + 1w), last_used = <string>time::now() WHERE hash = crypto::sha256($jwt);",
+    )
+    .table(SESSIONS_TABLE)
+    .bind_id("jwt", &jwt)
+    .bind_id("new_jwt", &new_jwt)
+    .execute()
+    .await?;
+
+    Ok(Json(Token::new(new_jwt)))
+}
+
+pub async fn list_all_sessions(header: HeaderMap) -> Result<Json<Vec<Session>>, ServerError> {
+    let jwt = get_jwt(&header)?;
+
+    Ok(Json(
+        Query::new(
+            "SELECT *, hash = crypto::sha256($jwt) AS current OMIT hash FROM type::table($tb) WHERE <datetime>expires > time::now() ORDER BY time DESC;",
+        )
+        .table(SESSIONS_TABLE)
+        .bind_id("jwt", &jwt)
+        .take()
+        .await?,
+    ))
+}
+
+pub async fn revoke_session(id: Path<String>) -> Result<Json<Option<Session>>, ServerError> {
+    Ok(Json(CONN.delete((SESSIONS_TABLE, id.trim())).await?))
+}
@@ -0,0 +1,203 @@
+use crate::backend::{
+    CONN, config::get_config, errors::ServerError, query::Query, user::AUTH_TABLE,
+};
+use axum::{
+    Json,
+    extract::Path,
+    http::{Method, Uri},
+};
+use ochat_types::user::{ApiToken, ApiTokenData, ApiTokenScope, NewApiToken};
+use serde_json::json;
+use surrealdb::opt::auth::Record;
+
+pub const API_TOKEN_PREFIX: &str = "ochat_";
+pub(crate) const API_TOKENS_TABLE: &str = "api_tokens";
+const API_ACCESS: &str = "api";
+
+pub async fn define_api_tokens() -> Result<(), ServerError> {
+    let _ = CONN
+        .query(&format!(
+            "
+DEFINE TABLE IF NOT EXISTS {0} SCHEMAFULL
+    PERMISSIONS FOR select, update, delete WHERE user_id = record::id($auth.id) FOR create FULL;
+DEFINE FIELD IF NOT EXISTS user_id ON TABLE {0} TYPE string DEFAULT ALWAYS record::id($auth.id);
+DEFINE FIELD IF NOT EXISTS name ON TABLE {0} TYPE string;
+DEFINE FIELD IF NOT EXISTS scope ON TABLE {0} TYPE string DEFAULT 'Full';
+DEFINE FIELD IF NOT EXISTS hash ON TABLE {0} TYPE string;
+DEFINE FIELD IF NOT EXISTS time ON TABLE {0} TYPE string DEFAULT <string>time::now();
+DEFINE FIELD IF NOT EXISTS expires ON TABLE {0} TYPE option<string>;
+DEFINE FIELD IF NOT EXISTS last_used ON TABLE {0} TYPE option<string>;
+DEFINE INDEX IF NOT EXISTS hash_index ON TABLE {0} COLUMNS hash UNIQUE;
+
+DEFINE ACCESS IF NOT EXISTS {1} ON DATABASE TYPE RECORD
+    SIGNIN ( SELECT * FROM type::thing('{2}', (SELECT VALUE user_id FROM ONLY {0} WHERE hash = crypto::sha256($token) AND (expires IS NONE OR <datetime>expires > time::now()) LIMIT 1)) )
+    DURATION FOR TOKEN 1h, FOR SESSION 1h
+;
+",
+            API_TOKENS_TABLE, API_ACCESS, AUTH_TABLE
+        ))
+        .await?;
+    Ok(())
+}
+
+pub async fn authenticate_api_token(token: &str) -> Result<ApiTokenScope, ServerError> {
+    let _ = CONN
+        .signin(Record {
+            namespace: &get_config().database.namespace,
+            database: &get_config().database.database,
+            access: API_ACCESS,
+            params: json!({ "token": token.trim() }),
+        })
+        .await
+        .map_err(|e| ServerError::Unauthorized(e.to_string()))?;
+
+    let mut tokens: Vec<ApiToken> = Query::new(
+        "UPDATE type::table($tb) SET last_used = <string>time::now() WHERE hash = crypto::sha256($token) RETURN AFTER;",
+    )
+    .table(API_TOKENS_TABLE)
+    .bind_id("token", token)
+    .take()
+    .await?;
+
+    match tokens.pop() {
+        Some(token) => Ok(token.scope),
+        None => Err(ServerError::Unauthorized(String::from(
+            "API token has been revoked.",
+        ))),
+    }
+}
+
+pub fn check_api_token_scope(
+    scope: &ApiTokenScope,
+    method: &Method,
+    uri: &Uri,
+) -> Result<(), ServerError> {
+    let generation = uri.path().starts_with("/generation/");
+
+    let allowed = match scope {
+        ApiTokenScope::Full => true,
+        ApiTokenScope::ReadOnly => method == Method::GET && !generation,
+        ApiTokenScope::Generation => generation,
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(ServerError::Forbidden(format!(
+            "This API token is limited to {} access.",
+            scope
+        )))
+    }
+}
+
+pub async fn create_api_token(
+    Json(data): Json<ApiTokenData>,
+) -> Result<Json<NewApiToken>, ServerError> {
+    let secret: Option<String> = Query::new("RETURN rand::string(48);").take().await?;
+    let Some(secret) = secret else {
+        return Err(ServerError::Unknown(String::from(
+            "Failed to generate an API token.",
+        )));
+    };
+    let token = format!("{}{}", API_TOKEN_PREFIX, secret);
+
+    let info: Option<ApiToken> = Query::new(
+        "CREATE ONLY type::table($tb) SET name = $name, scope = $scope, expires = $expires, hash = crypto::sha256($token);",
+    )
+    .table(API_TOKENS_TABLE)
+    .bind_str("name", &data.name)
+    .bind("scope", data.scope)
+    .bind("expires", data.expires)
+    .bind_id("token", &token)
+    .take()
+    .await?;
+
+    match info {
+        Some(info) => Ok(Json(NewApiToken { token, info })),
+        None => Err(ServerError::Unknown(String::from(
+            "Failed to save the API token.",
+        ))),
+    }
+}
+
+pub async fn list_all_api_tokens() -> Result<Json<Vec<ApiToken>>, ServerError> {
+    Ok(Json(CONN.select(API_TOKENS_TABLE).await?))
+}
+
+pub async fn revoke_api_token(id: Path<String>) -> Result<Json<Option<ApiToken>>, ServerError> {
+    Ok(Json(CONN.delete((API_TOKENS_TABLE, id.trim())).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(scope: ApiTokenScope, method: Method, path: &str) -> bool {
+        match check_api_token_scope(&scope, &method, &path.parse().unwrap()) {
+            Ok(()) => true,
+            Err(ServerError::Forbidden(_)) => false,
+            Err(e) => panic!("unexpected error : {}", e),
+        }
+    }
+
+    #[test]
+    fn full_tokens_reach_every_route() {
+        assert!(allowed(ApiTokenScope::Full, Method::GET, "/chat/all/"));
+        assert!(allowed(ApiTokenScope::Full, Method::DELETE, "/chat/id"));
+        assert!(allowed(
+            ApiTokenScope::Full,
+            Method::POST,
+            "/generation/text/job/"
+        ));
+    }
+
+    #[test]
+    fn read_only_tokens_only_read() {
+        assert!(allowed(ApiTokenScope::ReadOnly, Method::GET, "/chat/all/"));
+        assert!(allowed(
+            ApiTokenScope::ReadOnly,
+            Method::GET,
+            "/generationless/"
+        ));
+        assert!(!allowed(ApiTokenScope::ReadOnly, Method::POST, "/chat/"));
+        assert!(!allowed(
+            ApiTokenScope::ReadOnly,
+            Method::DELETE,
+            "/chat/id"
+        ));
+        assert!(!allowed(
+            ApiTokenScope::ReadOnly,
+            Method::GET,
+            "/generation/text/run/"
+        ));
+    }
+
+    #[test]
+    fn generation_tokens_only_generate() {
+        assert!(allowed(
+            ApiTokenScope::Generation,
+            Method::GET,
+            "/generation/text/run/?q=1"
+        ));
+        assert!(allowed(
+            ApiTokenScope::Generation,
+            Method::POST,
+            "/generation/text/job/"
+        ));
+        assert!(allowed(
+            ApiTokenScope::Generation,
+            Method::DELETE,
+            "/generation/text/job/id"
+        ));
+        assert!(!allowed(
+            ApiTokenScope::Generation,
+            Method::GET,
+            "/chat/all/"
+        ));
+        assert!(!allowed(
+            ApiTokenScope::Generation,
+            Method::GET,
+            "/generationless/"
+        ));
+    }
+}
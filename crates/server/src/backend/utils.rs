@@ -1,6 +1,6 @@
 use std::{env, fs};
 
-use crate::backend::{CONN, errors::ServerError};
+use crate::backend::{errors::ServerError, query::Query};
 
 pub fn get_path_settings(path: String) -> String {
     let mut new_path = env::var("XDG_CONFIG_HOME")
@@ -16,9 +16,13 @@ pub fn get_path_settings(path: String) -> String {
     return new_path;
 }
 
-pub async fn get_count(query: &str) -> Result<u8, ServerError> {
+pub async fn get_count(table: &str) -> Result<u8, ServerError> {
     let mut count: u8 = 0;
-    let query: Option<serde_json::Value> = CONN.query(query).await?.take(0)?;
+    let query: Option<serde_json::Value> =
+        Query::new("SELECT count() FROM type::table($tb) GROUP ALL;")
+            .table(table)
+            .take()
+            .await?;
 
     if let Some(mut query) = query {
         if query.is_array() {
@@ -0,0 +1,84 @@
+use crate::{settings::SettingsProvider, surreal::RecordId};
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct AssistantData {
+    #[builder(default = "None")]
+    pub user_id: Option<String>,
+    pub name: String,
+    #[builder(default = "None")]
+    pub avatar: Option<String>,
+    pub model: SettingsProvider,
+    #[builder(default = "None")]
+    pub system_prompt: Option<String>,
+    #[builder(default = "None")]
+    pub options: Option<String>,
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub starters: Vec<String>,
+}
+
+/// A reusable bundle of a model, system prompt, options and tools.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Assistant {
+    pub user_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    pub model: SettingsProvider,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Id of the generation options used instead of the model's defaults.
+    #[serde(default)]
+    pub options: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Messages offered to start a new chat with.
+    #[serde(default)]
+    pub starters: Vec<String>,
+    pub id: RecordId,
+}
+
+impl Assistant {
+    /// The name used to @mention the assistant in a message.
+    pub fn mention(&self) -> String {
+        self.name.trim().to_lowercase().replace(' ', "-")
+    }
+}
+
+impl Display for Assistant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.avatar {
+            Some(avatar) => write!(f, "{} {}", avatar, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl PartialEq for Assistant {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Into<AssistantData> for Assistant {
+    fn into(self) -> AssistantData {
+        AssistantData {
+            user_id: Some(self.user_id),
+            name: self.name,
+            avatar: self.avatar,
+            model: self.model,
+            system_prompt: self.system_prompt,
+            options: self.options,
+            tools: self.tools,
+            starters: self.starters,
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
-use crate::surreal::{Datetime, RecordId};
+use crate::{
+    settings::SettingsProvider,
+    surreal::{Datetime, RecordId},
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ChatData {
     #[builder(default = "None")]
     pub user_id: Option<String>,
@@ -11,21 +15,70 @@ pub struct ChatData {
     pub root: Option<String>,
     #[builder(default = "None")]
     pub time: Option<Datetime>,
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub models: Vec<SettingsProvider>,
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub tools: Vec<String>,
+    #[builder(default = "None")]
+    pub system_prompt: Option<String>,
+    #[builder(default = "None")]
+    pub options: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Chat {
     pub user_id: String,
     pub root: Option<String>,
     pub time: Datetime,
+    #[serde(default)]
+    pub models: Vec<SettingsProvider>,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Id of the generation options used instead of each model's defaults.
+    #[serde(default)]
+    pub options: Option<String>,
     pub id: RecordId,
 }
 
+/// The generation settings remembered by a chat.
+#[derive(Serialize, Deserialize, Clone, Debug, Builder, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ChatConfig {
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub models: Vec<SettingsProvider>,
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub tools: Vec<String>,
+    #[builder(default = "None")]
+    pub system_prompt: Option<String>,
+    #[builder(default = "None")]
+    pub options: Option<String>,
+}
+
+impl From<&Chat> for ChatConfig {
+    fn from(value: &Chat) -> Self {
+        Self {
+            models: value.models.clone(),
+            tools: value.tools.clone(),
+            system_prompt: value.system_prompt.clone(),
+            options: value.options.clone(),
+        }
+    }
+}
+
 pub mod messages {
     use super::*;
+    use crate::generation::text::{FunctionCall, GenerationMetrics};
     use std::fmt::Display;
 
     #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub enum Role {
         #[default]
         User,
@@ -50,18 +103,19 @@ pub mod messages {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct MessageData {
-        #[serde(default = "Default::default")]
+        #[serde(default)]
         #[builder(default = "String::new()")]
         pub content: String,
         #[builder(default = "None")]
         pub model: Option<ModelData>,
         #[builder(default = "None")]
         pub thinking: Option<String>,
-        #[serde(default = "Vec::new")]
+        #[serde(default)]
         #[builder(default = "Vec::new()")]
         pub files: Vec<String>,
-        #[serde(default = "Vec::new")]
+        #[serde(default)]
         #[builder(default = "Vec::new()")]
         pub children: Vec<String>,
         #[builder(default = "None")]
@@ -69,9 +123,36 @@ pub mod messages {
         #[serde(default = "Role::default")]
         #[builder(default = "Role::User")]
         pub role: Role,
+        #[serde(default)]
+        #[builder(default = "None")]
+        pub metrics: Option<GenerationMetrics>,
+        #[serde(default)]
+        #[builder(default = "None")]
+        pub rating: Option<MessageRating>,
+        #[serde(default)]
+        #[builder(default = "Vec::new()")]
+        pub func_calls: Vec<FunctionCall>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "lowercase")]
+    pub enum Rating {
+        Up,
+        Down,
+    }
+
+    /// A user's verdict on an AI message.
+    #[derive(Serialize, Deserialize, Clone, Debug, Builder, PartialEq)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct MessageRating {
+        pub rating: Rating,
+        #[builder(default = "None")]
+        pub comment: Option<String>,
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct ModelData {
         pub provider: String,
         pub model: String,
@@ -87,24 +168,57 @@ pub mod messages {
                 children: self.children,
                 time: Some(self.time),
                 role: self.role,
+                metrics: self.metrics,
+                rating: self.rating,
+                func_calls: self.func_calls,
             }
         }
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct Message {
         pub content: String,
-        #[serde(default = "Vec::new")]
+        #[serde(default)]
         pub files: Vec<String>,
-        #[serde(default = "Vec::new")]
+        #[serde(default)]
         pub children: Vec<String>,
         pub model: Option<ModelData>,
         pub thinking: Option<String>,
         pub role: Role,
         pub time: Datetime,
+        #[serde(default)]
+        pub metrics: Option<GenerationMetrics>,
+        #[serde(default)]
+        pub rating: Option<MessageRating>,
+        /// The tool calls made while answering, set on `Role::Function` messages.
+        #[serde(default)]
+        pub func_calls: Vec<FunctionCall>,
         pub id: RecordId,
     }
 
+    /// Byte range of a matched term inside a search snippet.
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct SearchHighlight {
+        pub start: usize,
+        pub end: usize,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct MessageSearchResult {
+        pub message: String,
+        pub chat: String,
+        pub role: Role,
+        pub snippet: String,
+        #[serde(default)]
+        pub highlights: Vec<SearchHighlight>,
+        /// Whether the match is in the thinking rather than the content.
+        #[serde(default)]
+        pub thinking: bool,
+    }
+
     impl PartialEq for Message {
         fn eq(&self, other: &Self) -> bool {
             self.id == other.id
@@ -116,6 +230,7 @@ pub mod previews {
     use super::*;
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct Preview {
         pub user_id: String,
         pub text: String,
@@ -124,9 +239,247 @@ pub mod previews {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct PreviewData {
         pub user_id: Option<String>,
         pub text: String,
         pub time: Datetime,
     }
 }
+
+pub mod export {
+    use super::{
+        messages::{Message, Role},
+        *,
+    };
+    use crate::files::B64File;
+    use std::{fmt::Display, str::FromStr};
+
+    pub const CHAT_EXPORT_VERSION: u32 = 1;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "lowercase")]
+    pub enum ExportFormat {
+        #[default]
+        Json,
+        Markdown,
+        Html,
+    }
+
+    impl ExportFormat {
+        pub const ALL: [Self; 3] = [Self::Json, Self::Markdown, Self::Html];
+
+        pub fn extension(&self) -> &'static str {
+            match self {
+                Self::Json => "json",
+                Self::Markdown => "md",
+                Self::Html => "html",
+            }
+        }
+    }
+
+    impl Display for ExportFormat {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Self::Json => "json",
+                    Self::Markdown => "markdown",
+                    Self::Html => "html",
+                }
+            )
+        }
+    }
+
+    impl FromStr for ExportFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.trim().to_lowercase().as_str() {
+                "json" => Ok(Self::Json),
+                "markdown" | "md" => Ok(Self::Markdown),
+                "html" | "htm" => Ok(Self::Html),
+                x => Err(format!("Unknown export format '{}'", x)),
+            }
+        }
+    }
+
+    /// The fine-tuning datasets that can be built from rated messages.
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "lowercase")]
+    pub enum DatasetFormat {
+        /// A `{prompt, chosen, rejected}` line per liked and disliked pair of regenerations.
+        #[default]
+        Dpo,
+        /// A `{messages}` line per liked conversation.
+        Sft,
+    }
+
+    impl DatasetFormat {
+        pub const ALL: [Self; 2] = [Self::Dpo, Self::Sft];
+    }
+
+    impl Display for DatasetFormat {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Self::Dpo => "dpo",
+                    Self::Sft => "sft",
+                }
+            )
+        }
+    }
+
+    impl FromStr for DatasetFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.trim().to_lowercase().as_str() {
+                "dpo" => Ok(Self::Dpo),
+                "sft" => Ok(Self::Sft),
+                x => Err(format!("Unknown dataset format '{}'", x)),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct DatasetMessage {
+        pub role: String,
+        pub content: String,
+    }
+
+    impl From<&Message> for DatasetMessage {
+        fn from(value: &Message) -> Self {
+            Self {
+                role: String::from(match value.role {
+                    Role::User => "user",
+                    Role::AI => "assistant",
+                    Role::Function => "tool",
+                    Role::System => "system",
+                }),
+                content: value.content.clone(),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct DpoRecord {
+        pub prompt: Vec<DatasetMessage>,
+        pub chosen: Vec<DatasetMessage>,
+        pub rejected: Vec<DatasetMessage>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct SftRecord {
+        pub messages: Vec<DatasetMessage>,
+    }
+
+    /// Every message of a chat, including all branches, with the files they reference.
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct ChatExport {
+        pub version: u32,
+        pub chat: Chat,
+        #[serde(default)]
+        pub title: Option<String>,
+        #[serde(default)]
+        pub messages: Vec<Message>,
+        #[serde(default)]
+        pub files: Vec<B64File>,
+    }
+}
+
+pub mod import {
+    use super::*;
+    use serde_json::Value;
+    use std::{fmt::Display, str::FromStr};
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    #[serde(rename_all = "lowercase")]
+    pub enum ImportFormat {
+        /// Detects the format of every chat on its own.
+        #[default]
+        Auto,
+        ChatGPT,
+        OpenWebUI,
+        Ochat,
+    }
+
+    impl ImportFormat {
+        pub const ALL: [Self; 4] = [Self::Auto, Self::ChatGPT, Self::OpenWebUI, Self::Ochat];
+    }
+
+    impl Display for ImportFormat {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Self::Auto => "auto",
+                    Self::ChatGPT => "chatgpt",
+                    Self::OpenWebUI => "openwebui",
+                    Self::Ochat => "ochat",
+                }
+            )
+        }
+    }
+
+    impl FromStr for ImportFormat {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s
+                .trim()
+                .to_lowercase()
+                .replace([' ', '-', '_'], "")
+                .as_str()
+            {
+                "auto" => Ok(Self::Auto),
+                "chatgpt" | "openai" => Ok(Self::ChatGPT),
+                "openwebui" | "webui" => Ok(Self::OpenWebUI),
+                "ochat" => Ok(Self::Ochat),
+                x => Err(format!("Unknown import format '{}'", x)),
+            }
+        }
+    }
+
+    /// A single chat or a list of chats as exported by one of the supported apps.
+    #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct ImportData {
+        #[serde(default)]
+        #[builder(default = "ImportFormat::Auto")]
+        pub format: ImportFormat,
+        /// Name of the folder to file the imported chats into, created if missing.
+        #[serde(default)]
+        #[builder(default = "None")]
+        pub folder: Option<String>,
+        pub data: Value,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct ImportedChat {
+        pub title: Option<String>,
+        pub chat: Option<Chat>,
+        pub error: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+    pub struct ImportResult {
+        pub imported: usize,
+        pub failed: usize,
+        #[serde(default)]
+        pub chats: Vec<ImportedChat>,
+    }
+}
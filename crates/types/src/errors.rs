@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Display;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    Unauthorized,
+    Forbidden,
+    Validation,
+    Upstream,
+    Conflict,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::NotFound => 404,
+            Self::Unauthorized => 401,
+            Self::Forbidden => 403,
+            Self::Validation => 400,
+            Self::Upstream => 502,
+            Self::Conflict => 409,
+            Self::Internal => 500,
+        }
+    }
+
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            404 => Self::NotFound,
+            401 => Self::Unauthorized,
+            403 => Self::Forbidden,
+            400 | 413 | 415 | 422 => Self::Validation,
+            502 | 503 | 504 => Self::Upstream,
+            409 => Self::Conflict,
+            _ => Self::Internal,
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::NotFound => "Not Found",
+                Self::Unauthorized => "Unauthorized",
+                Self::Forbidden => "Forbidden",
+                Self::Validation => "Validation",
+                Self::Upstream => "Upstream",
+                Self::Conflict => "Conflict",
+                Self::Internal => "Internal",
+            }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<Value>,
+}
+
+impl ErrorBody {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+}
+
+impl Display for ErrorBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} Error : {}", self.code, self.message)
+    }
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::surreal::RecordId;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct B64FileData {
     #[builder(default = "None")]
     pub user_id: Option<String>,
@@ -17,6 +18,7 @@ pub struct B64FileData {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum FileType {
     #[default]
     Image,
@@ -41,6 +43,7 @@ impl Display for FileType {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct DBFile {
     pub user_id: String,
     pub path: String,
@@ -50,6 +53,7 @@ pub struct DBFile {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct B64File {
     pub user_id: String,
     pub b64data: String,
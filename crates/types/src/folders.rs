@@ -4,11 +4,12 @@ use serde::{Deserialize, Serialize};
 use crate::surreal::RecordId;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FolderData {
     #[builder(default = "None")]
     pub user_id: Option<String>,
     #[builder(default = "Vec::new()")]
-    #[serde(default = "Vec::new")]
+    #[serde(default)]
     pub chats: Vec<String>,
     #[builder(default = "None")]
     pub parent: Option<String>,
@@ -18,6 +19,7 @@ pub struct FolderData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FolderNameData {
     pub name: String,
 }
@@ -27,9 +29,10 @@ fn new_name() -> String {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Folder {
     pub user_id: String,
-    #[serde(default = "Vec::new")]
+    #[serde(default)]
     pub chats: Vec<String>,
     pub parent: Option<String>,
     pub name: String,
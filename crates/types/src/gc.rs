@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// What a garbage collection sweep removed, or would remove when it is a dry run.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub messages: usize,
+    pub files: usize,
+    pub previews: usize,
+    /// Trashed chats, folders and prompts past the retention period.
+    pub trash: usize,
+    /// Files in the uploads folder that no file record points at.
+    pub uploads: usize,
+    /// Disk space taken by the removed files and uploads.
+    pub bytes: u64,
+}
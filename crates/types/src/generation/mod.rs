@@ -5,6 +5,7 @@ pub mod text;
 pub mod tts;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SoundSpec {
     pub sample_rate: u32,
     // pub bits_per_sample: u16,
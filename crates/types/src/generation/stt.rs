@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use crate::{generation::SoundSpec, settings::SettingsProvider};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SttQueryData {
     #[builder(default = "None")]
     pub model: Option<SettingsProvider>,
@@ -12,6 +13,7 @@ pub struct SttQueryData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SttResponse {
     pub text: String,
 }
@@ -1,27 +1,48 @@
-use crate::chats::messages::{Message, Role};
+use crate::{
+    chats::messages::{Message, Role},
+    options::{GenOption, GenOptionKey},
+    settings::ContextStrategy,
+    surreal::Datetime,
+};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ChatStreamResult {
     Idle,
     Err(String),
     Generating(ChatResponse),
     Generated(ChatResponse),
+    /// A tool the model asked for started running, its result is still empty.
+    ToolCall(FunctionCall),
+    ToolResult(FunctionCall),
+    /// Sources the tool results were retrieved from.
+    Citations(Vec<Citation>),
+    Status(String),
     Finished,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ChatQueryMessage {
     pub text: String,
-    #[serde(default = "Vec::new")]
+    #[serde(default)]
     #[builder(default = "Vec::new()")]
     pub files: Vec<String>,
     #[serde(default = "Role::default")]
     #[builder(default = "Role::User")]
     pub role: Role,
+    /// The stored message this was built from, lets the server cache summaries on it.
+    #[serde(default)]
+    #[builder(default = "None")]
+    pub id: Option<String>,
+    /// The tool calls and results a `Role::Function` message replays.
+    #[serde(default)]
+    #[builder(default = "Vec::new()")]
+    pub func_calls: Vec<FunctionCall>,
 }
 
 impl From<Message> for ChatQueryMessage {
@@ -30,6 +51,8 @@ impl From<Message> for ChatQueryMessage {
             text: value.content,
             files: value.files,
             role: value.role,
+            id: Some(value.id.key().to_string()),
+            func_calls: value.func_calls,
         }
     }
 }
@@ -61,19 +84,93 @@ pub fn split_text_into_thinking(text: String) -> (String, Option<String>) {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct FunctionCall {
+    /// The provider's id for the call, needed to match the result to it on later turns.
+    #[serde(default)]
+    pub id: Option<String>,
     pub name: String,
     pub result: Option<Value>,
     pub args: HashMap<String, Value>,
 }
 
+impl FunctionCall {
+    /// The result as the text it is handed back to the model with.
+    pub fn result_text(&self) -> String {
+        match &self.result {
+            Some(Value::String(x)) => x.clone(),
+            Some(x) => x.to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// What the call is doing, in a form that can be shown while it runs.
+    pub fn describe(&self) -> String {
+        let arg = |x: &str| self.args.get(x).and_then(|x| x.as_str());
+
+        match (self.name.as_str(), arg("query"), arg("url")) {
+            ("web_search", Some(query), _) => format!("Searching the web for \"{}\"", query),
+            ("web_scraper", _, Some(url)) => format!("Reading {}", url),
+            _ => format!("Calling {}", self.name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Citation {
+    pub url: String,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// How a response was generated, as measured by the server.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GenerationMetrics {
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    /// Milliseconds until the first chunk arrived, only known when streaming.
+    pub time_to_first_token: Option<u64>,
+    /// Milliseconds the whole generation took.
+    #[serde(default)]
+    pub duration: u64,
+    pub tokens_per_second: Option<f64>,
+    pub finish_reason: Option<String>,
+    /// The activated generation options that were applied.
+    #[serde(default)]
+    pub options: Vec<GenOption>,
+    /// Activated options the backend has no parameter for, so they were left out.
+    #[serde(default)]
+    pub unsupported_options: Vec<GenOptionKey>,
+    pub context: Option<ContextTrim>,
+}
+
+/// Set when the history had to be shortened to fit the model's context window.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ContextTrim {
+    pub strategy: ContextStrategy,
+    /// How many messages were dropped or folded into the summary.
+    pub trimmed: usize,
+    /// Id of the oldest message that was still sent as is.
+    pub first_kept: Option<String>,
+    pub context_window: u64,
+    /// Estimated size of the history that was sent.
+    pub prompt_tokens: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ChatResponse {
     pub role: Role,
     pub content: String,
     pub thinking: Option<String>,
-    #[serde(default = "Vec::new")]
+    #[serde(default)]
     pub func_calls: Vec<FunctionCall>,
+    /// Only set on the final response.
+    #[serde(default)]
+    pub metrics: Option<GenerationMetrics>,
 }
 
 impl Default for ChatResponse {
@@ -83,6 +180,7 @@ impl Default for ChatResponse {
             content: String::new(),
             thinking: None,
             func_calls: Vec::new(),
+            metrics: None,
         }
     }
 }
@@ -98,16 +196,40 @@ impl Into<ChatQueryMessage> for ChatResponse {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ChatQueryData {
     pub provider: String,
     pub model: String,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     #[builder(default = "Default::default()")]
     pub force_disable_tools: bool,
-    #[serde(default = "Vec::new")]
+    #[serde(default)]
     #[builder(default = "Vec::new()")]
     pub tools: Vec<String>,
-    #[serde(default = "Vec::new")]
+    #[serde(default)]
     #[builder(default = "Vec::new()")]
     pub messages: Vec<ChatQueryMessage>,
+    /// Id of the generation options to use instead of the model's defaults.
+    #[serde(default)]
+    #[builder(default = "None")]
+    pub options: Option<String>,
+}
+
+/// Starts a server side generation that writes its output into `message`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TextJobData {
+    pub message: String,
+    pub query: ChatQueryData,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TextJob {
+    #[serde(default)]
+    pub user_id: String,
+    pub message: String,
+    pub provider: String,
+    pub model: String,
+    pub started: Datetime,
 }
@@ -3,6 +3,7 @@ use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TtsQueryData {
     #[builder(default = "None")]
     pub model: Option<SettingsProvider>,
@@ -10,6 +11,7 @@ pub struct TtsQueryData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TtsResponse {
     pub spec: SoundSpec,
     pub data: Vec<f32>,
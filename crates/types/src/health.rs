@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    #[default]
+    Ok,
+    Degraded,
+    Unavailable,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HealthCheck {
+    pub name: String,
+    pub state: HealthState,
+    /// Whether a failure of this check makes the server unready.
+    pub required: bool,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HealthStatus {
+    pub state: HealthState,
+    pub version: String,
+    pub uptime: u64,
+    #[serde(default)]
+    pub checks: Vec<HealthCheck>,
+}
@@ -1,15 +1,20 @@
 use serde::{Deserialize, Serialize};
 
+pub mod assistants;
 pub mod chats;
+pub mod errors;
 pub mod files;
 pub mod folders;
+pub mod gc;
 pub mod generation;
+pub mod health;
 pub mod options;
 pub mod prompts;
 pub mod providers;
 pub mod settings;
 pub mod surreal;
 pub mod tools;
+pub mod trash;
 pub mod user;
 pub mod version;
 
@@ -23,6 +28,7 @@ pub const WORD_ART: &str = r"
 ";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ServerFeatures {
     Sound,
     Python,
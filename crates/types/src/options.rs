@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::{fmt::Display, ops::Deref};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum GenOptionKey {
     Mirostat,
     MirostatETA,
@@ -89,7 +90,7 @@ impl GenOptionKey {
             Self::RepeatPenalty => "Sets how strongly to penalize repetitions. A higher value (e.g., 1.5) will penalize repetitions more strongly, while a lower value (e.g., 0.9) will be more lenient.",
             Self::Temperature => "The temperature of the model. Increasing the temperature will make the model answer more creatively.",
             Self::Seed => "Sets the random number seed to use for generation. Setting this to a specific number will make the model generate the same text for the same prompt.",
-            Self::StopSequence => "Stop Sequence",
+            Self::StopSequence => "Text that ends the answer as soon as the model generates it. Separate multiple sequences with commas.",
             Self::TailFreeZ => "Tail free sampling is used to reduce the impact of less probable tokens from the output. A higher value (e.g., 2.0) will reduce the impact more, while a value of 1.0 disables this setting.",
             Self::NumberPredict => "Maximum number of tokens to predict when generating text. (Default: 128, -1 = infinite generation, -2 = fill context)",
             Self::TopK => "Reduces the probability of generating nonsense. A higher value (e.g. 100) will give more diverse answers, while a lower value (e.g. 10) will be more conservative.",
@@ -100,6 +101,7 @@ impl GenOptionKey {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct GenOption {
     pub key: GenOptionKey,
     pub activated: bool,
@@ -140,6 +142,7 @@ impl GenOption {
     }
 }
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum GenOptionValue {
     Float(f32),
     Text(String),
@@ -188,7 +191,7 @@ impl From<GenOptionKey> for GenOption {
             GenOptionKey::RepeatPenalty => Self::new(value, GenOptionValue::Float(1.1)),
             GenOptionKey::Temperature => Self::new(value, GenOptionValue::Float(0.8)),
             GenOptionKey::Seed => Self::new(value, GenOptionValue::Float(0.0)),
-            GenOptionKey::StopSequence => Self::new(value, GenOptionValue::Float(0.0)),
+            GenOptionKey::StopSequence => Self::new(value, GenOptionValue::Text(String::new())),
             GenOptionKey::TailFreeZ => Self::new(value, GenOptionValue::Float(1.0)),
             GenOptionKey::NumberPredict => Self::new(value, GenOptionValue::Int(128)),
             GenOptionKey::TopK => Self::new(value, GenOptionValue::Float(40.0)),
@@ -198,6 +201,7 @@ impl From<GenOptionKey> for GenOption {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct GenOptionsData {
     #[builder(default = "None")]
     pub user_id: Option<String>,
@@ -228,6 +232,7 @@ impl From<GenOptions> for GenOptionsData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct GenOptions {
     pub user_id: String,
     pub name: String,
@@ -247,6 +252,7 @@ pub mod relationships {
     use super::*;
 
     #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct GenModelRelationshipData {
         #[builder(default = "None")]
         pub user_id: Option<String>,
@@ -262,6 +268,7 @@ pub mod relationships {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct GenModelRelationship {
         pub user_id: String,
         pub provider: String,
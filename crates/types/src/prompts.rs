@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PromptData {
     #[builder(default = "None")]
     pub user_id: Option<String>,
@@ -42,6 +43,7 @@ impl Into<PromptData> for Prompt {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Prompt {
     pub user_id: String,
     pub command: String,
@@ -55,9 +57,10 @@ pub struct Prompt {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OpenWebUIUser {
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     pub username: String,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     pub verified: bool,
 }
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use crate::surreal::RecordId;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ProviderType {
     OpenAI,
     Gemini,
@@ -24,6 +25,7 @@ impl Display for ProviderType {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ProviderData {
     pub name: String,
     pub url: String,
@@ -33,6 +35,7 @@ pub struct ProviderData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Provider {
     pub name: String,
     pub url: String,
@@ -48,6 +51,7 @@ impl Display for Provider {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Model {
     pub id: String,
     pub object: Option<String>,
@@ -59,8 +63,9 @@ pub mod ollama {
     use super::*;
 
     #[derive(Serialize, Deserialize, Debug, Clone)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct OllamaModelsInfo {
-        #[serde(default = "String::new")]
+        #[serde(default)]
         pub name: String,
         pub url: String,
         pub tags: Vec<Vec<String>>,
@@ -71,6 +76,7 @@ pub mod ollama {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub enum OllamaPullModelStreamResult {
         Idle,
         Err(String),
@@ -79,6 +85,7 @@ pub mod ollama {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct OllamaPullModelResponse {
         pub status: String,
         pub digest: Option<String>,
@@ -96,11 +103,13 @@ pub mod hf {
     use super::*;
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct DownloadedHFModels {
         pub variants: Vec<HFModelVariant>,
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct HFModel {
         pub id: String,
         #[serde(alias = "lastModified")]
@@ -110,10 +119,11 @@ pub mod hf {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct HFModelDetails {
-        #[serde(default = "Default::default")]
+        #[serde(default)]
         pub id: String,
-        #[serde(default = "Default::default")]
+        #[serde(default)]
         pub description: String,
         #[serde(alias = "lastModified")]
         pub last_modified: Datetime,
@@ -123,17 +133,18 @@ pub mod hf {
         pub pipeline_tag: Option<String>,
         pub architecture: Option<String>,
         #[serde(alias = "cardData")]
-        #[serde(default = "Default::default")]
+        #[serde(default)]
         pub card_data: CardData,
-        #[serde(default = "Default::default")]
+        #[serde(default)]
         pub parameters: u64,
-        #[serde(default = "Default::default")]
+        #[serde(default)]
         pub variants: HFModelVariants,
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct CardData {
-        #[serde(default = "Default::default")]
+        #[serde(default)]
         base_model: Option<Value>,
     }
 
@@ -155,6 +166,7 @@ pub mod hf {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct HFModelVariant {
         pub model: String,
         pub name: String,
@@ -164,6 +176,7 @@ pub mod hf {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub enum ModelType {
         Text,
         Stt,
@@ -177,9 +190,11 @@ pub mod hf {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct HFModelVariants(pub HashMap<u64, Vec<HFModelVariant>>);
 
     #[derive(Serialize, Deserialize, Clone, Debug)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub enum HFPullModelStreamResult {
         Idle,
         Err(String),
@@ -188,6 +203,7 @@ pub mod hf {
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+    #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
     pub struct HFPullModelResponse {
         pub total: Option<u64>,
         pub completed: Option<u64>,
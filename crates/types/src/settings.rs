@@ -5,6 +5,7 @@ use std::{env, fmt::Display, fs, path::PathBuf, str::FromStr};
 use crate::surreal::RecordId;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SettingsData {
     #[builder(default = "None")]
     pub previews_provider: Option<SettingsProvider>,
@@ -16,9 +17,48 @@ pub struct SettingsData {
     pub use_llama_cpp: Option<bool>,
     #[builder(default = "None")]
     pub hf_token: Option<String>,
+    #[builder(default = "None")]
+    pub open_signup: Option<bool>,
+    #[builder(default = "None")]
+    pub context_strategy: Option<ContextStrategy>,
+    /// Used when the model's own context length is unknown, `0` clears it.
+    #[builder(default = "None")]
+    pub context_window: Option<u32>,
+}
+
+/// What the server does with the oldest messages once a chat outgrows the model's context.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ContextStrategy {
+    #[default]
+    DropOldest,
+    /// Folds the oldest messages into a summary that is cached on the last message it covers.
+    Summarize,
+    /// Sends everything and leaves it to the provider.
+    Off,
+}
+
+impl ContextStrategy {
+    pub const ALL: [Self; 3] = [Self::DropOldest, Self::Summarize, Self::Off];
+}
+
+impl Display for ContextStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::DropOldest => "drop oldest",
+                Self::Summarize => "summarize",
+                Self::Off => "off",
+            }
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SettingsProvider {
     pub provider: String,
     pub model: String,
@@ -61,6 +101,9 @@ impl Into<SettingsData> for Settings {
             use_llama_cpp: Some(self.use_llama_cpp),
             models_path: Some(self.models_path),
             hf_token: self.hf_token,
+            open_signup: Some(self.open_signup),
+            context_strategy: Some(self.context_strategy),
+            context_window: self.context_window,
         }
     }
 }
@@ -83,15 +126,26 @@ fn get_models_path() -> PathBuf {
     PathBuf::from_str(&get_path_local("models/".to_string())).unwrap()
 }
 
+fn get_open_signup() -> bool {
+    true
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Settings {
     pub previews_provider: Option<SettingsProvider>,
     pub embeddings_provider: Option<SettingsProvider>,
     #[serde(default = "get_models_path")]
     pub models_path: PathBuf,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     pub use_llama_cpp: bool,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     pub hf_token: Option<String>,
+    #[serde(default = "get_open_signup")]
+    pub open_signup: bool,
+    #[serde(default)]
+    pub context_strategy: ContextStrategy,
+    #[serde(default)]
+    pub context_window: Option<u32>,
     pub id: RecordId,
 }
@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RecordId {
     pub tb: String,
     pub id: RecordIdKey,
@@ -26,6 +27,7 @@ impl Display for RecordId {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct RecordIdOnly(RecordId);
 
 impl Deref for RecordIdOnly {
@@ -36,6 +38,7 @@ impl Deref for RecordIdOnly {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[allow(non_snake_case)]
 pub struct RecordIdKey(Id);
 
@@ -70,6 +73,7 @@ impl From<(String, String)> for RecordId {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Id {
     String(String),
 }
@@ -89,6 +93,7 @@ impl Display for RecordIdKey {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Datetime(pub DateTime<Utc>);
 
 impl Default for Datetime {
@@ -96,3 +101,9 @@ impl Default for Datetime {
         Self(Utc::now())
     }
 }
+
+impl Datetime {
+    pub fn from_timestamp(secs: f64) -> Option<Self> {
+        DateTime::from_timestamp_millis((secs * 1000.0) as i64).map(Self)
+    }
+}
@@ -6,14 +6,15 @@ use std::{collections::HashMap, fmt::Display};
 use crate::{prompts::OpenWebUIUser, surreal::RecordId};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ToolData {
     #[builder(default = "None")]
     pub user_id: Option<String>,
     pub name: String,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     #[builder(default = "Default::default()")]
     pub tool_type: ToolType,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     #[builder(default = "Default::default()")]
     pub tools: Vec<ToolInformation>,
     pub content: String,
@@ -21,8 +22,9 @@ pub struct ToolData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ToolInformation {
-    #[serde(default = "Vec::new")]
+    #[serde(default)]
     #[builder(default = "Vec::new()")]
     pub name: Vec<String>,
     #[builder(default = "None")]
@@ -32,6 +34,7 @@ pub struct ToolInformation {
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ToolType {
     Python,
     #[default]
@@ -45,6 +48,7 @@ impl ToolType {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ToolParameters {
     pub r#type: String,
     pub properties: HashMap<String, ToolParameter>,
@@ -52,6 +56,7 @@ pub struct ToolParameters {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ToolDataType {
     #[default]
     #[serde(rename = "string")]
@@ -97,6 +102,7 @@ impl ToolDataType {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ToolParameter {
     pub r#type: ToolDataType,
     #[serde(rename = "enum")]
@@ -105,12 +111,13 @@ pub struct ToolParameter {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Tool {
     pub user_id: String,
     pub name: String,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     pub tool_type: ToolType,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     pub tools: Vec<ToolInformation>,
     pub content: String,
     pub user: Option<OpenWebUIUser>,
@@ -118,4 +125,5 @@ pub struct Tool {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OpenWebUITool {}
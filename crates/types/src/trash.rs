@@ -0,0 +1,53 @@
+use crate::surreal::Datetime;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum TrashKind {
+    Chat,
+    Folder,
+    Prompt,
+}
+
+impl TrashKind {
+    pub const ALL: [Self; 3] = [Self::Chat, Self::Folder, Self::Prompt];
+}
+
+impl Display for TrashKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Chat => "chat",
+                Self::Folder => "folder",
+                Self::Prompt => "prompt",
+            }
+        )
+    }
+}
+
+impl FromStr for TrashKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "chat" => Ok(Self::Chat),
+            "folder" => Ok(Self::Folder),
+            "prompt" => Ok(Self::Prompt),
+            x => Err(format!("Unknown trash kind '{}'", x)),
+        }
+    }
+}
+
+/// A soft deleted chat, folder or prompt waiting to be restored or purged.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub id: String,
+    pub name: String,
+    pub deleted_at: Datetime,
+}
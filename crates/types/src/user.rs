@@ -2,9 +2,10 @@ use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-use crate::surreal::RecordId;
+use crate::surreal::{Datetime, RecordId};
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Role {
     #[default]
     User,
@@ -12,6 +13,7 @@ pub enum Role {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Gender {
     Male,
     Female,
@@ -33,6 +35,7 @@ impl Display for Gender {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SignupData {
     pub name: String,
     pub email: String,
@@ -40,6 +43,7 @@ pub struct SignupData {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Token {
     pub token: String,
 }
@@ -51,22 +55,109 @@ impl Token {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct User {
     pub name: String,
     pub email: String,
-    #[serde(default = "String::new")]
+    #[serde(default)]
     #[builder(default = "String::new()")]
     pub bio: String,
     #[builder(default = "None")]
     pub gender: Option<Gender>,
-    #[serde(default = "Default::default")]
+    #[serde(default)]
     #[builder(default = "Default::default()")]
     pub role: Role,
+    #[serde(default = "get_enabled")]
+    #[builder(default = "true")]
+    pub enabled: bool,
     pub id: RecordId,
 }
 
+fn get_enabled() -> bool {
+    true
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SigninData {
     pub name: String,
     pub password: String,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub enum ApiTokenScope {
+    #[default]
+    Full,
+    ReadOnly,
+    Generation,
+}
+
+impl Display for ApiTokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Full => "full",
+                Self::ReadOnly => "read-only",
+                Self::Generation => "generation",
+            }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ApiTokenData {
+    pub name: String,
+    #[serde(default)]
+    #[builder(default = "Default::default()")]
+    pub scope: ApiTokenScope,
+    #[builder(default = "None")]
+    pub expires: Option<Datetime>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ApiToken {
+    pub user_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub scope: ApiTokenScope,
+    pub time: Datetime,
+    #[serde(default)]
+    pub expires: Option<Datetime>,
+    #[serde(default)]
+    pub last_used: Option<Datetime>,
+    pub id: RecordId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NewApiToken {
+    pub token: String,
+    pub info: ApiToken,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Session {
+    pub user_id: String,
+    #[serde(default)]
+    pub device: Option<String>,
+    pub time: Datetime,
+    #[serde(default)]
+    pub last_used: Option<Datetime>,
+    pub expires: Datetime,
+    #[serde(default)]
+    pub current: bool,
+    pub id: RecordId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Builder, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ChangePasswordData {
+    pub old_password: String,
+    pub new_password: String,
+}